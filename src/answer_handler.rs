@@ -1,9 +1,17 @@
-use crate::SharedTaskState;
-use crate::TaskState;
+use crate::calendar;
+use crate::chores::{self, ChoreAction, ChoreStates};
+use crate::clock::Clock;
+use crate::config::ConfigHandle;
+use crate::data_grabber::we_recycle::{self, Regions, WeRecycleWasteGrabber};
 use crate::email;
-use chrono::{Datelike, TimeZone};
+use crate::metrics;
+use crate::roster;
+use crate::stats::{self, DutyOutcome};
+use crate::swap::{self, SwapAnswer};
+use crate::{Config, SharedTaskState, TaskState};
+use chrono::NaiveDate;
 use teloxide::prelude::*;
-use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, MessageId};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, InputFile, MessageId};
 
 /// This function is called when the "Done" button is pressed
 async fn done_handler(
@@ -11,27 +19,27 @@ async fn done_handler(
     chat_id: ChatId,
     message_id: MessageId,
     task_state: &std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
+    now: chrono::DateTime<chrono_tz::Tz>,
 ) -> ResponseResult<()> {
     let _ = bot
         .edit_message_text(chat_id, message_id, "Thank you! Have a nice evening. <3")
         .await?;
     let mut task_state = task_state.lock().unwrap();
     if task_state.state == TaskState::Pending {
+        stats::record(
+            now,
+            chat_id.0,
+            stats::TRASH_CHORE,
+            &task_state.reminder_trashes(now),
+            DutyOutcome::Done,
+        );
+        metrics::answered("done");
+        // A settled reminder cancels the escalation steps still ahead today.
         task_state.state = TaskState::None;
-        let tomorrow = chrono::Local::now() + chrono::Duration::days(1);
-        let tomorrow_evening = chrono::Local
-            .with_ymd_and_hms(
-                tomorrow.year(),
-                tomorrow.month(),
-                tomorrow.day(),
-                18,
-                00,
-                00,
-            )
-            .unwrap();
-        task_state.next_trigger = tomorrow_evening;
-    }
-    return Ok(());
+        task_state.message_id = None;
+        task_state.persist();
+    }
+    Ok(())
 }
 
 async fn cant_handler(
@@ -39,6 +47,8 @@ async fn cant_handler(
     chat_id: ChatId,
     message_id: MessageId,
     task_state: &std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
+    wakeup: &tokio::sync::Notify,
+    now: chrono::DateTime<chrono_tz::Tz>,
 ) -> ResponseResult<()> {
     let _ = bot
         .edit_message_text(
@@ -51,10 +61,261 @@ async fn cant_handler(
     if task_state.state != TaskState::Pending {
         return Ok(());
     }
+    stats::record(
+        now,
+        chat_id.0,
+        stats::TRASH_CHORE,
+        &task_state.reminder_trashes(now),
+        DutyOutcome::Cant,
+    );
+    metrics::answered("cant");
     task_state.state = TaskState::Failed;
-    // set next trigger to now
-    task_state.next_trigger = chrono::Local::now();
-    return Ok(());
+    task_state.message_id = None;
+    task_state.persist();
+    // The scheduler asks the group for help as soon as it wakes up.
+    wakeup.notify_one();
+    Ok(())
+}
+
+/// Called when the Done or I can't button of a chore reminder is pressed.
+#[allow(clippy::too_many_arguments)]
+async fn chore_handler(
+    bot: &Bot,
+    chat_id: ChatId,
+    message_id: MessageId,
+    chore_id: &str,
+    action: ChoreAction,
+    config: &Config,
+    chore_states: &std::sync::Arc<std::sync::Mutex<ChoreStates>>,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) -> ResponseResult<()> {
+    let Some(chore) = config.chores.iter().find(|chore| chore.id == chore_id) else {
+        bot.edit_message_text(chat_id, message_id, "This chore doesn't exist anymore.")
+            .await?;
+        return Ok(());
+    };
+    let reply = match action {
+        ChoreAction::Done => "Thank you! See you next time. <3",
+        ChoreAction::Cant => "No problem. I will ask the others to help.",
+    };
+    bot.edit_message_text(chat_id, message_id, reply).await?;
+
+    let mut chore_states = chore_states.lock().unwrap();
+    if let Some(state) = chore_states.get_mut(chore_id) {
+        let changed = match action {
+            ChoreAction::Done => state.mark_done(chore, now),
+            ChoreAction::Cant => state.mark_failed(now),
+        };
+        if changed {
+            let outcome = match action {
+                ChoreAction::Done => DutyOutcome::Done,
+                ChoreAction::Cant => DutyOutcome::Cant,
+            };
+            stats::record(now, chat_id.0, chore_id, &[], outcome);
+            chores::persist(chore_id, state);
+        }
+    }
+    Ok(())
+}
+
+/// Called when a flatmate answers an offer to take over someone's week.
+async fn swap_answer_handler(
+    bot: &Bot,
+    chat_id: ChatId,
+    message_id: MessageId,
+    swap_id: i64,
+    answer: SwapAnswer,
+    config: &Config,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) -> ResponseResult<()> {
+    if answer == SwapAnswer::Decline {
+        bot.edit_message_text(chat_id, message_id, "No worries, maybe next time.")
+            .await?;
+        return Ok(());
+    }
+    let request = match crate::database::claim_swap_request(swap_id, chat_id.0) {
+        Ok(Some(request)) => request,
+        Ok(None) => {
+            bot.edit_message_text(chat_id, message_id, "Too late, someone already took over.")
+                .await?;
+            return Ok(());
+        }
+        Err(e) => {
+            tracing::error!("Failed to claim swap request {}: {}", swap_id, e);
+            bot.edit_message_text(chat_id, message_id, "Sorry, something went wrong.")
+                .await?;
+            return Ok(());
+        }
+    };
+    let swapped = roster::update_roster(&config.flatmates, now, |roster| {
+        roster.week == request.week
+            && roster.on_duty() == Some(request.requester)
+            && roster.swap(chat_id.0)
+    });
+    let failure = match swapped {
+        Ok(true) => None,
+        Ok(false) => Some("This swap has expired."),
+        Err(e) => {
+            tracing::error!("Failed to record swap {}: {}", swap_id, e);
+            Some("Sorry, something went wrong.")
+        }
+    };
+    if let Some(reply) = failure {
+        // Nobody took over, so the request must not stay claimed.
+        if let Err(e) = crate::database::release_swap_request(swap_id, chat_id.0) {
+            tracing::error!("Failed to release swap request {}: {}", swap_id, e);
+        }
+        bot.edit_message_text(chat_id, message_id, reply).await?;
+        return Ok(());
+    }
+
+    stats::record(
+        now,
+        chat_id.0,
+        stats::TRASH_CHORE,
+        &[],
+        DutyOutcome::Covered,
+    );
+
+    let requester_name =
+        crate::data_grabber::grab_food_master_name(config, request.requester).await;
+    let acceptor_name = crate::data_grabber::grab_food_master_name(config, chat_id.0).await;
+    bot.edit_message_text(
+        chat_id,
+        message_id,
+        format!(
+            "Thank you! You are the food master this week.\n\
+            {} will take one of your weeks in exchange.",
+            requester_name
+        ),
+    )
+    .await?;
+    bot.send_message(
+        ChatId(request.requester),
+        format!(
+            "{} takes over your week. You will take one of theirs later on.",
+            acceptor_name
+        ),
+    )
+    .await?;
+    match crate::database::get_swap_offers(swap_id) {
+        Ok(offers) => {
+            for (offer_chat_id, offer_message_id) in offers {
+                if offer_chat_id == chat_id.0 {
+                    continue;
+                }
+                if let Err(e) = bot
+                    .edit_message_text(
+                        ChatId(offer_chat_id),
+                        MessageId(offer_message_id),
+                        format!("{} already took over.", acceptor_name),
+                    )
+                    .await
+                {
+                    tracing::warn!("Failed to withdraw swap offer: {}", e);
+                }
+            }
+        }
+        Err(e) => tracing::error!("Failed to load offers of swap {}: {}", swap_id, e),
+    }
+    crate::telegram_writer::notify_group(
+        bot,
+        config,
+        &format!(
+            "{} is the food master this week instead of {}.",
+            acceptor_name, requester_name
+        ),
+    )
+    .await;
+    Ok(())
+}
+
+/// What pressing Done on a request for help means for the duty log: the
+/// helper covered for the food master, unless it is the food master
+/// themselves. A reminder still pending is settled by it, and the food master
+/// missed it unless they pressed.
+fn cover_outcomes(helper: i64, master: i64, pending: bool) -> Vec<(i64, DutyOutcome)> {
+    match (helper == master, pending) {
+        (true, true) => vec![(master, DutyOutcome::Done)],
+        (true, false) => Vec::new(),
+        (false, true) => vec![
+            (helper, DutyOutcome::Covered),
+            (master, DutyOutcome::Missed),
+        ],
+        (false, false) => vec![(helper, DutyOutcome::Covered)],
+    }
+}
+
+/// Called when Done is pressed on a request to step in for the food master
+/// sent on `today`, after I can't or while escalating an unanswered reminder.
+#[allow(clippy::too_many_arguments)]
+async fn cover_handler(
+    bot: &Bot,
+    chat_id: ChatId,
+    message_id: MessageId,
+    helper: &teloxide::types::User,
+    today: NaiveDate,
+    master_id: i64,
+    task_state: &std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) -> ResponseResult<()> {
+    // The trashes are collected the morning after the request.
+    if now.date_naive() > today + chrono::Duration::days(1) {
+        bot.edit_message_text(chat_id, message_id, "This request is over.")
+            .await?;
+        return Ok(());
+    }
+    let helper_id = helper.id.0 as i64;
+    let claimed = match crate::database::claim_cover(today, master_id, helper_id) {
+        Ok(claimed) => claimed,
+        Err(e) => {
+            tracing::error!("Failed to claim the request for help: {}", e);
+            bot.edit_message_text(chat_id, message_id, "Sorry, something went wrong.")
+                .await?;
+            return Ok(());
+        }
+    };
+    if !claimed {
+        bot.edit_message_text(
+            chat_id,
+            message_id,
+            "Someone already took care of it. Thank you anyway!",
+        )
+        .await?;
+        return Ok(());
+    }
+    bot.edit_message_text(
+        chat_id,
+        message_id,
+        format!("Thank you, {}! Have a nice evening. <3", helper.first_name),
+    )
+    .await?;
+
+    let trashes = stats::tomorrow_trashes(today);
+    let reminder = {
+        let mut task_state = task_state.lock().unwrap();
+        let pending = task_state.state == TaskState::Pending;
+        for (chat_id, outcome) in cover_outcomes(helper_id, master_id, pending) {
+            stats::record(now, chat_id, stats::TRASH_CHORE, &trashes, outcome);
+        }
+        if !pending {
+            return Ok(());
+        }
+        metrics::answered("covered");
+        // A settled reminder cancels the escalation steps still ahead today.
+        task_state.state = TaskState::None;
+        let reminder = task_state.message_id.take();
+        task_state.persist();
+        reminder
+    };
+    if let Some(reminder) = reminder
+        && let Err(e) = bot
+            .edit_message_reply_markup(ChatId(master_id), MessageId(reminder))
+            .await
+    {
+        tracing::warn!("Failed to clear the reminder buttons: {}", e);
+    }
+    Ok(())
 }
 
 async fn request_bags_handler(
@@ -86,8 +347,19 @@ async fn confirm_request_bags_handler(
     bot: &Bot,
     chat_id: ChatId,
     message_id: MessageId,
+    config: &Config,
 ) -> ResponseResult<()> {
-    let reply = match email::request_new_bags() {
+    let Some(email_config) = &config.email else {
+        tracing::warn!("Bags were requested, but no email settings are configured");
+        bot.edit_message_text(
+            chat_id,
+            message_id,
+            "Sorry, I don't know how to reach We-Recycle. Please order the bags yourselves.",
+        )
+        .await?;
+        return Ok(());
+    };
+    let reply = match email::request_new_bags(email_config) {
         Ok(()) => "Thank you! I sent a request to We-Recycle.",
         Err(e) => {
             tracing::error!("Failed to send bag request email: {}", e);
@@ -115,6 +387,10 @@ pub async fn handle_callback_query(
     bot: Bot,
     query: CallbackQuery,
     task_state: std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
+    chore_states: std::sync::Arc<std::sync::Mutex<ChoreStates>>,
+    config: ConfigHandle,
+    wakeup: std::sync::Arc<tokio::sync::Notify>,
+    clock: std::sync::Arc<dyn Clock>,
 ) -> ResponseResult<()> {
     let data = query.data.clone();
     let Some(message) = query.message.clone() else {
@@ -126,24 +402,60 @@ pub async fn handle_callback_query(
         // Get the chat and message IDs once; every handler edits this message.
         let chat_id = message.chat().id;
         let message_id = message.id();
+        let now = clock.now();
+        let config = config.current();
         match data.as_str() {
             "done" => {
-                done_handler(&bot, chat_id, message_id, &task_state).await?;
+                done_handler(&bot, chat_id, message_id, &task_state, now).await?;
             }
             "cant" => {
-                cant_handler(&bot, chat_id, message_id, &task_state).await?;
+                cant_handler(&bot, chat_id, message_id, &task_state, &wakeup, now).await?;
             }
             "new_bags" => {
                 request_bags_handler(&bot, chat_id, message_id).await?;
             }
             "sure_bags" => {
-                confirm_request_bags_handler(&bot, chat_id, message_id).await?;
+                confirm_request_bags_handler(&bot, chat_id, message_id, &config).await?;
             }
             "enough_bags" => {
                 no_need_bags_handler(&bot, chat_id, message_id).await?;
             }
             _ => {
-                bot.send_message(chat_id, "Unrecognized option.").await?;
+                if let Some((chore_id, action)) = chores::parse_callback_data(data) {
+                    chore_handler(
+                        &bot,
+                        chat_id,
+                        message_id,
+                        chore_id,
+                        action,
+                        &config,
+                        &chore_states,
+                        now,
+                    )
+                    .await?;
+                    // The chore's next trigger may have moved, e.g. to now
+                    // for I can't.
+                    wakeup.notify_one();
+                } else if let Some((swap_id, answer)) = swap::parse_callback_data(data) {
+                    swap_answer_handler(&bot, chat_id, message_id, swap_id, answer, &config, now)
+                        .await?;
+                } else if let Some((today, master_id)) =
+                    crate::telegram_writer::parse_cover_callback_data(data)
+                {
+                    cover_handler(
+                        &bot,
+                        chat_id,
+                        message_id,
+                        &query.from,
+                        today,
+                        master_id,
+                        &task_state,
+                        now,
+                    )
+                    .await?;
+                } else {
+                    bot.send_message(chat_id, "Unrecognized option.").await?;
+                }
             }
         }
         // Answer the callback query to remove the "loading" state
@@ -153,42 +465,456 @@ pub async fn handle_callback_query(
     Ok(())
 }
 
+async fn ping_handler(
+    bot: &Bot,
+    chat_id: ChatId,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) -> ResponseResult<()> {
+    bot.send_message(chat_id, "pong!").await?;
+
+    let mut status = format!("now is {}", now);
+    match crate::database::load_jobs(now.timezone()) {
+        Ok(jobs) => {
+            for job in jobs {
+                let next_run = job
+                    .next_run
+                    .map_or("never".to_string(), |next_run| next_run.to_string());
+                status.push_str(&format!("\n{} runs next at {}", job.name, next_run));
+            }
+        }
+        Err(e) => tracing::error!("Error getting scheduled jobs: {:?}", e),
+    }
+    bot.send_message(chat_id, status).await?;
+    let trashes = crate::database::get_all_trashes();
+    tracing::info!("Trashes: {:?}", trashes);
+
+    match trashes {
+        Ok(trashes) => {
+            // Past collections stay in the database, but only the upcoming
+            // ones are of interest.
+            let mut trashes: Vec<_> = trashes
+                .into_iter()
+                .filter(|(date, _)| *date >= now.date_naive())
+                .collect();
+            trashes.sort_by_key(|(date, _)| *date);
+            for (date, waste_types) in trashes {
+                bot.send_message(chat_id, format!("{}: {:?}", date, waste_types))
+                    .await?;
+            }
+        }
+        Err(e) => {
+            tracing::error!("Error getting trashes: {:?}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Show the trash duty rotation in order, marking who is on duty and who is
+/// away.
+async fn roster_handler(
+    bot: &Bot,
+    chat_id: ChatId,
+    config: &Config,
+    today: NaiveDate,
+) -> ResponseResult<()> {
+    let roster = match roster::load_roster_at(&config.flatmates, today) {
+        Ok(roster) => roster,
+        Err(e) => {
+            tracing::error!("Failed to load the roster: {}", e);
+            bot.send_message(chat_id, "Sorry, I couldn't load the roster.")
+                .await?;
+            return Ok(());
+        }
+    };
+    let on_duty = roster.on_duty();
+    let mut txt = String::from("Trash duty rotation:\n");
+    for (position, member) in roster.members.iter().enumerate() {
+        let name = crate::data_grabber::grab_food_master_name(config, member.chat_id).await;
+        let mut line = format!("{}. {}", position + 1, name);
+        if Some(member.chat_id) == on_duty {
+            line.push_str(" (on duty this week)");
+        }
+        if member.away {
+            line.push_str(" (away)");
+        }
+        txt.push_str(&line);
+        txt.push('\n');
+    }
+    bot.send_message(chat_id, txt).await?;
+    Ok(())
+}
+
+/// Parse the arguments of "/away 2026-12-20 2027-01-05" into the first and
+/// last day of the absence.
+fn parse_absence(args: &str) -> Result<(NaiveDate, NaiveDate), String> {
+    let dates: Vec<&str> = args.split_whitespace().collect();
+    let [from, until] = dates[..] else {
+        return Err(
+            "Please give the first and last day, e.g. /away 2026-12-20 2027-01-05".to_string(),
+        );
+    };
+    let parse = |raw: &str| {
+        NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+            .map_err(|_| format!("'{}' is not a date like 2026-12-20", raw))
+    };
+    let (from, until) = (parse(from)?, parse(until)?);
+    if until < from {
+        return Err("The last day can't be before the first one.".to_string());
+    }
+    Ok((from, until))
+}
+
+/// "/away" marks the flatmate owning this chat as away until "/back";
+/// "/away <from> <until>" plans an absence. Either way the rotation skips them
+/// and gives them their turn back later.
+async fn away_handler(
+    bot: &Bot,
+    chat_id: ChatId,
+    config: &Config,
+    args: &str,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) -> ResponseResult<()> {
+    let today = now.date_naive();
+    if args.trim().is_empty() {
+        let reply = match roster::update_roster(&config.flatmates, now, |roster| {
+            roster.set_away(chat_id.0, true)
+        }) {
+            Ok(true) => "Got it, I will skip you in the rotation until you send /back.",
+            Ok(false) => "This chat is not on the trash duty roster.",
+            Err(e) => {
+                tracing::error!("Failed to update the roster: {}", e);
+                "Sorry, I couldn't update the roster."
+            }
+        };
+        bot.send_message(chat_id, reply).await?;
+        return Ok(());
+    }
+
+    let (from, until) = match parse_absence(args) {
+        Ok(dates) => dates,
+        Err(reason) => {
+            bot.send_message(chat_id, reason).await?;
+            return Ok(());
+        }
+    };
+    if until < today {
+        bot.send_message(chat_id, "This absence is already over.")
+            .await?;
+        return Ok(());
+    }
+    let absence = roster::Absence {
+        chat_id: chat_id.0,
+        from,
+        until,
+    };
+    let reply =
+        match roster::update_roster(&config.flatmates, now, |roster| roster.add_absence(absence)) {
+            Ok(true) => format!(
+                "Have a nice time! I will skip you in the rotation from {} to {} \
+            and give you your turn back afterwards.",
+                from.format("%d.%m.%Y"),
+                until.format("%d.%m.%Y")
+            ),
+            Ok(false) => "This chat is not on the trash duty roster.".to_string(),
+            Err(e) => {
+                tracing::error!("Failed to update the roster: {}", e);
+                "Sorry, I couldn't update the roster.".to_string()
+            }
+        };
+    bot.send_message(chat_id, reply).await?;
+    Ok(())
+}
+
+/// Put the flatmate owning this chat back into the rotation, ending any
+/// absence still running.
+async fn back_handler(
+    bot: &Bot,
+    chat_id: ChatId,
+    config: &Config,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) -> ResponseResult<()> {
+    let today = now.date_naive();
+    let reply = match roster::update_roster(&config.flatmates, now, |roster| {
+        roster.end_absences(chat_id.0, today);
+        roster.set_away(chat_id.0, false)
+    }) {
+        Ok(true) => "Welcome back! You are in the rotation again.",
+        Ok(false) => "This chat is not on the trash duty roster.",
+        Err(e) => {
+            tracing::error!("Failed to update the roster: {}", e);
+            "Sorry, I couldn't update the roster."
+        }
+    };
+    bot.send_message(chat_id, reply).await?;
+    Ok(())
+}
+
+/// Ask the other flatmates to take over this week's trash duty. Only the
+/// flatmate on duty can ask.
+async fn swap_handler(
+    bot: &Bot,
+    chat_id: ChatId,
+    config: &Config,
+    today: NaiveDate,
+) -> ResponseResult<()> {
+    let roster = match roster::load_roster_at(&config.flatmates, today) {
+        Ok(roster) => roster,
+        Err(e) => {
+            tracing::error!("Failed to load the roster: {}", e);
+            bot.send_message(chat_id, "Sorry, I couldn't load the roster.")
+                .await?;
+            return Ok(());
+        }
+    };
+    if roster.on_duty() != Some(chat_id.0) {
+        bot.send_message(
+            chat_id,
+            "Only the food master of this week can ask for a swap.",
+        )
+        .await?;
+        return Ok(());
+    }
+    let swap_id = match crate::database::create_swap_request(chat_id.0, roster.week) {
+        Ok(swap_id) => swap_id,
+        Err(e) => {
+            tracing::error!("Failed to create swap request: {}", e);
+            bot.send_message(chat_id, "Sorry, I couldn't start the swap.")
+                .await?;
+            return Ok(());
+        }
+    };
+    let requester_name = crate::data_grabber::grab_food_master_name(config, chat_id.0).await;
+    let mut offered = 0;
+    for member in roster
+        .members
+        .iter()
+        .filter(|member| member.chat_id != chat_id.0 && !member.away)
+    {
+        let Some(message_id) =
+            crate::telegram_writer::swap_offer(bot, member.chat_id, swap_id, &requester_name).await
+        else {
+            continue;
+        };
+        offered += 1;
+        if let Err(e) = crate::database::add_swap_offer(swap_id, member.chat_id, message_id) {
+            tracing::error!("Failed to record swap offer: {}", e);
+        }
+    }
+    let reply = if offered == 0 {
+        "Sorry, nobody is around to swap with you this week."
+    } else {
+        "I asked the others. I will let you know when someone takes over."
+    };
+    bot.send_message(chat_id, reply).await?;
+    Ok(())
+}
+
+/// Show per-flatmate completion rates, covers and streaks from the duty log.
+async fn stats_handler(bot: &Bot, chat_id: ChatId, config: &Config) -> ResponseResult<()> {
+    let entries = match crate::database::get_duty_log(config.timezone) {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::error!("Failed to load the duty log: {}", e);
+            bot.send_message(chat_id, "Sorry, I couldn't load the statistics.")
+                .await?;
+            return Ok(());
+        }
+    };
+    let mut named = Vec::new();
+    for flatmate_stats in stats::compute(&config.flatmates, &entries) {
+        let name = crate::data_grabber::grab_food_master_name(config, flatmate_stats.chat_id).await;
+        named.push((name, flatmate_stats));
+    }
+    bot.send_message(chat_id, stats::format_stats(&named))
+        .await?;
+    Ok(())
+}
+
+/// Send the upcoming collections as an iCalendar file to import.
+async fn calendar_handler(
+    bot: &Bot,
+    chat_id: ChatId,
+    config: &Config,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) -> ResponseResult<()> {
+    let ics = match calendar::feed(config, now).await {
+        Ok(ics) => ics,
+        Err(e) => {
+            tracing::error!("Failed to build the calendar: {}", e);
+            bot.send_message(chat_id, "Sorry, I couldn't build the calendar.")
+                .await?;
+            return Ok(());
+        }
+    };
+    let file = InputFile::memory(ics.into_bytes()).file_name(calendar::FILE_NAME);
+    bot.send_document(chat_id, file).await?;
+    Ok(())
+}
+
+/// What to tell a household looking for its We-Recycle collection region.
+fn werecycle_regions_message(regions: &Regions) -> String {
+    if regions.is_empty() {
+        return format!(
+            "I couldn't find any regions in the We-Recycle calendars at {}.",
+            we_recycle::CALENDARS_URL
+        );
+    }
+    format!(
+        "The We-Recycle calendars serve the regions {}. Look up yours at {} \
+         and set it as region of the we_recycle provider.",
+        regions,
+        we_recycle::CALENDARS_URL
+    )
+}
+
+/// List every region in the current We-Recycle calendars.
+async fn werecycle_regions_handler(
+    bot: &Bot,
+    chat_id: ChatId,
+    today: NaiveDate,
+) -> ResponseResult<()> {
+    let regions = match WeRecycleWasteGrabber::regions(today).await {
+        Ok(regions) => regions,
+        Err(e) => {
+            tracing::error!("Failed to read the We-Recycle regions: {}", e);
+            bot.send_message(chat_id, "Sorry, I couldn't read the We-Recycle calendars.")
+                .await?;
+            return Ok(());
+        }
+    };
+    bot.send_message(chat_id, werecycle_regions_message(&regions))
+        .await?;
+    Ok(())
+}
+
+/// First word of a message with any "@botname" suffix stripped, so "/roster"
+/// and "/roster@gstaldergeist_bot" are the same command.
+fn command(text: &str) -> Option<&str> {
+    let word = text.split_whitespace().next()?;
+    word.split('@').next()
+}
+
+/// Everything after the first word of a message.
+fn command_args(text: &str) -> &str {
+    let text = text.trim_start();
+    match text.find(char::is_whitespace) {
+        Some(end) => text[end..].trim(),
+        None => "",
+    }
+}
+
 pub async fn handle_message(
     bot: Bot,
     msg: Message,
-    task_state: std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
+    config: ConfigHandle,
+    clock: std::sync::Arc<dyn Clock>,
 ) -> ResponseResult<()> {
     tracing::info!("Received message: {:?} from {:?}", msg.text(), msg.chat.id);
-    if let Some(text) = msg.text() {
-        if text == "ping" {
-            let chat_id = msg.chat.id;
-            bot.send_message(chat_id, "pong!").await?;
-
-            let time = chrono::Local::now();
-            bot.send_message(
-                chat_id,
-                format!(
-                    "now is {}, next trigger is {}",
-                    time,
-                    task_state.lock().unwrap().next_trigger
-                ),
-            )
-            .await?;
-            let trashes = crate::database::get_all_trashes();
-            tracing::info!("Trashes: {:?}", trashes);
-
-            match trashes {
-                Ok(trashes) => {
-                    for (date, waste_types) in trashes {
-                        bot.send_message(chat_id, format!("{}: {:?}", date, waste_types))
-                            .await?;
-                    }
-                }
-                Err(e) => {
-                    tracing::error!("Error getting trashes: {:?}", e);
-                }
-            }
-        }
+    let chat_id = msg.chat.id;
+    let args = msg.text().map(command_args).unwrap_or_default();
+    let now = clock.now();
+    let today = now.date_naive();
+    let config = config.current();
+    match msg.text().and_then(command) {
+        Some("ping") => ping_handler(&bot, chat_id, now).await?,
+        Some("/roster") => roster_handler(&bot, chat_id, &config, today).await?,
+        Some("/away") => away_handler(&bot, chat_id, &config, args, now).await?,
+        Some("/back") => back_handler(&bot, chat_id, &config, now).await?,
+        Some("/swap") => swap_handler(&bot, chat_id, &config, today).await?,
+        Some("/stats") => stats_handler(&bot, chat_id, &config).await?,
+        Some("/calendar") => calendar_handler(&bot, chat_id, &config, now).await?,
+        Some("/werecycle_regions") => werecycle_regions_handler(&bot, chat_id, today).await?,
+        _ => {}
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DutyOutcome, Regions, command, command_args, cover_outcomes, parse_absence,
+        werecycle_regions_message,
+    };
+    use chrono::NaiveDate;
+
+    #[test]
+    fn command_is_the_first_word() {
+        assert_eq!(command("/away until sunday"), Some("/away"));
+        assert_eq!(command("  ping "), Some("ping"));
+    }
+
+    #[test]
+    fn command_strips_the_bot_name() {
+        assert_eq!(command("/roster@gstaldergeist_bot"), Some("/roster"));
+    }
+
+    #[test]
+    fn empty_message_has_no_command() {
+        assert_eq!(command(""), None);
+        assert_eq!(command("   "), None);
+    }
+
+    #[test]
+    fn command_args_are_the_rest_of_the_message() {
+        assert_eq!(
+            command_args("/away  2026-12-20 2027-01-05 "),
+            "2026-12-20 2027-01-05"
+        );
+        assert_eq!(command_args("/away"), "");
+    }
+
+    #[test]
+    fn parses_an_absence() {
+        assert_eq!(
+            parse_absence("2026-12-20 2027-01-05"),
+            Ok((
+                NaiveDate::from_ymd_opt(2026, 12, 20).unwrap(),
+                NaiveDate::from_ymd_opt(2027, 1, 5).unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn single_day_absence_is_allowed() {
+        let (from, until) = parse_absence("2026-12-20 2026-12-20").unwrap();
+        assert_eq!(from, until);
+    }
+
+    #[test]
+    fn rejects_malformed_absences() {
+        for args in [
+            "",
+            "2026-12-20",
+            "2026-12-20 2027-01-05 2027-01-06",
+            "20.12.2026 05.01.2027",
+            "2027-01-05 2026-12-20",
+        ] {
+            assert!(parse_absence(args).is_err(), "{} should be rejected", args);
+        }
+    }
+
+    #[test]
+    fn werecycle_regions_are_listed_as_in_the_calendar() {
+        let regions: Regions = "1 - 6 + 19".parse().unwrap();
+        assert_eq!(
+            werecycle_regions_message(&regions),
+            "The We-Recycle calendars serve the regions 1 - 6 + 19. Look up yours at \
+             https://www.werecycle.ch/en/abholdaten/ and set it as region of the \
+             we_recycle provider."
+        );
+        let none = Regions::default();
+        assert!(werecycle_regions_message(&none).starts_with("I couldn't find any regions"));
+    }
+
+    #[test]
+    fn helpers_cover_and_settle_a_pending_reminder() {
+        use DutyOutcome::*;
+        assert_eq!(cover_outcomes(20, 10, false), vec![(20, Covered)]);
+        assert_eq!(
+            cover_outcomes(20, 10, true),
+            vec![(20, Covered), (10, Missed)]
+        );
+        assert_eq!(cover_outcomes(10, 10, true), vec![(10, Done)]);
+        assert_eq!(cover_outcomes(10, 10, false), vec![]);
+    }
+}
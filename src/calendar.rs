@@ -0,0 +1,245 @@
+use crate::Config;
+use crate::data_grabber::TrashType;
+use crate::error::GstaldergeistError;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::HashMap;
+
+/// Name of the feed as sent on Telegram.
+pub const FILE_NAME: &str = "gstaldergeist.ics";
+
+/// Longest content line in octets, without the line break.
+const MAX_LINE_LEN: usize = 75;
+
+/// A collection day with the flatmate who takes the trash out the evening
+/// before.
+#[derive(Debug, PartialEq)]
+pub struct Collection {
+    pub date: NaiveDate,
+    pub trashes: Vec<TrashType>,
+    pub on_duty: String,
+}
+
+/// Escape a TEXT value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Append `line` folded to lines of at most 75 octets, each ending in CRLF.
+fn push_line(ics: &mut String, line: &str) {
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > MAX_LINE_LEN {
+            ics.push_str("\r\n ");
+            // The space starting a continuation counts towards its length.
+            len = 1;
+        }
+        ics.push(c);
+        len += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+/// The collections as an iCalendar feed with one all-day event per day,
+/// each with an alarm at `reminder_hour` the evening before. `channel_id`
+/// tells the events apart from those of other flats' feeds, and `stamp` is
+/// when the feed was made.
+pub fn render(
+    collections: &[Collection],
+    reminder_hour: u32,
+    channel_id: i64,
+    stamp: DateTime<Utc>,
+) -> String {
+    let mut ics = String::new();
+    let stamp = stamp.format("%Y%m%dT%H%M%SZ").to_string();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//Gstaldergeist//Trash collections//EN",
+        "CALSCALE:GREGORIAN",
+        "METHOD:PUBLISH",
+        "X-WR-CALNAME:Trash collections",
+    ] {
+        push_line(&mut ics, line);
+    }
+    for collection in collections {
+        let trashes: Vec<String> = collection.trashes.iter().map(|t| t.to_string()).collect();
+        let trashes = trashes.join(", ");
+        let date = collection.date.format("%Y%m%d");
+        let end = (collection.date + chrono::Duration::days(1)).format("%Y%m%d");
+        let summary = format!("{} ({})", trashes, collection.on_duty);
+        let description = format!("Collected: {}\nOn duty: {}", trashes, collection.on_duty);
+        let alarm = format!("Put out the trash tonight: {}", trashes);
+        for line in [
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}@{}.gstaldergeist", date, channel_id),
+            format!("DTSTAMP:{}", stamp),
+            format!("DTSTART;VALUE=DATE:{}", date),
+            format!("DTEND;VALUE=DATE:{}", end),
+            format!("SUMMARY:{}", escape(&summary)),
+            format!("DESCRIPTION:{}", escape(&description)),
+            "TRANSP:TRANSPARENT".to_string(),
+            "BEGIN:VALARM".to_string(),
+            "ACTION:DISPLAY".to_string(),
+            format!("DESCRIPTION:{}", escape(&alarm)),
+            // All-day events start at midnight, so the evening before is
+            // this many hours earlier.
+            format!("TRIGGER:-PT{}H", 24 - reminder_hour),
+            "END:VALARM".to_string(),
+            "END:VEVENT".to_string(),
+        ] {
+            push_line(&mut ics, &line);
+        }
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+/// Every stored collection from `today` on, with who will be on duty for it.
+pub async fn upcoming_collections(
+    config: &Config,
+    today: NaiveDate,
+) -> Result<Vec<Collection>, GstaldergeistError> {
+    let mut trashes: Vec<(NaiveDate, Vec<TrashType>)> = crate::database::get_all_trashes()?
+        .into_iter()
+        .filter(|(date, _)| *date >= today)
+        .collect();
+    trashes.sort_by_key(|(date, _)| *date);
+    let dates: Vec<NaiveDate> = trashes.iter().map(|(date, _)| *date).collect();
+    let on_duty = crate::roster::duty_forecast(&config.flatmates, today, &dates)?;
+
+    let mut names: HashMap<i64, String> = HashMap::new();
+    let mut collections = Vec::new();
+    for ((date, trashes), chat_id) in trashes.into_iter().zip(on_duty) {
+        let on_duty = match chat_id {
+            Some(chat_id) => match names.get(&chat_id) {
+                Some(name) => name.clone(),
+                None => {
+                    let name = crate::data_grabber::grab_food_master_name(config, chat_id).await;
+                    names.insert(chat_id, name.clone());
+                    name
+                }
+            },
+            None => "nobody".to_string(),
+        };
+        collections.push(Collection {
+            date,
+            trashes,
+            on_duty,
+        });
+    }
+    Ok(collections)
+}
+
+/// The feed of the upcoming collections as of `now`.
+pub async fn feed(
+    config: &Config,
+    now: DateTime<chrono_tz::Tz>,
+) -> Result<String, GstaldergeistError> {
+    let collections = upcoming_collections(config, now.date_naive()).await?;
+    Ok(render(
+        &collections,
+        config.escalation.reminder_hour,
+        config.global_channel_id,
+        now.with_timezone(&Utc),
+    ))
+}
+
+/// Rewrite the feed at the configured `calendar_path`, if any. The file is
+/// replaced in one step so a subscriber never reads half of it.
+pub async fn write_feed(
+    config: &Config,
+    now: DateTime<chrono_tz::Tz>,
+) -> Result<(), GstaldergeistError> {
+    let Some(path) = &config.calendar_path else {
+        return Ok(());
+    };
+    let ics = feed(config, now).await?;
+    let partial = path.with_extension("ics.partial");
+    tokio::fs::write(&partial, ics).await?;
+    tokio::fs::rename(&partial, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn stamp() -> DateTime<Utc> {
+        date(2026, 1, 4).and_hms_opt(18, 30, 0).unwrap().and_utc()
+    }
+
+    #[test]
+    fn one_event_per_collection_day_with_an_alarm_the_evening_before() {
+        let ics = render(
+            &[
+                Collection {
+                    date: date(2026, 1, 6),
+                    trashes: vec![TrashType::Normal, TrashType::Paper],
+                    on_duty: "Alice".to_string(),
+                },
+                Collection {
+                    date: date(2026, 1, 31),
+                    trashes: vec![TrashType::Bio],
+                    on_duty: "Bob".to_string(),
+                },
+            ],
+            19,
+            -100,
+            stamp(),
+        );
+        let lines: Vec<&str> = ics.split("\r\n").collect();
+        assert_eq!(lines.first(), Some(&"BEGIN:VCALENDAR"));
+        assert_eq!(lines[lines.len() - 2..], ["END:VCALENDAR", ""]);
+        assert_eq!(lines.iter().filter(|l| **l == "BEGIN:VEVENT").count(), 2);
+        for expected in [
+            "UID:20260106@-100.gstaldergeist",
+            "DTSTAMP:20260104T183000Z",
+            "DTSTART;VALUE=DATE:20260106",
+            "DTEND;VALUE=DATE:20260107",
+            "SUMMARY:Normal\\, Paper (Alice)",
+            "DESCRIPTION:Collected: Normal\\, Paper\\nOn duty: Alice",
+            "TRIGGER:-PT5H",
+            "DTEND;VALUE=DATE:20260201",
+            "SUMMARY:Bio (Bob)",
+        ] {
+            assert!(lines.contains(&expected), "missing {}", expected);
+        }
+    }
+
+    #[test]
+    fn long_lines_are_folded_without_splitting_characters() {
+        let ics = render(
+            &[Collection {
+                date: date(2026, 1, 6),
+                trashes: vec![TrashType::Cardboard],
+                on_duty: "Zoë ".repeat(30),
+            }],
+            19,
+            -100,
+            stamp(),
+        );
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_LEN));
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains(&format!("SUMMARY:Cardboard ({})", "Zoë ".repeat(30))));
+    }
+
+    #[test]
+    fn escapes_text_values() {
+        assert_eq!(escape("a;b,c\\d\ne"), "a\\;b\\,c\\\\d\\ne");
+    }
+}
@@ -0,0 +1,544 @@
+use crate::clock::Clock;
+use crate::error::GstaldergeistError;
+use crate::stats::DutyOutcome;
+use crate::{Config, TaskState};
+use chrono::{Datelike, Timelike, Weekday};
+use std::collections::HashMap;
+use teloxide::prelude::*;
+
+/// How long the flatmate on duty has to press Done before the group is asked
+/// to step in.
+const CHORE_GRACE_HOURS: i64 = 24;
+
+/// A weekly household chore with its own rotation, e.g. cleaning the bathroom
+/// every Saturday at 10:00.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chore {
+    /// Short identifier, also used to namespace the callback data of the
+    /// chore's buttons.
+    pub id: String,
+    pub weekday: Weekday,
+    pub hour: u32,
+    /// Telegram chat ids of the flatmates taking turns, in order.
+    pub rotation: Vec<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChoreState {
+    pub state: TaskState,
+    /// Index into the chore's rotation of the flatmate currently on duty.
+    pub turn: usize,
+    pub next_trigger: chrono::DateTime<chrono_tz::Tz>,
+    /// Id of the reminder carrying the Done / I can't buttons, while it is
+    /// still outstanding.
+    pub message_id: Option<i32>,
+}
+
+/// State of every configured chore, keyed by chore id.
+pub type ChoreStates = HashMap<String, ChoreState>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChoreAction {
+    Done,
+    Cant,
+}
+
+const CALLBACK_PREFIX: &str = "chore:";
+
+/// Callback data for one of a chore's buttons, e.g. "chore:bathroom:done".
+pub fn callback_data(chore_id: &str, action: ChoreAction) -> String {
+    let action = match action {
+        ChoreAction::Done => "done",
+        ChoreAction::Cant => "cant",
+    };
+    format!("{}{}:{}", CALLBACK_PREFIX, chore_id, action)
+}
+
+/// Inverse of [`callback_data`]. Returns `None` for data that does not belong
+/// to a chore.
+pub fn parse_callback_data(data: &str) -> Option<(&str, ChoreAction)> {
+    let (chore_id, action) = data.strip_prefix(CALLBACK_PREFIX)?.split_once(':')?;
+    let action = match action {
+        "done" => ChoreAction::Done,
+        "cant" => ChoreAction::Cant,
+        _ => return None,
+    };
+    Some((chore_id, action))
+}
+
+/// Parse a comma-separated list of chores, e.g.
+/// "bathroom:sat:10, plants:wed:18:123|456".
+///
+/// Each entry is `id:weekday:hour`, optionally followed by a `|`-separated
+/// rotation of chat ids. Without one, the chore rotates among all `flatmates`.
+pub fn parse_chores(raw: &str, flatmates: &[i64]) -> Result<Vec<Chore>, GstaldergeistError> {
+    let mut chores: Vec<Chore> = Vec::new();
+    for entry in raw
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let chore = parse_chore(entry, flatmates)?;
+        if chores.iter().any(|other| other.id == chore.id) {
+            return Err(GstaldergeistError::ConfigError(format!(
+                "CHORES lists the chore '{}' twice",
+                chore.id
+            )));
+        }
+        chores.push(chore);
+    }
+    Ok(chores)
+}
+
+fn parse_chore(entry: &str, flatmates: &[i64]) -> Result<Chore, GstaldergeistError> {
+    let invalid = |reason: &str| {
+        GstaldergeistError::ConfigError(format!(
+            "CHORES entries must look like id:weekday:hour[:id1|id2|...], got '{}': {}",
+            entry, reason
+        ))
+    };
+    let parts: Vec<&str> = entry.split(':').map(str::trim).collect();
+    if parts.len() != 3 && parts.len() != 4 {
+        return Err(invalid("wrong number of fields"));
+    }
+    let hour: u32 = parts[2]
+        .parse()
+        .map_err(|_| invalid("the hour must be between 0 and 23"))?;
+    let rotation = match parts.get(3) {
+        Some(rotation) => Some(
+            rotation
+                .split('|')
+                .map(|chat_id| chat_id.trim().parse::<i64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid("the rotation must be chat ids separated by '|'"))?,
+        ),
+        None => None,
+    };
+    new_chore(parts[0], parts[1], hour, rotation, flatmates).map_err(|reason| invalid(&reason))
+}
+
+/// Check and assemble a chore. Without a `rotation`, it rotates among all
+/// `flatmates`. The error is the reason the chore is invalid.
+pub fn new_chore(
+    id: &str,
+    weekday: &str,
+    hour: u32,
+    rotation: Option<Vec<i64>>,
+    flatmates: &[i64],
+) -> Result<Chore, String> {
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err("the id may only contain letters, digits, '_' and '-'".to_string());
+    }
+    let weekday: Weekday = weekday
+        .trim()
+        .parse()
+        .map_err(|_| "unknown weekday".to_string())?;
+    if hour >= 24 {
+        return Err("the hour must be between 0 and 23".to_string());
+    }
+    let rotation = rotation.unwrap_or_else(|| flatmates.to_vec());
+    if rotation.is_empty() {
+        return Err("the rotation is empty".to_string());
+    }
+    Ok(Chore {
+        id: id.to_string(),
+        weekday,
+        hour,
+        rotation,
+    })
+}
+
+/// First occurrence of the chore strictly after `now`.
+pub fn next_occurrence(
+    chore: &Chore,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) -> chrono::DateTime<chrono_tz::Tz> {
+    let days_ahead = (7 + chore.weekday.num_days_from_monday() as i64
+        - now.weekday().num_days_from_monday() as i64)
+        % 7;
+    let date = now.date_naive() + chrono::Duration::days(days_ahead);
+    let candidate = crate::at_hour(now.timezone(), date, chore.hour);
+    if candidate > now {
+        candidate
+    } else {
+        crate::at_hour(now.timezone(), date + chrono::Duration::days(7), chore.hour)
+    }
+}
+
+impl ChoreState {
+    /// State of a chore nobody has taken a turn at yet.
+    pub fn new(chore: &Chore, now: chrono::DateTime<chrono_tz::Tz>) -> Self {
+        ChoreState {
+            state: TaskState::None,
+            turn: 0,
+            next_trigger: next_occurrence(chore, now),
+            message_id: None,
+        }
+    }
+
+    /// Chat id of the flatmate currently on duty.
+    pub fn assignee(&self, chore: &Chore) -> i64 {
+        chore.rotation[self.turn % chore.rotation.len()]
+    }
+
+    /// The assignee has been reminded and has until the grace period runs out
+    /// to confirm.
+    pub fn mark_reminded(&mut self, message_id: Option<i32>, now: chrono::DateTime<chrono_tz::Tz>) {
+        self.state = TaskState::Pending;
+        self.message_id = message_id;
+        self.next_trigger = now + chrono::Duration::hours(CHORE_GRACE_HOURS);
+    }
+
+    /// The assignee pressed Done. Returns `false` if no reminder was
+    /// outstanding, e.g. for a button pressed twice.
+    pub fn mark_done(&mut self, chore: &Chore, now: chrono::DateTime<chrono_tz::Tz>) -> bool {
+        if self.state != TaskState::Pending {
+            return false;
+        }
+        self.state = TaskState::Done;
+        self.finish_turn(chore, now);
+        true
+    }
+
+    /// The assignee pressed I can't: the group is asked on the next tick.
+    pub fn mark_failed(&mut self, now: chrono::DateTime<chrono_tz::Tz>) -> bool {
+        if self.state != TaskState::Pending {
+            return false;
+        }
+        self.state = TaskState::Failed;
+        self.message_id = None;
+        self.next_trigger = now;
+        true
+    }
+
+    /// The group has been asked to step in; the rotation moves on regardless.
+    pub fn mark_handed_over(&mut self, chore: &Chore, now: chrono::DateTime<chrono_tz::Tz>) {
+        self.state = TaskState::None;
+        self.finish_turn(chore, now);
+    }
+
+    fn finish_turn(&mut self, chore: &Chore, now: chrono::DateTime<chrono_tz::Tz>) {
+        self.turn = (self.turn + 1) % chore.rotation.len();
+        self.message_id = None;
+        self.next_trigger = next_occurrence(chore, now);
+    }
+}
+
+/// Load the saved state of every configured chore. Chores without a saved
+/// state, e.g. newly configured ones, start fresh.
+pub fn restore_chore_states(config: &Config, now: chrono::DateTime<chrono_tz::Tz>) -> ChoreStates {
+    let mut saved = match crate::database::load_chore_states(now.timezone()) {
+        Ok(saved) => saved,
+        Err(e) => {
+            tracing::error!("Failed to restore chore states, starting fresh: {}", e);
+            HashMap::new()
+        }
+    };
+    config
+        .chores
+        .iter()
+        .map(|chore| {
+            let state = saved
+                .remove(&chore.id)
+                .unwrap_or_else(|| ChoreState::new(chore, now));
+            (chore.id.clone(), state)
+        })
+        .collect()
+}
+
+/// Bring the chore states in line with a reloaded configuration. Removed
+/// chores are dropped and new ones start fresh. A chore moved to another day
+/// or hour is rescheduled, unless its reminder is still outstanding.
+pub fn reload_chore_states(
+    config: &Config,
+    chore_states: &mut ChoreStates,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) {
+    let mut previous = std::mem::take(chore_states);
+    for chore in &config.chores {
+        let state = match previous.remove(&chore.id) {
+            Some(mut state) => {
+                let moved = state.next_trigger.weekday() != chore.weekday
+                    || state.next_trigger.hour() != chore.hour;
+                if moved && state.state == TaskState::None {
+                    state.next_trigger = next_occurrence(chore, now);
+                    persist(&chore.id, &state);
+                }
+                state
+            }
+            None => ChoreState::new(chore, now),
+        };
+        chore_states.insert(chore.id.clone(), state);
+    }
+}
+
+pub fn persist(chore_id: &str, state: &ChoreState) {
+    if let Err(e) = crate::database::save_chore_state(chore_id, state) {
+        tracing::error!("Failed to persist state of chore {}: {}", chore_id, e);
+    }
+}
+
+/// Send the reminders and help requests of every chore whose trigger has
+/// passed.
+pub async fn run_due_chores(
+    config: &Config,
+    clock: &dyn Clock,
+    chore_states: &std::sync::Arc<std::sync::Mutex<ChoreStates>>,
+    bot: &Bot,
+) {
+    for chore in &config.chores {
+        let now = clock.now();
+        let Some(state) = chore_states.lock().unwrap().get(&chore.id).cloned() else {
+            continue;
+        };
+        if now < state.next_trigger {
+            continue;
+        }
+        let assignee = state.assignee(chore);
+        match state.state {
+            TaskState::None | TaskState::Done => {
+                let message_id = crate::telegram_writer::chore_reminder(bot, chore, assignee).await;
+                update_chore(chore_states, &chore.id, |state| {
+                    state.mark_reminded(message_id, now)
+                });
+            }
+            TaskState::Pending | TaskState::Failed => {
+                // I can't was already recorded when it was pressed.
+                if state.state == TaskState::Pending {
+                    crate::stats::record(now, assignee, &chore.id, &[], DutyOutcome::Missed);
+                }
+                crate::telegram_writer::chore_help_request(bot, config, chore, &state.state).await;
+                update_chore(chore_states, &chore.id, |state| {
+                    state.mark_handed_over(chore, now)
+                });
+            }
+        }
+    }
+}
+
+/// When the next chore needs attention, if any chore is configured.
+pub fn next_trigger(
+    chore_states: &std::sync::Arc<std::sync::Mutex<ChoreStates>>,
+) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+    chore_states
+        .lock()
+        .unwrap()
+        .values()
+        .map(|state| state.next_trigger)
+        .min()
+}
+
+fn update_chore(
+    chore_states: &std::sync::Arc<std::sync::Mutex<ChoreStates>>,
+    chore_id: &str,
+    update: impl FnOnce(&mut ChoreState),
+) {
+    let mut states = chore_states.lock().unwrap();
+    if let Some(state) = states.get_mut(chore_id) {
+        update(state);
+        persist(chore_id, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Timelike};
+
+    fn local(
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        min: u32,
+    ) -> chrono::DateTime<chrono_tz::Tz> {
+        chrono_tz::Europe::Zurich
+            .with_ymd_and_hms(year, month, day, hour, min, 0)
+            .single()
+            .expect("unambiguous local time")
+    }
+
+    fn bathroom() -> Chore {
+        Chore {
+            id: "bathroom".to_string(),
+            weekday: Weekday::Sat,
+            hour: 10,
+            rotation: vec![10, 20, 30],
+        }
+    }
+
+    #[test]
+    fn parses_chores_with_and_without_rotation() {
+        let chores =
+            parse_chores("bathroom:sat:10, plants:Wednesday:18:20|30", &[10, 20, 30]).unwrap();
+        assert_eq!(
+            chores,
+            vec![
+                bathroom(),
+                Chore {
+                    id: "plants".to_string(),
+                    weekday: Weekday::Wed,
+                    hour: 18,
+                    rotation: vec![20, 30],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_chore_list_is_allowed() {
+        assert_eq!(parse_chores("", &[10]).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn rejects_malformed_chores() {
+        for raw in [
+            "bathroom",
+            "bathroom:sat",
+            "bathroom:someday:10",
+            "bathroom:sat:24",
+            "bathroom:sat:10:abc",
+            "bath room:sat:10",
+            ":sat:10",
+            "bathroom:sat:10,bathroom:sun:10",
+        ] {
+            let err = parse_chores(raw, &[10]).unwrap_err();
+            assert!(
+                matches!(err, GstaldergeistError::ConfigError(_)),
+                "{} should be rejected",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn callback_data_round_trips() {
+        for action in [ChoreAction::Done, ChoreAction::Cant] {
+            let data = callback_data("bathroom", action);
+            assert_eq!(parse_callback_data(&data), Some(("bathroom", action)));
+        }
+    }
+
+    #[test]
+    fn trash_callbacks_are_not_chore_callbacks() {
+        assert_eq!(parse_callback_data("done"), None);
+        assert_eq!(parse_callback_data("cant"), None);
+        assert_eq!(parse_callback_data("chore:bathroom:maybe"), None);
+    }
+
+    #[test]
+    fn next_occurrence_later_the_same_week() {
+        // 2026-06-03 is a Wednesday.
+        assert_eq!(
+            next_occurrence(&bathroom(), local(2026, 6, 3, 12, 0)),
+            local(2026, 6, 6, 10, 0)
+        );
+    }
+
+    #[test]
+    fn next_occurrence_later_the_same_day() {
+        assert_eq!(
+            next_occurrence(&bathroom(), local(2026, 6, 6, 9, 59)),
+            local(2026, 6, 6, 10, 0)
+        );
+    }
+
+    #[test]
+    fn next_occurrence_at_the_trigger_moves_a_week_ahead() {
+        let next = next_occurrence(&bathroom(), local(2026, 6, 6, 10, 0));
+        assert_eq!(next, local(2026, 6, 13, 10, 0));
+        assert_eq!(next.minute(), 0);
+    }
+
+    #[test]
+    fn done_hands_the_chore_to_the_next_flatmate() {
+        let chore = bathroom();
+        let mut state = ChoreState::new(&chore, local(2026, 6, 6, 9, 0));
+        assert_eq!(state.assignee(&chore), 10);
+
+        state.mark_reminded(Some(42), local(2026, 6, 6, 10, 0));
+        assert_eq!(state.state, TaskState::Pending);
+        assert_eq!(state.next_trigger, local(2026, 6, 7, 10, 0));
+
+        assert!(state.mark_done(&chore, local(2026, 6, 6, 12, 0)));
+        assert_eq!(state.state, TaskState::Done);
+        assert_eq!(state.assignee(&chore), 20);
+        assert_eq!(state.message_id, None);
+        assert_eq!(state.next_trigger, local(2026, 6, 13, 10, 0));
+    }
+
+    #[test]
+    fn done_without_pending_reminder_is_ignored() {
+        let chore = bathroom();
+        let mut state = ChoreState::new(&chore, local(2026, 6, 6, 9, 0));
+        assert!(!state.mark_done(&chore, local(2026, 6, 6, 12, 0)));
+        assert_eq!(state.assignee(&chore), 10);
+    }
+
+    #[test]
+    fn cant_triggers_the_help_request_immediately() {
+        let chore = bathroom();
+        let mut state = ChoreState::new(&chore, local(2026, 6, 6, 9, 0));
+        state.mark_reminded(Some(42), local(2026, 6, 6, 10, 0));
+        assert!(state.mark_failed(local(2026, 6, 6, 11, 0)));
+        assert_eq!(state.state, TaskState::Failed);
+        assert_eq!(state.next_trigger, local(2026, 6, 6, 11, 0));
+
+        state.mark_handed_over(&chore, local(2026, 6, 6, 11, 0));
+        assert_eq!(state.state, TaskState::None);
+        assert_eq!(state.assignee(&chore), 20);
+        assert_eq!(state.next_trigger, local(2026, 6, 13, 10, 0));
+    }
+
+    #[test]
+    fn rotation_wraps_around() {
+        let chore = bathroom();
+        let mut state = ChoreState::new(&chore, local(2026, 6, 6, 9, 0));
+        for _ in 0..3 {
+            state.mark_handed_over(&chore, local(2026, 6, 6, 11, 0));
+        }
+        assert_eq!(state.assignee(&chore), 10);
+    }
+
+    #[test]
+    fn reload_reschedules_moved_chores_and_keeps_the_rest() {
+        let now = local(2026, 6, 3, 12, 0);
+        let plants = Chore {
+            id: "plants".to_string(),
+            weekday: Weekday::Wed,
+            hour: 18,
+            rotation: vec![20, 30],
+        };
+        let mut states = ChoreStates::new();
+        let mut bathroom_state = ChoreState::new(&bathroom(), now);
+        bathroom_state.turn = 1;
+        states.insert("bathroom".to_string(), bathroom_state);
+        states.insert("plants".to_string(), ChoreState::new(&plants, now));
+
+        let mut config = crate::config::test_config(vec![10, 20, 30]);
+        let dishes = Chore {
+            id: "dishes".to_string(),
+            weekday: Weekday::Mon,
+            hour: 20,
+            rotation: vec![10, 20],
+        };
+        config.chores = vec![
+            Chore {
+                weekday: Weekday::Sun,
+                hour: 11,
+                ..bathroom()
+            },
+            dishes.clone(),
+        ];
+        reload_chore_states(&config, &mut states, now);
+
+        assert_eq!(states.len(), 2);
+        assert_eq!(states["bathroom"].turn, 1);
+        assert_eq!(states["bathroom"].next_trigger, local(2026, 6, 7, 11, 0));
+        assert_eq!(states["dishes"], ChoreState::new(&dishes, now));
+    }
+}
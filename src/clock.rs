@@ -0,0 +1,58 @@
+use chrono::{Offset, TimeZone};
+
+/// Source of the current time, so time-driven behaviour can be tested without
+/// waiting for it. Times are in the configured timezone, whatever the host's
+/// is.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> chrono::DateTime<chrono_tz::Tz>;
+}
+
+/// `naive` as a time in `tz`. Of a time repeated when the clocks go back the
+/// first occurrence counts; a time skipped when they go forward is moved just
+/// as far past the end of the gap, so it still happens that day.
+pub fn local_time(
+    tz: chrono_tz::Tz,
+    naive: chrono::NaiveDateTime,
+) -> chrono::DateTime<chrono_tz::Tz> {
+    match tz.from_local_datetime(&naive) {
+        chrono::LocalResult::Single(dt) | chrono::LocalResult::Ambiguous(dt, _) => dt,
+        // The UTC offset from before the gap lands just as far past its end.
+        chrono::LocalResult::None => {
+            let before = tz.offset_from_utc_datetime(&(naive - chrono::Duration::days(1)));
+            tz.from_utc_datetime(&(naive - before.fix()))
+        }
+    }
+}
+
+/// The real wall clock, read in `tz`.
+pub struct SystemClock {
+    pub tz: chrono_tz::Tz,
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> chrono::DateTime<chrono_tz::Tz> {
+        chrono::Utc::now().with_timezone(&self.tz)
+    }
+}
+
+/// A clock that only moves when told to.
+#[cfg(test)]
+pub struct FakeClock(std::sync::Mutex<chrono::DateTime<chrono_tz::Tz>>);
+
+#[cfg(test)]
+impl FakeClock {
+    pub fn new(now: chrono::DateTime<chrono_tz::Tz>) -> Self {
+        FakeClock(std::sync::Mutex::new(now))
+    }
+
+    pub fn set(&self, now: chrono::DateTime<chrono_tz::Tz>) {
+        *self.0.lock().unwrap() = now;
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> chrono::DateTime<chrono_tz::Tz> {
+        *self.0.lock().unwrap()
+    }
+}
@@ -0,0 +1,966 @@
+use crate::chores::{self, Chore};
+use crate::data_grabber::fetch::CacheConfig;
+use crate::data_grabber::{self, Provider};
+use crate::email::EmailConfig;
+use crate::error::GstaldergeistError;
+use crate::escalation::{self, EscalationAction, EscalationPolicy, EscalationStep};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Where the configuration file is read from, unless `CONFIG_FILE` names
+/// another one.
+const DEFAULT_CONFIG_PATH: &str = "/data/gstaldergeist.toml";
+const DEFAULT_DATABASE_PATH: &str = "/data/gstaldergeist.db";
+const DEFAULT_CACHE_PATH: &str = "/data/cache";
+/// Collections are fetched twice a day, so a download is checked for changes
+/// at most once between them.
+const DEFAULT_CACHE_TTL_HOURS: u32 = 6;
+/// The flat is in Zurich, so that is where reminders are timed by default.
+const DEFAULT_TIMEZONE: chrono_tz::Tz = chrono_tz::Europe::Zurich;
+const DEFAULT_SMTP_PORT: u16 = 465;
+/// Providers used unless the configuration lists its own: the flat's, in
+/// collection region 19 of the We-Recycle calendar.
+const DEFAULT_PROVIDERS: &[(&str, &str)] = &[("adliswil", ""), ("we_recycle", "region = \"19\"")];
+/// How often the configuration file is checked for changes.
+const WATCH_INTERVAL_SECS: u64 = 5;
+
+#[derive(Clone)]
+pub struct Config {
+    pub flatmates: Vec<i64>,
+    pub global_channel_id: i64,
+    pub bot_token: String,
+    pub chores: Vec<Chore>,
+    pub escalation: EscalationPolicy,
+    /// Zone all hours and dates are meant in, independent of the host's.
+    pub timezone: chrono_tz::Tz,
+    pub database_path: PathBuf,
+    /// Where the providers' downloads are kept.
+    pub cache: CacheConfig,
+    /// Where the collection calendar is kept up to date for phones to
+    /// subscribe to, if anywhere.
+    pub calendar_path: Option<PathBuf>,
+    /// Without it, bags can't be ordered from We-Recycle.
+    pub email: Option<EmailConfig>,
+    /// Where the collection dates come from.
+    pub providers: Vec<Provider>,
+    /// Without it, no HTTP server is started.
+    pub http: Option<HttpConfig>,
+}
+
+/// Settings of the embedded HTTP server.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpConfig {
+    pub listen: std::net::SocketAddr,
+    /// Bearer token required to change anything through the API. Without
+    /// it, the API is read-only.
+    pub token: Option<String>,
+}
+
+/// The configuration file as written. Every setting is optional here, so that
+/// the environment variables can fill in or override any of them.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    timezone: Option<String>,
+    database_path: Option<PathBuf>,
+    calendar_path: Option<PathBuf>,
+    #[serde(default)]
+    telegram: TelegramSection,
+    #[serde(default)]
+    reminders: RemindersSection,
+    #[serde(default)]
+    email: EmailSection,
+    #[serde(default)]
+    http: HttpSection,
+    #[serde(default)]
+    cache: CacheSection,
+    /// Each table names a provider and holds its options.
+    providers: Option<Vec<toml::Table>>,
+    chores: Option<Vec<ChoreSection>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct TelegramSection {
+    bot_token: Option<String>,
+    channel_id: Option<i64>,
+    flatmates: Option<Vec<i64>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct RemindersSection {
+    hour: Option<u32>,
+    escalation: Option<Vec<StepSection>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StepSection {
+    hour: u32,
+    action: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct EmailSection {
+    smtp_server: Option<String>,
+    smtp_port: Option<u16>,
+    address: Option<String>,
+    password: Option<String>,
+    name: Option<String>,
+    household_address: Option<String>,
+    to: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct HttpSection {
+    listen: Option<String>,
+    token: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct CacheSection {
+    path: Option<PathBuf>,
+    ttl_hours: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChoreSection {
+    id: String,
+    weekday: String,
+    hour: u32,
+    rotation: Option<Vec<i64>>,
+}
+
+fn config_error(message: String) -> GstaldergeistError {
+    GstaldergeistError::ConfigError(message)
+}
+
+/// Path of the configuration file.
+pub fn config_path() -> PathBuf {
+    std::env::var_os("CONFIG_FILE")
+        .map_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH), PathBuf::from)
+}
+
+/// Read and check the configuration file at `path` together with the
+/// environment. The file is optional, as long as the environment provides
+/// what it leaves out.
+pub fn load(path: &Path) -> Result<Config, GstaldergeistError> {
+    let file = match std::fs::read_to_string(path) {
+        Ok(raw) => parse_file(&raw, path)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => ConfigFile::default(),
+        Err(e) => {
+            return Err(config_error(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            )));
+        }
+    };
+    from_sources(file, |name| std::env::var(name).ok())
+}
+
+fn parse_file(raw: &str, path: &Path) -> Result<ConfigFile, GstaldergeistError> {
+    toml::from_str(raw).map_err(|e| config_error(format!("{}: {}", path.display(), e)))
+}
+
+/// Parse a comma-separated list of Telegram chat ids, e.g. "123, 456, 789".
+fn parse_flatmates(raw: &str) -> Result<Vec<i64>, GstaldergeistError> {
+    raw.split(',')
+        .map(|s| {
+            let trimmed = s.trim();
+            trimmed.parse::<i64>().map_err(|_| {
+                config_error(format!(
+                    "TELEGRAM_FLATMATES must be a comma-separated list of numbers like \
+                     123,456,789, got '{}'",
+                    trimmed
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Parse an IANA timezone name, e.g. "Europe/Zurich".
+fn parse_timezone(raw: &str) -> Result<chrono_tz::Tz, GstaldergeistError> {
+    raw.trim().parse().map_err(|_| {
+        config_error(format!(
+            "timezone must be an IANA timezone name like Europe/Zurich, got '{}'",
+            raw.trim()
+        ))
+    })
+}
+
+/// Combine the file with the environment variables, which take precedence,
+/// and check the result.
+fn from_sources(
+    file: ConfigFile,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<Config, GstaldergeistError> {
+    let missing = |key: &str, var: &str| config_error(format!("{} (or {}) is not set", key, var));
+
+    let bot_token = env("TELEGRAM_BOT_TOKEN")
+        .or(file.telegram.bot_token)
+        .ok_or_else(|| missing("telegram.bot_token", "TELEGRAM_BOT_TOKEN"))?;
+    let global_channel_id = match env("TELEGRAM_CHANNEL_ID") {
+        Some(raw) => raw
+            .trim()
+            .parse()
+            .map_err(|_| config_error("TELEGRAM_CHANNEL_ID must be a number".to_string()))?,
+        None => file
+            .telegram
+            .channel_id
+            .ok_or_else(|| missing("telegram.channel_id", "TELEGRAM_CHANNEL_ID"))?,
+    };
+    let flatmates = match env("TELEGRAM_FLATMATES") {
+        Some(raw) => parse_flatmates(&raw)?,
+        None => file
+            .telegram
+            .flatmates
+            .ok_or_else(|| missing("telegram.flatmates", "TELEGRAM_FLATMATES"))?,
+    };
+    if flatmates.is_empty() {
+        return Err(config_error(
+            "telegram.flatmates must list at least one chat id".to_string(),
+        ));
+    }
+
+    // Chores besides the trash are optional.
+    let chores = match env("CHORES") {
+        Some(raw) => chores::parse_chores(&raw, &flatmates)?,
+        None => chores_from_file(file.chores.unwrap_or_default(), &flatmates)?,
+    };
+
+    let reminder_hour = file
+        .reminders
+        .hour
+        .unwrap_or(escalation::DEFAULT_REMINDER_HOUR);
+    if reminder_hour >= 24 {
+        return Err(config_error(format!(
+            "reminders.hour must be between 0 and 23, got {}",
+            reminder_hour
+        )));
+    }
+    let escalation = match (env("ESCALATION"), file.reminders.escalation) {
+        (Some(raw), _) => escalation::parse_escalation(&raw, reminder_hour)?,
+        (None, Some(steps)) => escalation_from_file(steps, reminder_hour)?,
+        // Without a ladder, the group is asked at 19:00 as it always was.
+        (None, None) => EscalationPolicy::new(reminder_hour, EscalationPolicy::default().steps)
+            .map_err(|_| {
+                config_error(format!(
+                    "reminders.hour {} is not before the default 19:00 escalation; \
+                         set reminders.escalation too",
+                    reminder_hour
+                ))
+            })?,
+    };
+
+    let timezone = match env("TIMEZONE").or(file.timezone) {
+        Some(raw) => parse_timezone(&raw)?,
+        None => DEFAULT_TIMEZONE,
+    };
+    let database_path = file
+        .database_path
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_DATABASE_PATH));
+    let ttl_hours = file.cache.ttl_hours.unwrap_or(DEFAULT_CACHE_TTL_HOURS);
+    let cache = CacheConfig {
+        path: file
+            .cache
+            .path
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_CACHE_PATH)),
+        ttl: chrono::Duration::hours(ttl_hours.into()),
+    };
+    let email = email_config(file.email, &env)?;
+    let http = http_config(file.http, &env)?;
+    let providers = match file.providers {
+        Some(tables) => providers_from_file(tables, timezone)?,
+        None => default_providers(timezone),
+    };
+
+    Ok(Config {
+        flatmates,
+        global_channel_id,
+        bot_token,
+        chores,
+        escalation,
+        timezone,
+        database_path,
+        cache,
+        calendar_path: file.calendar_path,
+        email,
+        providers,
+        http,
+    })
+}
+
+fn providers_from_file(
+    tables: Vec<toml::Table>,
+    timezone: chrono_tz::Tz,
+) -> Result<Vec<Provider>, GstaldergeistError> {
+    let mut providers = Vec::new();
+    for (index, mut options) in tables.into_iter().enumerate() {
+        let name = match options.remove("name") {
+            Some(toml::Value::String(name)) => name,
+            Some(_) => {
+                return Err(config_error(format!(
+                    "providers[{}].name must be a string",
+                    index
+                )));
+            }
+            None => {
+                return Err(config_error(format!("providers[{}] needs a name", index)));
+            }
+        };
+        // Collections are stored and synced by the name of their provider.
+        if providers.iter().any(|other: &Provider| other.name == name) {
+            return Err(config_error(format!(
+                "providers[{}]: the provider '{}' is listed twice",
+                index, name
+            )));
+        }
+        let provider = data_grabber::provider(&name, options, timezone).map_err(|reason| {
+            config_error(format!("providers[{}] ('{}'): {}", index, name, reason))
+        })?;
+        providers.push(provider);
+    }
+    Ok(providers)
+}
+
+fn default_providers(timezone: chrono_tz::Tz) -> Vec<Provider> {
+    DEFAULT_PROVIDERS
+        .iter()
+        .map(|(name, options)| {
+            let options = options.parse().expect("the default options are valid TOML");
+            data_grabber::provider(name, options, timezone)
+                .expect("the default providers are registered")
+        })
+        .collect()
+}
+
+fn chores_from_file(
+    sections: Vec<ChoreSection>,
+    flatmates: &[i64],
+) -> Result<Vec<Chore>, GstaldergeistError> {
+    let mut chores: Vec<Chore> = Vec::new();
+    for (index, section) in sections.into_iter().enumerate() {
+        let chore = chores::new_chore(
+            &section.id,
+            &section.weekday,
+            section.hour,
+            section.rotation,
+            flatmates,
+        )
+        .map_err(|reason| {
+            config_error(format!("chores[{}] ('{}'): {}", index, section.id, reason))
+        })?;
+        if chores.iter().any(|other| other.id == chore.id) {
+            return Err(config_error(format!(
+                "chores[{}]: the chore '{}' is listed twice",
+                index, chore.id
+            )));
+        }
+        chores.push(chore);
+    }
+    Ok(chores)
+}
+
+fn escalation_from_file(
+    sections: Vec<StepSection>,
+    reminder_hour: u32,
+) -> Result<EscalationPolicy, GstaldergeistError> {
+    let mut steps = Vec::new();
+    for (index, section) in sections.into_iter().enumerate() {
+        let invalid =
+            |reason: &str| config_error(format!("reminders.escalation[{}]: {}", index, reason));
+        if section.hour >= 24 {
+            return Err(invalid("the hour must be between 0 and 23"));
+        }
+        let action = EscalationAction::parse(&section.action).ok_or_else(|| {
+            invalid(&format!(
+                "unknown action '{}', expected nudge, group or next",
+                section.action
+            ))
+        })?;
+        steps.push(EscalationStep {
+            hour: section.hour,
+            action,
+        });
+    }
+    EscalationPolicy::new(reminder_hour, steps).map_err(|index| {
+        config_error(format!(
+            "reminders.escalation[{}]: steps must come after the {}:00 reminder, \
+             in increasing order",
+            index, reminder_hour
+        ))
+    })
+}
+
+/// The email settings, if any are given. Once one of them is, the others
+/// become required, except for the port.
+fn email_config(
+    file: EmailSection,
+    env: &impl Fn(&str) -> Option<String>,
+) -> Result<Option<EmailConfig>, GstaldergeistError> {
+    let smtp_server = env("EMAIL_SMTP_SERVER").or(file.smtp_server);
+    let address = env("EMAIL_ADDRESS").or(file.address);
+    let password = env("EMAIL_PASSWORD").or(file.password);
+    let name = env("EMAIL_NAME").or(file.name);
+    let household_address = env("ADDRESS").or(file.household_address);
+    let to = env("TO_EMAIL").or(file.to);
+    let settings = [
+        ("email.smtp_server", "EMAIL_SMTP_SERVER", &smtp_server),
+        ("email.address", "EMAIL_ADDRESS", &address),
+        ("email.password", "EMAIL_PASSWORD", &password),
+        ("email.name", "EMAIL_NAME", &name),
+        ("email.household_address", "ADDRESS", &household_address),
+        ("email.to", "TO_EMAIL", &to),
+    ];
+    if settings.iter().all(|(_, _, value)| value.is_none()) {
+        return Ok(None);
+    }
+    if let Some((key, var, _)) = settings.iter().find(|(_, _, value)| value.is_none()) {
+        return Err(config_error(format!(
+            "{} (or {}) is not set, but other email settings are",
+            key, var
+        )));
+    }
+    let address = address.unwrap_or_default();
+    Ok(Some(EmailConfig {
+        smtp_server: smtp_server.unwrap_or_default(),
+        smtp_port: file.smtp_port.unwrap_or(DEFAULT_SMTP_PORT),
+        username: address.clone(),
+        password: password.unwrap_or_default(),
+        from_email: address,
+        from_name: name.unwrap_or_default(),
+        address: household_address.unwrap_or_default(),
+        to_email: to.unwrap_or_default(),
+    }))
+}
+
+fn http_config(
+    file: HttpSection,
+    env: &impl Fn(&str) -> Option<String>,
+) -> Result<Option<HttpConfig>, GstaldergeistError> {
+    let token = env("HTTP_TOKEN").or(file.token);
+    let Some(listen) = env("HTTP_LISTEN").or(file.listen) else {
+        if token.is_some() {
+            return Err(config_error(
+                "http.listen (or HTTP_LISTEN) is not set, but http.token is".to_string(),
+            ));
+        }
+        return Ok(None);
+    };
+    let listen = listen.trim().parse().map_err(|_| {
+        config_error(format!(
+            "http.listen must be an address like 0.0.0.0:8080, got '{}'",
+            listen.trim()
+        ))
+    })?;
+    if token
+        .as_deref()
+        .is_some_and(|token| token.trim().is_empty())
+    {
+        return Err(config_error("http.token must not be empty".to_string()));
+    }
+    Ok(Some(HttpConfig { listen, token }))
+}
+
+/// The configuration in use. A reload swaps it as a whole, so a reader never
+/// sees half of an old and half of a new one.
+#[derive(Clone)]
+pub struct ConfigHandle(std::sync::Arc<std::sync::RwLock<std::sync::Arc<Config>>>);
+
+impl ConfigHandle {
+    pub fn new(config: Config) -> Self {
+        ConfigHandle(std::sync::Arc::new(std::sync::RwLock::new(
+            std::sync::Arc::new(config),
+        )))
+    }
+
+    pub fn current(&self) -> std::sync::Arc<Config> {
+        self.0.read().unwrap().clone()
+    }
+
+    fn replace(&self, config: Config) {
+        *self.0.write().unwrap() = std::sync::Arc::new(config);
+    }
+}
+
+/// `new`, except for the settings only read at startup, which are kept from
+/// `current` with a warning if they changed.
+fn keep_startup_settings(current: &Config, mut new: Config) -> Config {
+    if new.bot_token != current.bot_token {
+        tracing::warn!("telegram.bot_token changed; it takes effect after a restart");
+        new.bot_token = current.bot_token.clone();
+    }
+    if new.timezone != current.timezone {
+        tracing::warn!("timezone changed; it takes effect after a restart");
+        new.timezone = current.timezone;
+    }
+    if new.database_path != current.database_path {
+        tracing::warn!("database_path changed; it takes effect after a restart");
+        new.database_path = current.database_path.clone();
+    }
+    if new.cache != current.cache {
+        tracing::warn!("cache changed; it takes effect after a restart");
+        new.cache = current.cache.clone();
+    }
+    let listen = |config: &Config| config.http.as_ref().map(|http| http.listen);
+    if listen(&new) != listen(current) {
+        tracing::warn!("http.listen changed; it takes effect after a restart");
+        new.http = match (&current.http, new.http) {
+            (Some(current), Some(new)) => Some(HttpConfig {
+                listen: current.listen,
+                ..new
+            }),
+            (current, _) => current.clone(),
+        };
+    }
+    new
+}
+
+fn modified(path: &Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Reload `config` from `path` if the file was modified since
+/// `last_modified`. Returns whether the configuration was replaced; a file
+/// that fails to load is reported and otherwise ignored, so the last valid
+/// configuration stays in use.
+fn reload_if_changed(
+    path: &Path,
+    config: &ConfigHandle,
+    last_modified: &mut Option<std::time::SystemTime>,
+) -> bool {
+    let modified = modified(path);
+    if modified == *last_modified {
+        return false;
+    }
+    *last_modified = modified;
+    match load(path) {
+        Ok(new) => {
+            config.replace(keep_startup_settings(&config.current(), new));
+            tracing::info!("Reloaded the configuration from {}", path.display());
+            true
+        }
+        Err(e) => {
+            tracing::error!("Keeping the previous configuration: {}", e);
+            false
+        }
+    }
+}
+
+/// Reload the configuration whenever the file at `path` changes, and wake the
+/// scheduler so it picks up new reminder hours and chores.
+pub async fn watch(
+    path: PathBuf,
+    config: ConfigHandle,
+    wakeup: std::sync::Arc<tokio::sync::Notify>,
+) {
+    let mut last_modified = modified(&path);
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(WATCH_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        if reload_if_changed(&path, &config, &mut last_modified) {
+            wakeup.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+pub fn test_config(flatmates: Vec<i64>) -> Config {
+    Config {
+        flatmates,
+        global_channel_id: -1,
+        bot_token: String::new(),
+        chores: Vec::new(),
+        escalation: EscalationPolicy::default(),
+        timezone: DEFAULT_TIMEZONE,
+        database_path: PathBuf::from(DEFAULT_DATABASE_PATH),
+        cache: CacheConfig {
+            path: PathBuf::from(DEFAULT_CACHE_PATH),
+            ttl: chrono::Duration::hours(DEFAULT_CACHE_TTL_HOURS.into()),
+        },
+        calendar_path: None,
+        email: None,
+        providers: default_providers(DEFAULT_TIMEZONE),
+        http: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const FULL: &str = r#"
+        timezone = "America/New_York"
+        database_path = "/tmp/geist.db"
+        calendar_path = "/tmp/geist.ics"
+
+        [telegram]
+        bot_token = "token"
+        channel_id = -100
+        flatmates = [10, 20, 30]
+
+        [reminders]
+        hour = 17
+        escalation = [
+            { hour = 18, action = "nudge" },
+            { hour = 21, action = "group" },
+        ]
+
+        [email]
+        smtp_server = "smtp.example.com"
+        smtp_port = 587
+        address = "geist@example.com"
+        password = "secret"
+        name = "Gstalder Geist"
+        household_address = "Gstalderstreet 1"
+        to = "bags@example.com"
+
+        [http]
+        listen = "127.0.0.1:8080"
+        token = "kitchen"
+
+        [cache]
+        path = "/tmp/geist-cache"
+        ttl_hours = 0
+
+        [[providers]]
+        name = "adliswil"
+        municipality = "thalwil"
+
+        [[providers]]
+        name = "we_recycle"
+        region = "7"
+
+        [[chores]]
+        id = "bathroom"
+        weekday = "sat"
+        hour = 10
+
+        [[chores]]
+        id = "plants"
+        weekday = "wed"
+        hour = 18
+        rotation = [20, 30]
+    "#;
+
+    fn load_with(raw: &str, env: &[(&str, &str)]) -> Result<Config, GstaldergeistError> {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        from_sources(parse_file(raw, Path::new("test.toml"))?, |name| {
+            env.get(name).cloned()
+        })
+    }
+
+    fn error_message(raw: &str, env: &[(&str, &str)]) -> String {
+        match load_with(raw, env) {
+            Err(GstaldergeistError::ConfigError(message)) => message,
+            Err(e) => panic!("expected a configuration error, got {}", e),
+            Ok(_) => panic!("expected a configuration error"),
+        }
+    }
+
+    #[test]
+    fn reads_every_setting_from_the_file() {
+        let config = load_with(FULL, &[]).unwrap();
+        assert_eq!(config.bot_token, "token");
+        assert_eq!(config.global_channel_id, -100);
+        assert_eq!(config.flatmates, vec![10, 20, 30]);
+        assert_eq!(config.timezone, chrono_tz::America::New_York);
+        assert_eq!(config.database_path, PathBuf::from("/tmp/geist.db"));
+        assert_eq!(config.cache.path, PathBuf::from("/tmp/geist-cache"));
+        assert_eq!(config.cache.ttl, chrono::Duration::zero());
+        assert_eq!(config.calendar_path, Some(PathBuf::from("/tmp/geist.ics")));
+        assert_eq!(config.escalation.reminder_hour, 17);
+        assert_eq!(config.escalation.first_group_hour(), Some(21));
+        let names: Vec<&str> = config.providers.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["adliswil", "we_recycle"]);
+        let email = config.email.unwrap();
+        assert_eq!(email.smtp_port, 587);
+        assert_eq!(email.from_email, "geist@example.com");
+        assert_eq!(email.address, "Gstalderstreet 1");
+        let http = config.http.unwrap();
+        assert_eq!(http.listen, "127.0.0.1:8080".parse().unwrap());
+        assert_eq!(http.token.as_deref(), Some("kitchen"));
+        assert_eq!(config.chores.len(), 2);
+        assert_eq!(config.chores[0].rotation, vec![10, 20, 30]);
+        assert_eq!(config.chores[1].rotation, vec![20, 30]);
+    }
+
+    #[test]
+    fn environment_alone_is_enough_and_defaults_fill_the_rest() {
+        let config = load_with(
+            "",
+            &[
+                ("TELEGRAM_BOT_TOKEN", "token"),
+                ("TELEGRAM_CHANNEL_ID", "-100"),
+                ("TELEGRAM_FLATMATES", "10, 20"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(config.flatmates, vec![10, 20]);
+        assert_eq!(config.escalation, EscalationPolicy::default());
+        assert_eq!(config.timezone, DEFAULT_TIMEZONE);
+        assert_eq!(config.database_path, PathBuf::from(DEFAULT_DATABASE_PATH));
+        assert_eq!(config.cache.ttl, chrono::Duration::hours(6));
+        assert!(config.calendar_path.is_none());
+        let names: Vec<&str> = config.providers.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["adliswil", "we_recycle"]);
+        assert!(config.email.is_none());
+        assert!(config.http.is_none());
+        assert!(config.chores.is_empty());
+    }
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let config = load_with(
+            FULL,
+            &[
+                ("TELEGRAM_FLATMATES", "40, 50"),
+                ("ESCALATION", "22:next"),
+                ("CHORES", "dishes:mon:20"),
+                ("TIMEZONE", "Europe/Zurich"),
+                ("EMAIL_PASSWORD", "other"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(config.flatmates, vec![40, 50]);
+        assert_eq!(config.escalation.steps.len(), 1);
+        assert_eq!(config.chores[0].id, "dishes");
+        assert_eq!(config.chores[0].rotation, vec![40, 50]);
+        assert_eq!(config.timezone, chrono_tz::Europe::Zurich);
+        assert!(config.email.unwrap().password == "other");
+    }
+
+    #[test]
+    fn parses_iana_timezones() {
+        assert_eq!(
+            parse_timezone(" Europe/Zurich ").unwrap(),
+            chrono_tz::Europe::Zurich
+        );
+        assert_eq!(parse_timezone("UTC").unwrap(), chrono_tz::UTC);
+        assert!(matches!(
+            parse_timezone("Mars/Olympus_Mons"),
+            Err(GstaldergeistError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn parse_flatmates_single_value() {
+        assert_eq!(parse_flatmates("123").unwrap(), vec![123]);
+    }
+
+    #[test]
+    fn parse_flatmates_multiple_values() {
+        assert_eq!(parse_flatmates("123,456,789").unwrap(), vec![123, 456, 789]);
+    }
+
+    #[test]
+    fn parse_flatmates_trims_whitespace() {
+        assert_eq!(
+            parse_flatmates(" 123 , 456 ,789 ").unwrap(),
+            vec![123, 456, 789]
+        );
+    }
+
+    #[test]
+    fn parse_flatmates_accepts_negative_ids() {
+        // Telegram group/channel chat ids are negative.
+        assert_eq!(
+            parse_flatmates("-1001234567890,42").unwrap(),
+            vec![-1001234567890, 42]
+        );
+    }
+
+    #[test]
+    fn parse_flatmates_rejects_non_numeric() {
+        let err = parse_flatmates("123,abc,789").unwrap_err();
+        assert!(matches!(err, GstaldergeistError::ConfigError(_)));
+    }
+
+    #[test]
+    fn parse_flatmates_rejects_empty_string() {
+        assert!(parse_flatmates("").is_err());
+    }
+
+    #[test]
+    fn errors_name_the_offending_setting() {
+        let telegram = "[telegram]\nbot_token = \"t\"\nchannel_id = 1\nflatmates = [10]\n";
+        let cases = [
+            ("", "telegram.bot_token (or TELEGRAM_BOT_TOKEN) is not set"),
+            (
+                "[telegram]\nbot_token = \"t\"\nchannel_id = 1\nflatmates = []\n",
+                "telegram.flatmates must list at least one chat id",
+            ),
+            (
+                &format!("{}[reminders]\nhour = 24\n", telegram),
+                "reminders.hour must be between 0 and 23, got 24",
+            ),
+            (
+                &format!("{}[reminders]\nhour = 20\n", telegram),
+                "reminders.hour 20 is not before the default 19:00 escalation",
+            ),
+            (
+                &format!(
+                    "{}[reminders]\nescalation = [{{ hour = 18, action = \"shout\" }}]\n",
+                    telegram
+                ),
+                "reminders.escalation[0]: unknown action 'shout'",
+            ),
+            (
+                &format!(
+                    "{}[reminders]\nescalation = [{{ hour = 20, action = \"nudge\" }}, \
+                     {{ hour = 19, action = \"group\" }}]\n",
+                    telegram
+                ),
+                "reminders.escalation[1]: steps must come after the 16:00 reminder",
+            ),
+            (
+                &format!(
+                    "{}[[chores]]\nid = \"bath room\"\nweekday = \"sat\"\nhour = 10\n",
+                    telegram
+                ),
+                "chores[0] ('bath room'): the id may only contain",
+            ),
+            (
+                &format!("{}[[providers]]\nname = \"zurich\"\n", telegram),
+                "providers[0] ('zurich'): unknown provider 'zurich', expected one of \
+                 adliswil, ics, we_recycle",
+            ),
+            (
+                &format!("{}[[providers]]\nregion = \"19\"\n", telegram),
+                "providers[0] needs a name",
+            ),
+            (
+                &format!(
+                    "{}[[providers]]\nname = \"we_recycle\"\nregoin = \"19\"\n",
+                    telegram
+                ),
+                "providers[0] ('we_recycle'): unknown field `regoin`, expected `region`",
+            ),
+            (
+                &format!(
+                    "{}[[providers]]\nname = \"we_recycle\"\nregion = \"nineteen\"\n",
+                    telegram
+                ),
+                "providers[0] ('we_recycle'): region must be the number",
+            ),
+            (
+                &format!(
+                    "{}[[providers]]\nname = \"adliswil\"\n\
+                     [[providers]]\nname = \"adliswil\"\nmunicipality = \"thalwil\"\n",
+                    telegram
+                ),
+                "providers[1]: the provider 'adliswil' is listed twice",
+            ),
+            (
+                &format!("{}[email]\nsmtp_server = \"smtp\"\n", telegram),
+                "email.address (or EMAIL_ADDRESS) is not set, but other email settings are",
+            ),
+            (
+                &format!("{}[http]\nlisten = \"8080\"\n", telegram),
+                "http.listen must be an address like 0.0.0.0:8080, got '8080'",
+            ),
+            (
+                &format!("{}[http]\ntoken = \"secret\"\n", telegram),
+                "http.listen (or HTTP_LISTEN) is not set, but http.token is",
+            ),
+            (
+                &format!("timezone = \"Mars/Olympus_Mons\"\n{}", telegram),
+                "timezone must be an IANA timezone name",
+            ),
+        ];
+        for (raw, expected) in cases {
+            let message = error_message(raw, &[]);
+            assert!(
+                message.contains(expected),
+                "'{}' should contain '{}'",
+                message,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_files_report_the_file_and_key() {
+        let message = error_message("[telegram]\nchannel_id = \"not a number\"\n", &[]);
+        assert!(message.starts_with("test.toml: "), "{}", message);
+        assert!(message.contains("channel_id"), "{}", message);
+
+        let message = error_message("[telegram]\nbot_tokn = \"t\"\n", &[]);
+        assert!(message.contains("unknown field `bot_tokn`"), "{}", message);
+    }
+
+    #[test]
+    fn reload_keeps_the_settings_read_at_startup() {
+        let current = load_with(FULL, &[]).unwrap();
+        let changed = FULL
+            .replace("bot_token = \"token\"", "bot_token = \"other\"")
+            .replace("America/New_York", "Europe/Zurich")
+            .replace("hour = 17", "hour = 15")
+            .replace("127.0.0.1:8080", "0.0.0.0:80")
+            .replace("token = \"kitchen\"", "token = \"hallway\"")
+            .replace("/tmp/geist-cache", "/tmp/other-cache");
+        let reloaded = keep_startup_settings(&current, load_with(&changed, &[]).unwrap());
+        assert_eq!(reloaded.bot_token, "token");
+        assert_eq!(reloaded.timezone, chrono_tz::America::New_York);
+        assert_eq!(reloaded.escalation.reminder_hour, 15);
+        assert_eq!(reloaded.cache.path, PathBuf::from("/tmp/geist-cache"));
+        let http = reloaded.http.unwrap();
+        assert_eq!(http.listen, "127.0.0.1:8080".parse().unwrap());
+        assert_eq!(http.token.as_deref(), Some("hallway"));
+    }
+
+    #[test]
+    fn reloads_a_changed_file_and_ignores_a_broken_one() {
+        let path =
+            std::env::temp_dir().join(format!("gstaldergeist-config-{}.toml", std::process::id()));
+        // Set the modification time explicitly: some filesystems only keep
+        // whole seconds of it.
+        let write = |raw: &str, seconds: u64| {
+            std::fs::write(&path, raw).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds))
+                .unwrap();
+        };
+        let env = [
+            "TELEGRAM_BOT_TOKEN",
+            "TELEGRAM_CHANNEL_ID",
+            "TELEGRAM_FLATMATES",
+            "CHORES",
+            "ESCALATION",
+            "TIMEZONE",
+        ];
+        if env.iter().any(|name| std::env::var_os(name).is_some()) {
+            // The environment would override the file under test.
+            return;
+        }
+        write(FULL, 1_000);
+        let config = ConfigHandle::new(load(&path).unwrap());
+        let mut last_modified = modified(&path);
+        assert!(!reload_if_changed(&path, &config, &mut last_modified));
+
+        write(&FULL.replace("hour = 17", "hour = 15"), 2_000);
+        assert!(reload_if_changed(&path, &config, &mut last_modified));
+        assert_eq!(config.current().escalation.reminder_hour, 15);
+
+        write("[telegram\n", 3_000);
+        assert!(!reload_if_changed(&path, &config, &mut last_modified));
+        assert_eq!(config.current().escalation.reminder_hour, 15);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
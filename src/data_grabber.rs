@@ -1,5 +1,9 @@
 mod adliswil;
-mod we_recycle;
+pub mod fetch;
+mod ics;
+#[cfg(test)]
+mod stub_server;
+pub mod we_recycle;
 use chrono::{Datelike, NaiveDate};
 use core::fmt;
 
@@ -8,7 +12,7 @@ use async_trait::async_trait;
 use serde::Deserialize;
 use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TrashType {
     WeRecycle,
     Normal,
@@ -17,6 +21,22 @@ pub enum TrashType {
     Paper,
 }
 
+impl TrashType {
+    /// Names of the trash types in the configuration.
+    pub const NAMES: &[&str] = &["we_recycle", "normal", "bio", "cardboard", "paper"];
+
+    pub fn from_name(name: &str) -> Option<TrashType> {
+        match name {
+            "we_recycle" => Some(TrashType::WeRecycle),
+            "normal" => Some(TrashType::Normal),
+            "bio" => Some(TrashType::Bio),
+            "cardboard" => Some(TrashType::Cardboard),
+            "paper" => Some(TrashType::Paper),
+            _ => None,
+        }
+    }
+}
+
 // impl TrashType from rusqlite
 impl rusqlite::types::FromSql for TrashType {
     fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
@@ -55,6 +75,10 @@ struct ChatInfo {
     title: String,
 }
 
+/// A source of collection dates, e.g. a municipality's waste calendar.
+///
+/// Supporting another municipality means implementing this trait and adding
+/// the implementation to [`PROVIDERS`].
 #[async_trait]
 pub trait WasteGrabber: Send + Sync {
     async fn get_trashes(
@@ -62,6 +86,113 @@ pub trait WasteGrabber: Send + Sync {
         from: NaiveDate,
         to: NaiveDate,
     ) -> Result<HashMap<NaiveDate, Vec<TrashType>>, GstaldergeistError>;
+
+    /// The last day `get_trashes(from, to)` reports on: `to`, unless the
+    /// provider gets the days after it in the same request anyway.
+    fn fetches_until(&self, to: NaiveDate) -> NaiveDate {
+        to
+    }
+}
+
+/// Builds a provider from its options in the configuration, i.e. its
+/// `[[providers]]` table without the name, and the configured timezone. The
+/// error explains what is wrong with the options.
+pub type ProviderFactory = fn(toml::Table, chrono_tz::Tz) -> Result<Box<dyn WasteGrabber>, String>;
+
+/// Every provider, by the name it is enabled with in the configuration.
+const PROVIDERS: &[(&str, ProviderFactory)] = &[
+    ("adliswil", adliswil::AdliswilWasteGrabber::from_options),
+    ("ics", ics::IcsWasteGrabber::from_options),
+    (
+        "we_recycle",
+        we_recycle::WeRecycleWasteGrabber::from_options,
+    ),
+];
+
+/// How long a provider gets to report the collections, and how often it is
+/// asked before giving up on it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FetchPolicy {
+    pub timeout: std::time::Duration,
+    /// Tries in all, at least one.
+    pub attempts: u32,
+    /// Pause after the first failed try, doubled after each further one.
+    pub retry_delay: std::time::Duration,
+}
+
+impl Default for FetchPolicy {
+    fn default() -> Self {
+        FetchPolicy {
+            timeout: std::time::Duration::from_secs(30),
+            attempts: 3,
+            retry_delay: std::time::Duration::from_secs(5),
+        }
+    }
+}
+
+/// Take the fetch policy out of a provider's `options`, which every
+/// provider accepts besides its own.
+fn fetch_policy(options: &mut toml::Table) -> Result<FetchPolicy, String> {
+    let mut take = |key: &str, min: i64| match options.remove(key) {
+        None => Ok(None),
+        Some(toml::Value::Integer(value)) if value >= min => Ok(Some(value as u64)),
+        Some(_) => Err(format!(
+            "{} must be a whole number of at least {}",
+            key, min
+        )),
+    };
+    let mut policy = FetchPolicy::default();
+    if let Some(secs) = take("timeout_secs", 1)? {
+        policy.timeout = std::time::Duration::from_secs(secs);
+    }
+    if let Some(attempts) = take("attempts", 1)? {
+        policy.attempts = u32::try_from(attempts).unwrap_or(u32::MAX);
+    }
+    if let Some(secs) = take("retry_delay_secs", 0)? {
+        policy.retry_delay = std::time::Duration::from_secs(secs);
+    }
+    Ok(policy)
+}
+
+/// An enabled provider.
+#[derive(Clone)]
+pub struct Provider {
+    pub name: String,
+    pub grabber: std::sync::Arc<dyn WasteGrabber>,
+    pub policy: FetchPolicy,
+}
+
+/// Construct the provider registered as `name` with `options`, for a
+/// household in `timezone`.
+pub fn provider(
+    name: &str,
+    mut options: toml::Table,
+    timezone: chrono_tz::Tz,
+) -> Result<Provider, String> {
+    let (_, factory) = PROVIDERS
+        .iter()
+        .find(|(registered, _)| *registered == name)
+        .ok_or_else(|| {
+            let known: Vec<&str> = PROVIDERS.iter().map(|(name, _)| *name).collect();
+            format!(
+                "unknown provider '{}', expected one of {}",
+                name,
+                known.join(", ")
+            )
+        })?;
+    let policy = fetch_policy(&mut options)?;
+    Ok(Provider {
+        name: name.to_string(),
+        grabber: factory(options, timezone)?.into(),
+        policy,
+    })
+}
+
+/// Deserialize a provider's options, rejecting any it does not know.
+fn provider_options<T: serde::de::DeserializeOwned>(options: toml::Table) -> Result<T, String> {
+    toml::Value::Table(options)
+        .try_into()
+        .map_err(|e: toml::de::Error| e.message().to_string())
 }
 
 impl fmt::Display for TrashType {
@@ -78,20 +209,75 @@ impl fmt::Display for TrashType {
 
 #[derive(Debug)]
 pub struct TrashesSchedule {
+    /// Day the schedule was collected on; "tomorrow" in the messages about it
+    /// is the day after.
+    pub today: NaiveDate,
     pub dates: HashMap<NaiveDate, Vec<TrashType>>,
+    /// What each provider reported.
+    pub by_provider: Vec<ProviderTrashes>,
     pub tomorrow_master_name: String,
     pub tomorrow_master_id: i64,
+    /// Flatmates away during the coming week, with the end of their absence
+    /// if known.
+    pub away: Vec<(String, Option<NaiveDate>)>,
+    /// When the dates were fetched, if they come from the database because
+    /// the providers could not be reached.
+    pub cached: Option<chrono::DateTime<chrono_tz::Tz>>,
+    /// The providers that could not be reached, whose collections are
+    /// missing from `dates`.
+    pub failed: Vec<ProviderError>,
+}
+
+/// The collections one provider reported for the days after the day they
+/// were fetched on until `until`.
+#[derive(Debug)]
+pub struct ProviderTrashes {
+    pub name: String,
+    pub until: NaiveDate,
+    pub dates: HashMap<NaiveDate, Vec<TrashType>>,
+}
+
+/// A provider that could not be reached, even after retrying.
+#[derive(Debug)]
+pub struct ProviderError {
+    pub name: String,
+    pub error: GstaldergeistError,
+}
+
+impl TrashesSchedule {
+    /// Trashes collected on the morning after `today`.
+    pub fn tomorrow_trashes(&self) -> Option<&Vec<TrashType>> {
+        self.dates.get(&(self.today + chrono::Duration::days(1)))
+    }
 }
 
 /// Selects the flatmate on trash duty for `date`. Duty rotates by one slot per
 /// ISO week, wrapping around the list of flatmates.
-fn food_master_id(flatmates: &[i64], date: NaiveDate) -> i64 {
+///
+/// This was the rotation before the roster existed; it is now only used to
+/// seed the roster so the switch does not change who is on duty.
+pub fn food_master_id(flatmates: &[i64], date: NaiveDate) -> i64 {
     flatmates[(1 + date.iso_week().week0() as usize) % flatmates.len()]
 }
 
-fn tomorrow_food_master_id(config: &super::Config) -> i64 {
-    let tomorrow = chrono::Local::now().naive_local().date() + chrono::Duration::days(1);
-    food_master_id(&config.flatmates, tomorrow)
+fn tomorrow_food_master_id(
+    config: &super::Config,
+    today: NaiveDate,
+) -> Result<i64, GstaldergeistError> {
+    let tomorrow = today + chrono::Duration::days(1);
+    crate::roster::flatmate_on_duty(&config.flatmates, tomorrow)
+}
+
+fn flatmates_away_next_week(
+    config: &super::Config,
+    today: NaiveDate,
+) -> Result<Vec<(i64, Option<NaiveDate>)>, GstaldergeistError> {
+    let tomorrow = today + chrono::Duration::days(1);
+    crate::roster::flatmates_away(
+        &config.flatmates,
+        tomorrow,
+        tomorrow + chrono::Duration::days(6),
+    )
 }
 
 const FOOD_MASTER_TITLE_PREFIX_LEN: usize = 17;
@@ -101,11 +287,10 @@ fn food_master_name_from_title(title: &str) -> String {
     title.chars().skip(FOOD_MASTER_TITLE_PREFIX_LEN).collect()
 }
 
-pub async fn grab_tomorrow_food_master_name(config: &super::Config) -> String {
+pub async fn grab_food_master_name(config: &super::Config, chat_id: i64) -> String {
     let client = reqwest::Client::new();
 
     let bot_token = &config.bot_token;
-    let chat_id = tomorrow_food_master_id(config);
 
     let url = format!(
         "https://api.telegram.org/bot{}/getChat?chat_id={}",
@@ -122,36 +307,245 @@ pub async fn grab_tomorrow_food_master_name(config: &super::Config) -> String {
     }
 }
 
+/// Ask `provider` for the collections after `from` until `to`, trying
+/// again as its policy says.
+async fn fetch_provider(
+    provider: &Provider,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<HashMap<NaiveDate, Vec<TrashType>>, GstaldergeistError> {
+    let policy = &provider.policy;
+    let mut delay = policy.retry_delay;
+    let mut attempt = 1;
+    loop {
+        let fetch = provider.grabber.get_trashes(from, to);
+        let trashes = match tokio::time::timeout(policy.timeout, fetch).await {
+            Ok(trashes) => trashes,
+            Err(_) => Err(GstaldergeistError::Other(format!(
+                "no answer within {:?}",
+                policy.timeout
+            ))),
+        };
+        crate::metrics::provider_fetched(&provider.name, trashes.is_err());
+        match trashes {
+            Ok(trashes) => return Ok(trashes),
+            Err(e) if attempt >= policy.attempts => return Err(e),
+            Err(e) => {
+                tracing::warn!(
+                    "Provider {} failed (attempt {}/{}): {}; retrying in {:?}",
+                    provider.name,
+                    attempt,
+                    policy.attempts,
+                    e,
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Ask every provider at once for the collections after `from` until `to`.
+/// Returns what the providers reported and, separately, those that failed.
+async fn fetch_providers(
+    providers: &[Provider],
+    from: NaiveDate,
+    to: NaiveDate,
+) -> (Vec<ProviderTrashes>, Vec<ProviderError>) {
+    let fetches: Vec<_> = providers
+        .iter()
+        .map(|provider| {
+            let provider = provider.clone();
+            tokio::spawn(async move { fetch_provider(&provider, from, to).await })
+        })
+        .collect();
+    let mut by_provider = Vec::new();
+    let mut failed = Vec::new();
+    for (provider, fetch) in providers.iter().zip(fetches) {
+        let trashes = fetch.await.unwrap_or_else(|e| {
+            Err(GstaldergeistError::Other(format!(
+                "the fetch crashed: {}",
+                e
+            )))
+        });
+        match trashes {
+            Ok(dates) => by_provider.push(ProviderTrashes {
+                name: provider.name.clone(),
+                until: provider.grabber.fetches_until(to),
+                dates,
+            }),
+            Err(error) => {
+                tracing::warn!("Provider {} failed: {}", provider.name, error);
+                failed.push(ProviderError {
+                    name: provider.name.clone(),
+                    error,
+                });
+            }
+        }
+    }
+    (by_provider, failed)
+}
+
+/// Collect the trashes from `today` until `to`, together with tomorrow's
+/// food master. The providers that fail are listed in the schedule and
+/// their collections left out; only if all of them fail is it an error.
 pub async fn get_trashes(
     config: &super::Config,
-    from: NaiveDate,
+    today: NaiveDate,
     to: NaiveDate,
 ) -> Result<TrashesSchedule, GstaldergeistError> {
-    // unfortunately traits do not implement async
-    let adliswil_grabber = adliswil::AdliswilWasteGrabber {};
-    let we_recycle_grabber = we_recycle::WeRecycleWasteGrabber {};
+    let (by_provider, failed) = fetch_providers(&config.providers, today, to).await;
+    if by_provider.is_empty() && !failed.is_empty() {
+        let errors: Vec<String> = failed
+            .iter()
+            .map(|failure| format!("{}: {}", failure.name, failure.error))
+            .collect();
+        return Err(GstaldergeistError::Other(errors.join("; ")));
+    }
+    let mut dates: HashMap<NaiveDate, Vec<TrashType>> = HashMap::new();
+    for provider in &by_provider {
+        for (date, trash) in &provider.dates {
+            dates.entry(*date).or_default().extend(trash);
+        }
+    }
+    let mut schedule = schedule(config, today, dates, by_provider, None).await?;
+    schedule.failed = failed;
+    Ok(schedule)
+}
 
-    let grabbers: Vec<Box<dyn WasteGrabber>> =
-        vec![Box::new(adliswil_grabber), Box::new(we_recycle_grabber)];
+/// The stored dates are only relied on if every provider was synced this
+/// recently.
+const MAX_CACHE_AGE_DAYS: i64 = 7;
 
-    let mut dates: HashMap<NaiveDate, Vec<TrashType>> = HashMap::new();
-    for grabber in grabbers {
-        for (date, trash) in grabber.get_trashes(from, to).await? {
-            dates.entry(date).or_default().extend(trash);
+/// The schedule as of `now` from the collections stored in the database, or
+/// `None` if it is not fresh enough: every provider must have been synced in
+/// the last week, up to tomorrow at least.
+pub async fn cached_schedule(
+    config: &super::Config,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) -> Result<Option<TrashesSchedule>, GstaldergeistError> {
+    let today = now.date_naive();
+    let syncs = crate::database::load_trash_syncs(now.timezone())?;
+    let Some(fetched_at) = cache_time(config, &syncs, now) else {
+        return Ok(None);
+    };
+    let dates = crate::database::get_all_trashes()?
+        .into_iter()
+        .filter(|(date, _)| *date > today)
+        .collect();
+    Ok(Some(
+        schedule(config, today, dates, Vec::new(), Some(fetched_at)).await?,
+    ))
+}
+
+/// When the oldest of the providers' syncs happened, if each of them is
+/// fresh enough to rely on as of `now`.
+fn cache_time(
+    config: &super::Config,
+    syncs: &HashMap<String, (chrono::DateTime<chrono_tz::Tz>, NaiveDate)>,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+    let tomorrow = now.date_naive() + chrono::Duration::days(1);
+    let mut oldest: Option<chrono::DateTime<chrono_tz::Tz>> = None;
+    for provider in &config.providers {
+        let (synced_at, until) = syncs.get(&provider.name)?;
+        if now - *synced_at > chrono::Duration::days(MAX_CACHE_AGE_DAYS) || *until < tomorrow {
+            return None;
+        }
+        if oldest.is_none_or(|oldest| *synced_at < oldest) {
+            oldest = Some(*synced_at);
         }
     }
+    oldest
+}
 
+/// The schedule of `dates` as seen on `today`, with tomorrow's food master.
+async fn schedule(
+    config: &super::Config,
+    today: NaiveDate,
+    dates: HashMap<NaiveDate, Vec<TrashType>>,
+    by_provider: Vec<ProviderTrashes>,
+    cached: Option<chrono::DateTime<chrono_tz::Tz>>,
+) -> Result<TrashesSchedule, GstaldergeistError> {
+    let tomorrow_master_id = tomorrow_food_master_id(config, today)?;
+    let mut away = Vec::new();
+    for (chat_id, until) in flatmates_away_next_week(config, today)? {
+        away.push((grab_food_master_name(config, chat_id).await, until));
+    }
     Ok(TrashesSchedule {
+        today,
         dates,
-        tomorrow_master_name: grab_tomorrow_food_master_name(config).await,
-        tomorrow_master_id: tomorrow_food_master_id(config),
+        by_provider,
+        tomorrow_master_name: grab_food_master_name(config, tomorrow_master_id).await,
+        tomorrow_master_id,
+        away,
+        cached,
+        failed: Vec::new(),
     })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{food_master_id, food_master_name_from_title};
-    use chrono::NaiveDate;
+    use super::{
+        FetchPolicy, Provider, TrashType, WasteGrabber, cache_time, fetch_providers,
+        food_master_id, food_master_name_from_title, provider,
+    };
+    use crate::error::GstaldergeistError;
+    use chrono::{NaiveDate, TimeZone};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    const TZ: chrono_tz::Tz = chrono_tz::Europe::Zurich;
+
+    /// Reports Normal trash on 2026-06-08 after `delay`, failing the first
+    /// `failures` times it is asked.
+    struct Stub {
+        delay: Duration,
+        failures: AtomicU32,
+    }
+
+    #[async_trait::async_trait]
+    impl WasteGrabber for Stub {
+        async fn get_trashes(
+            &self,
+            _from: NaiveDate,
+            _to: NaiveDate,
+        ) -> Result<HashMap<NaiveDate, Vec<TrashType>>, GstaldergeistError> {
+            tokio::time::sleep(self.delay).await;
+            let failing = self
+                .failures
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                    left.checked_sub(1)
+                })
+                .is_ok();
+            if failing {
+                return Err(GstaldergeistError::Other("server error".to_string()));
+            }
+            Ok(HashMap::from([(date(2026, 6, 8), vec![TrashType::Normal])]))
+        }
+    }
+
+    fn stub(name: &str, delay_ms: u64, failures: u32, attempts: u32) -> Provider {
+        Provider {
+            name: name.to_string(),
+            grabber: std::sync::Arc::new(Stub {
+                delay: Duration::from_millis(delay_ms),
+                failures: AtomicU32::new(failures),
+            }),
+            policy: FetchPolicy {
+                timeout: Duration::from_millis(500),
+                attempts,
+                retry_delay: Duration::from_millis(1),
+            },
+        }
+    }
+
+    fn names<T>(items: &[T], name: impl Fn(&T) -> &str) -> Vec<&str> {
+        items.iter().map(name).collect()
+    }
 
     fn date(y: i32, m: u32, d: u32) -> NaiveDate {
         NaiveDate::from_ymd_opt(y, m, d).unwrap()
@@ -219,4 +613,143 @@ mod tests {
         assert_eq!(food_master_id(&flatmates, date(2024, 1, 1)), 42);
         assert_eq!(food_master_id(&flatmates, date(2024, 6, 30)), 42);
     }
+
+    #[test]
+    fn providers_are_built_by_name_from_their_options() {
+        let adliswil = provider("adliswil", toml::Table::new(), TZ).unwrap();
+        assert_eq!(adliswil.name, "adliswil");
+        let options = "region = \"19\"".parse().unwrap();
+        assert_eq!(
+            provider("we_recycle", options, TZ).unwrap().name,
+            "we_recycle"
+        );
+        let options = "source = \"a.ics\"\ntypes = { Papier = \"paper\" }"
+            .parse()
+            .unwrap();
+        assert_eq!(provider("ics", options, TZ).unwrap().name, "ics");
+    }
+
+    #[test]
+    fn providers_reject_unknown_names_and_bad_options() {
+        let err = provider("zurich", toml::Table::new(), TZ).err().unwrap();
+        assert!(err.starts_with("unknown provider 'zurich'"), "{}", err);
+        let err = provider("we_recycle", toml::Table::new(), TZ)
+            .err()
+            .unwrap();
+        assert!(err.contains("missing field `region`"), "{}", err);
+        let options = "municipality = \"adliswil.evil.com/\"".parse().unwrap();
+        assert!(provider("adliswil", options, TZ).is_err());
+    }
+
+    #[test]
+    fn every_provider_takes_a_fetch_policy() {
+        let adliswil = provider("adliswil", toml::Table::new(), TZ).unwrap();
+        assert_eq!(adliswil.policy, FetchPolicy::default());
+        let options = "region = 19\ntimeout_secs = 60\nattempts = 1\nretry_delay_secs = 0"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            provider("we_recycle", options, TZ).unwrap().policy,
+            FetchPolicy {
+                timeout: Duration::from_secs(60),
+                attempts: 1,
+                retry_delay: Duration::ZERO,
+            }
+        );
+        for (options, error) in [
+            (
+                "timeout_secs = 0",
+                "timeout_secs must be a whole number of at least 1",
+            ),
+            (
+                "attempts = 0",
+                "attempts must be a whole number of at least 1",
+            ),
+            (
+                "attempts = \"3\"",
+                "attempts must be a whole number of at least 1",
+            ),
+            (
+                "retry_delay_secs = -5",
+                "retry_delay_secs must be a whole number of at least 0",
+            ),
+        ] {
+            let err = provider("adliswil", options.parse().unwrap(), TZ)
+                .err()
+                .unwrap();
+            assert_eq!(err, error);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn providers_are_fetched_at_once() {
+        let providers = [stub("adliswil", 300, 0, 1), stub("we_recycle", 300, 0, 1)];
+        let started = tokio::time::Instant::now();
+        let (by_provider, failed) =
+            fetch_providers(&providers, date(2026, 6, 7), date(2026, 6, 14)).await;
+        // Time only moves on when every task waits, so this is one delay of
+        // the stubs, not two.
+        assert_eq!(started.elapsed(), Duration::from_millis(300));
+        assert_eq!(
+            names(&by_provider, |fetched| &fetched.name),
+            vec!["adliswil", "we_recycle"]
+        );
+        assert!(failed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_failing_provider_leaves_the_others_results() {
+        let providers = [stub("adliswil", 0, 0, 1), stub("we_recycle", 10_000, 0, 1)];
+        let (by_provider, failed) =
+            fetch_providers(&providers, date(2026, 6, 7), date(2026, 6, 14)).await;
+        assert_eq!(
+            names(&by_provider, |fetched| &fetched.name),
+            vec!["adliswil"]
+        );
+        assert_eq!(
+            by_provider[0].dates[&date(2026, 6, 8)],
+            vec![TrashType::Normal]
+        );
+        assert_eq!(names(&failed, |failure| &failure.name), vec!["we_recycle"]);
+        assert_eq!(
+            failed[0].error.to_string(),
+            "Other error: no answer within 500ms"
+        );
+    }
+
+    #[tokio::test]
+    async fn providers_are_tried_as_often_as_their_policy_says() {
+        let providers = [stub("adliswil", 0, 2, 3), stub("we_recycle", 0, 2, 2)];
+        let (by_provider, failed) =
+            fetch_providers(&providers, date(2026, 6, 7), date(2026, 6, 14)).await;
+        assert_eq!(
+            names(&by_provider, |fetched| &fetched.name),
+            vec!["adliswil"]
+        );
+        assert_eq!(names(&failed, |failure| &failure.name), vec!["we_recycle"]);
+        assert_eq!(failed[0].error.to_string(), "Other error: server error");
+    }
+
+    #[test]
+    fn the_stored_schedule_needs_a_recent_sync_of_every_provider() {
+        let config = crate::config::Config {
+            providers: vec![provider("adliswil", toml::Table::new(), TZ).unwrap()],
+            ..crate::config::test_config(vec![10, 20])
+        };
+        let at = |day, hour| {
+            chrono_tz::Europe::Zurich
+                .with_ymd_and_hms(2026, 6, day, hour, 0, 0)
+                .unwrap()
+        };
+        let now = at(10, 17);
+        let synced = |at, until| HashMap::from([("adliswil".to_string(), (at, until))]);
+        let fresh = synced(at(7, 17), date(2026, 6, 30));
+        assert_eq!(cache_time(&config, &fresh, now), Some(at(7, 17)));
+        // Not up to tomorrow, too old, or never synced.
+        let short = synced(at(7, 17), date(2026, 6, 10));
+        assert_eq!(cache_time(&config, &short, now), None);
+        let old = synced(at(2, 17), date(2026, 6, 30));
+        assert_eq!(cache_time(&config, &old, now), None);
+        assert_eq!(cache_time(&config, &HashMap::new(), now), None);
+    }
 }
@@ -20,7 +20,46 @@ struct AdliswilWaste {
     results: AdliswilWasteInfo,
 }
 
-pub struct AdliswilWasteGrabber;
+/// Calendar of a municipality on entsorglos.swiss, Adliswil's by default.
+pub struct AdliswilWasteGrabber {
+    /// Where the municipality's calendar is served, like
+    /// `https://adliswil.entsorglos.swiss`.
+    base_url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Options {
+    #[serde(default = "default_municipality")]
+    municipality: String,
+}
+
+fn default_municipality() -> String {
+    "adliswil".to_string()
+}
+
+impl AdliswilWasteGrabber {
+    pub fn from_options(
+        options: toml::Table,
+        _timezone: chrono_tz::Tz,
+    ) -> Result<Box<dyn super::WasteGrabber>, String> {
+        let options: Options = super::provider_options(options)?;
+        if options.municipality.is_empty()
+            || !options
+                .municipality
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return Err(format!(
+                "municipality must be the entsorglos.swiss subdomain, like adliswil, got '{}'",
+                options.municipality
+            ));
+        }
+        Ok(Box::new(AdliswilWasteGrabber {
+            base_url: format!("https://{}.entsorglos.swiss", options.municipality),
+        }))
+    }
+}
 
 /// The Adliswil calendar API is queried one month at a time. Returns every
 /// `(year, month)` pair the `[from, to]` window touches so that windows
@@ -40,6 +79,12 @@ fn months_in_range(from: NaiveDate, to: NaiveDate) -> Vec<(i32, u32)> {
     months
 }
 
+/// The last day of `date`'s month.
+fn end_of_month(date: NaiveDate) -> NaiveDate {
+    let first = date.with_day(1).expect("every month has a first day");
+    first + chrono::Months::new(1) - chrono::Duration::days(1)
+}
+
 #[async_trait::async_trait]
 impl super::WasteGrabber for AdliswilWasteGrabber {
     async fn get_trashes(
@@ -47,23 +92,21 @@ impl super::WasteGrabber for AdliswilWasteGrabber {
         from: NaiveDate,
         to: NaiveDate,
     ) -> Result<HashMap<NaiveDate, Vec<TrashType>>, GstaldergeistError> {
-        let client = reqwest::Client::new();
+        let until = self.fetches_until(to);
 
         let mut result: HashMap<NaiveDate, Vec<TrashType>> = HashMap::new();
         for (year, month) in months_in_range(from, to) {
             let url = format!(
-                "https://adliswil.entsorglos.swiss/backend/widget/calendar-dates/{:02}-{}/",
-                month, year
+                "{}/backend/widget/calendar-dates/{:02}-{}/",
+                self.base_url, month, year
             );
 
-            let response = client.get(url).send().await?;
-
-            let waste_json = response.text().await?;
+            let waste_json = super::fetch::get_text(&url).await?;
             let wastes: AdliswilWaste = serde_json::from_str(&waste_json)?;
 
             for event in wastes.results.events {
                 let naive = event.date.date_naive();
-                if naive > from && naive <= to {
+                if naive > from && naive <= until {
                     let trastype = match event.waste_type {
                         1 => super::TrashType::Normal,
                         2 => super::TrashType::Bio,
@@ -77,11 +120,28 @@ impl super::WasteGrabber for AdliswilWasteGrabber {
         }
         Ok(result)
     }
+
+    /// The calendar comes a month at a time, so the rest of `to`'s month is
+    /// kept too.
+    fn fetches_until(&self, to: NaiveDate) -> NaiveDate {
+        end_of_month(to)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data_grabber::WasteGrabber;
+    use crate::data_grabber::stub_server::{Response, StubServer};
+
+    const OCTOBER: &str = "/backend/widget/calendar-dates/10-2026/";
+    const NOVEMBER: &str = "/backend/widget/calendar-dates/11-2026/";
+
+    fn grabber(server: &StubServer) -> AdliswilWasteGrabber {
+        AdliswilWasteGrabber {
+            base_url: server.url.clone(),
+        }
+    }
 
     fn date(year: i32, month: u32, day: u32) -> NaiveDate {
         NaiveDate::from_ymd_opt(year, month, day).unwrap()
@@ -111,6 +171,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn fetches_until_the_end_of_the_month() {
+        assert_eq!(end_of_month(date(2026, 1, 31)), date(2026, 1, 31));
+        assert_eq!(end_of_month(date(2026, 2, 4)), date(2026, 2, 28));
+        assert_eq!(end_of_month(date(2025, 12, 28)), date(2025, 12, 31));
+    }
+
     #[test]
     fn from_after_to_yields_no_months() {
         assert_eq!(
@@ -118,4 +185,61 @@ mod tests {
             Vec::<(i32, u32)>::new()
         );
     }
+
+    #[tokio::test]
+    async fn fetches_every_month_of_the_window_from_the_sample_api() {
+        let server = StubServer::start([
+            (
+                OCTOBER,
+                Response::fixture("adliswil/calendar-dates-10-2026.json"),
+            ),
+            (
+                NOVEMBER,
+                Response::fixture("adliswil/calendar-dates-11-2026.json"),
+            ),
+        ])
+        .await;
+        let trashes = grabber(&server)
+            .get_trashes(date(2026, 10, 28), date(2026, 11, 4))
+            .await
+            .unwrap();
+        // Types the bot does not know, like hazardous waste, are left out.
+        let expected = HashMap::from([
+            (date(2026, 10, 29), vec![TrashType::Bio]),
+            (date(2026, 10, 30), vec![TrashType::Cardboard]),
+            (date(2026, 11, 3), vec![TrashType::Normal, TrashType::Bio]),
+            (date(2026, 11, 18), vec![TrashType::Paper]),
+            (date(2026, 11, 27), vec![TrashType::Cardboard]),
+        ]);
+        assert_eq!(trashes, expected);
+        let paths: Vec<String> = server
+            .requests()
+            .into_iter()
+            .map(|request| request.path)
+            .collect();
+        assert_eq!(paths, vec![OCTOBER, NOVEMBER]);
+    }
+
+    #[tokio::test]
+    async fn a_month_the_api_cannot_send_is_an_error() {
+        let server = StubServer::start([(
+            OCTOBER,
+            Response::fixture("adliswil/calendar-dates-10-2026.json"),
+        )])
+        .await;
+        let result = grabber(&server)
+            .get_trashes(date(2026, 10, 28), date(2026, 11, 4))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn a_changed_format_is_an_error() {
+        let server =
+            StubServer::start([(OCTOBER, Response::ok(r#"{"results": {"dates": []}}"#))]).await;
+        let result = grabber(&server)
+            .get_trashes(date(2026, 10, 1), date(2026, 10, 8))
+            .await;
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,348 @@
+use crate::clock::Clock;
+use crate::error::GstaldergeistError;
+use chrono::{DateTime, Utc};
+use reqwest::{StatusCode, header};
+use std::path::{Path, PathBuf};
+
+/// Where downloads are kept and for how long.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CacheConfig {
+    pub path: PathBuf,
+    /// How long a download is used as it is. After that, the server is asked
+    /// whether it changed, and only sends it again if it did.
+    pub ttl: chrono::Duration,
+}
+
+/// Set once at startup from the configuration, with the clock that tells
+/// how old a download is.
+static CACHE: std::sync::OnceLock<(CacheConfig, std::sync::Arc<dyn Clock>)> =
+    std::sync::OnceLock::new();
+
+/// Keep downloads as `config` says from now on, aging them by `clock`. Only
+/// the first call counts; until then, nothing is cached.
+pub fn set_cache(config: CacheConfig, clock: std::sync::Arc<dyn Clock>) {
+    if CACHE.set((config, clock)).is_err() {
+        tracing::warn!("The download cache is already set");
+    }
+}
+
+/// What is kept about a download besides its body.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Entry {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// When the server last sent or confirmed the body.
+    fetched_at: DateTime<Utc>,
+}
+
+impl Entry {
+    fn is_fresh(&self, ttl: chrono::Duration, now: DateTime<Utc>) -> bool {
+        now - self.fetched_at < ttl
+    }
+}
+
+/// 64-bit FNV-1a hash of `bytes`. Unlike `DefaultHasher`, it stays the same
+/// across Rust versions, so cache files outlive upgrades.
+pub fn fingerprint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Numbers the files downloads are written to before they replace the kept
+/// one, so two downloads of the same URL at once do not write the same file.
+static NEXT_PARTIAL: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// The file the download of `url` is kept in: the entry as a line of JSON,
+/// followed by the body.
+fn entry_path(dir: &Path, url: &str) -> PathBuf {
+    dir.join(format!("{:016x}", fingerprint(url.as_bytes())))
+}
+
+/// The kept download of `url`, if there is a readable one.
+async fn read_entry(dir: &Path, url: &str) -> Option<(Entry, Vec<u8>)> {
+    let raw = tokio::fs::read(entry_path(dir, url)).await.ok()?;
+    let newline = raw.iter().position(|byte| *byte == b'\n')?;
+    let entry: Entry = serde_json::from_slice(&raw[..newline]).ok()?;
+    // Two URLs with the same hash take turns in the file.
+    if entry.url != url {
+        return None;
+    }
+    Some((entry, raw[newline + 1..].to_vec()))
+}
+
+/// Keep `body` as the download of `entry.url`. The file is replaced at once,
+/// so a crash never leaves a body with the validators of another.
+async fn write_entry(dir: &Path, entry: &Entry, body: &[u8]) -> std::io::Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+    let mut raw = serde_json::to_vec(entry)?;
+    raw.push(b'\n');
+    raw.extend_from_slice(body);
+    let path = entry_path(dir, &entry.url);
+    let partial = path.with_extension(format!(
+        "{}-{}.partial",
+        std::process::id(),
+        NEXT_PARTIAL.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+    tokio::fs::write(&partial, raw).await?;
+    tokio::fs::rename(partial, path).await
+}
+
+fn header_text(response: &reqwest::Response, name: header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Download `url`, from the cache while it is fresh. Once it is not, the
+/// server is asked with the cached ETag and Last-Modified date, so an
+/// unchanged document is not sent again.
+pub async fn get(url: &str) -> Result<Vec<u8>, GstaldergeistError> {
+    let cache = CACHE
+        .get()
+        .map(|(config, clock)| (config, clock.now().with_timezone(&Utc)));
+    get_cached(cache, url).await
+}
+
+/// `get` with the downloads kept as `cache` says as of the time given with
+/// it, or not at all.
+async fn get_cached(
+    cache: Option<(&CacheConfig, DateTime<Utc>)>,
+    url: &str,
+) -> Result<Vec<u8>, GstaldergeistError> {
+    let cached = match cache {
+        Some((cache, _)) => read_entry(&cache.path, url).await,
+        None => None,
+    };
+    if let (Some((cache, now)), Some((entry, body))) = (cache, &cached)
+        && entry.is_fresh(cache.ttl, now)
+    {
+        tracing::debug!("Using the cached download of {}", url);
+        return Ok(body.clone());
+    }
+
+    // No timeout of its own: each provider is given its own.
+    let mut request = reqwest::Client::new().get(url);
+    if let Some((entry, _)) = &cached {
+        if let Some(etag) = &entry.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let response = request.send().await?.error_for_status()?;
+
+    let Some((cache, now)) = cache else {
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Err(not_modified_without_cache(url));
+        }
+        return Ok(response.bytes().await?.to_vec());
+    };
+    let (entry, body) = match (response.status(), cached) {
+        (StatusCode::NOT_MODIFIED, Some((mut entry, body))) => {
+            tracing::debug!("{} is unchanged", url);
+            entry.fetched_at = now;
+            (entry, body)
+        }
+        (StatusCode::NOT_MODIFIED, None) => return Err(not_modified_without_cache(url)),
+        _ => {
+            let entry = Entry {
+                url: url.to_string(),
+                etag: header_text(&response, header::ETAG),
+                last_modified: header_text(&response, header::LAST_MODIFIED),
+                fetched_at: now,
+            };
+            (entry, response.bytes().await?.to_vec())
+        }
+    };
+    if let Err(e) = write_entry(&cache.path, &entry, &body).await {
+        tracing::warn!("Failed to cache the download of {}: {}", url, e);
+    }
+    Ok(body)
+}
+
+fn not_modified_without_cache(url: &str) -> GstaldergeistError {
+    GstaldergeistError::Other(format!(
+        "{} answered 304 Not Modified, but nothing is cached",
+        url
+    ))
+}
+
+/// Download `url` as text, see `get`.
+pub async fn get_text(url: &str) -> Result<String, GstaldergeistError> {
+    let body = get(url).await?;
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_grabber::stub_server::{Response, StubServer};
+
+    /// An empty directory of its own for the test `name`.
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "gstaldergeist-fetch-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn entry(url: &str) -> Entry {
+        Entry {
+            url: url.to_string(),
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            fetched_at: DateTime::from_timestamp(1767632400, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn fingerprint_is_fnv_1a() {
+        assert_eq!(fingerprint(b""), 0xcbf29ce484222325);
+        assert_eq!(fingerprint(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fingerprint(b"foobar"), 0x85944171f73967e8);
+    }
+
+    #[tokio::test]
+    async fn downloads_are_kept_with_their_validators() {
+        let dir = cache_dir("roundtrip");
+        let url = "https://example.com/calendar.pdf";
+        assert_eq!(read_entry(&dir, url).await, None);
+        let body = b"%PDF-1.4\nbinary \x00\xff\n".to_vec();
+        write_entry(&dir, &entry(url), &body).await.unwrap();
+        assert_eq!(read_entry(&dir, url).await, Some((entry(url), body)));
+        assert_eq!(
+            read_entry(&dir, "https://example.com/other.pdf").await,
+            None
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn unreadable_or_foreign_entries_are_ignored() {
+        let dir = cache_dir("foreign");
+        let url = "https://example.com/calendar.pdf";
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(entry_path(&dir, url), b"not json\nbody").unwrap();
+        assert_eq!(read_entry(&dir, url).await, None);
+        // What another URL with the same hash left behind.
+        let mut raw = serde_json::to_vec(&entry("https://example.com/other")).unwrap();
+        raw.extend_from_slice(b"\nbody");
+        std::fs::write(entry_path(&dir, url), raw).unwrap();
+        assert_eq!(read_entry(&dir, url).await, None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn entries_are_fresh_for_the_ttl() {
+        let entry = entry("https://example.com");
+        let ttl = chrono::Duration::hours(6);
+        assert!(entry.is_fresh(ttl, entry.fetched_at));
+        assert!(entry.is_fresh(ttl, entry.fetched_at + chrono::Duration::minutes(359)));
+        assert!(!entry.is_fresh(ttl, entry.fetched_at + ttl));
+        assert!(!entry.is_fresh(chrono::Duration::zero(), entry.fetched_at));
+    }
+
+    async fn calendar_server() -> StubServer {
+        StubServer::start([
+            ("/calendar.pdf", Response::ok("v1").with_etag("\"v1\"")),
+            ("/broken", Response::status(500)),
+        ])
+        .await
+    }
+
+    /// `hours` after the start of the tests' downloads.
+    fn at(hours: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1767632400, 0).unwrap() + chrono::Duration::hours(hours)
+    }
+
+    fn cache(name: &str, ttl: chrono::Duration) -> CacheConfig {
+        CacheConfig {
+            path: cache_dir(name),
+            ttl,
+        }
+    }
+
+    #[tokio::test]
+    async fn a_stale_download_is_revalidated_with_its_etag() {
+        let server = calendar_server().await;
+        let url = format!("{}/calendar.pdf", server.url);
+        let cache = cache("revalidate", chrono::Duration::hours(6));
+        assert_eq!(
+            get_cached(Some((&cache, at(0))), &url).await.unwrap(),
+            b"v1"
+        );
+        assert_eq!(
+            get_cached(Some((&cache, at(6))), &url).await.unwrap(),
+            b"v1"
+        );
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].headers.get("if-none-match"), None);
+        assert_eq!(
+            requests[1].headers.get("if-none-match").map(String::as_str),
+            Some("\"v1\"")
+        );
+        std::fs::remove_dir_all(cache.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_fresh_download_is_not_requested_again() {
+        let server = calendar_server().await;
+        let url = format!("{}/calendar.pdf", server.url);
+        let cache = cache("fresh", chrono::Duration::hours(6));
+        assert_eq!(
+            get_cached(Some((&cache, at(0))), &url).await.unwrap(),
+            b"v1"
+        );
+        assert_eq!(
+            get_cached(Some((&cache, at(5))), &url).await.unwrap(),
+            b"v1"
+        );
+        assert_eq!(server.requests().len(), 1);
+        std::fs::remove_dir_all(cache.path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn downloads_of_the_same_url_at_once_are_both_kept() {
+        let dir = cache_dir("concurrent");
+        let url = "https://example.com/calendar.pdf";
+        let entry = entry(url);
+        let (first, second) = tokio::join!(
+            write_entry(&dir, &entry, b"v1"),
+            write_entry(&dir, &entry, b"v1")
+        );
+        first.unwrap();
+        second.unwrap();
+        assert_eq!(read_entry(&dir, url).await, Some((entry, b"v1".to_vec())));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn without_a_cache_every_download_is_requested() {
+        let server = calendar_server().await;
+        let url = format!("{}/calendar.pdf", server.url);
+        assert_eq!(get_cached(None, &url).await.unwrap(), b"v1");
+        assert_eq!(get_cached(None, &url).await.unwrap(), b"v1");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].headers.get("if-none-match"), None);
+    }
+
+    #[tokio::test]
+    async fn error_statuses_are_errors_and_not_cached() {
+        let server = calendar_server().await;
+        let url = format!("{}/broken", server.url);
+        let cache = cache("broken", chrono::Duration::hours(6));
+        assert!(get_cached(Some((&cache, at(0))), &url).await.is_err());
+        assert_eq!(read_entry(&cache.path, &url).await, None);
+    }
+}
@@ -0,0 +1,996 @@
+use super::TrashType;
+use crate::error::GstaldergeistError;
+use chrono::{Datelike, Duration, NaiveDate, TimeZone, Weekday};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Upper bound on the periods a recurrence rule is expanded over, so a broken
+/// rule cannot keep the bot busy forever.
+const MAX_PERIODS: i64 = 100_000;
+
+/// Collection dates published as an iCalendar (`.ics`) feed, which many
+/// municipalities offer as a download or subscription URL.
+pub struct IcsWasteGrabber {
+    /// URL of the feed, or path of a local `.ics` file.
+    source: String,
+    /// Lowercase text to look for in an event's summary or categories, with
+    /// the trash type the event then stands for.
+    types: Vec<(String, TrashType)>,
+    /// Times in UTC or another zone count on the day they fall on here.
+    timezone: chrono_tz::Tz,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Options {
+    source: String,
+    types: BTreeMap<String, String>,
+}
+
+impl IcsWasteGrabber {
+    pub fn from_options(
+        options: toml::Table,
+        timezone: chrono_tz::Tz,
+    ) -> Result<Box<dyn super::WasteGrabber>, String> {
+        let options: Options = super::provider_options(options)?;
+        if options.source.is_empty() {
+            return Err("source must be the URL or path of an .ics file".to_string());
+        }
+        if options.types.is_empty() {
+            return Err("types must map at least one event summary to a trash type".to_string());
+        }
+        let mut types = Vec::new();
+        for (text, name) in options.types {
+            let trash = TrashType::from_name(&name).ok_or_else(|| {
+                format!(
+                    "types.{}: unknown trash type '{}', expected one of {}",
+                    text,
+                    name,
+                    TrashType::NAMES.join(", ")
+                )
+            })?;
+            types.push((text.to_lowercase(), trash));
+        }
+        Ok(Box::new(IcsWasteGrabber {
+            source: options.source,
+            types,
+            timezone,
+        }))
+    }
+
+    /// Trash types of an event, judged by its summary and categories.
+    fn trash_types(&self, event: &Event) -> Vec<TrashType> {
+        let summary = event.summary.to_lowercase();
+        let categories: Vec<String> = event.categories.iter().map(|c| c.to_lowercase()).collect();
+        let mut trashes = Vec::new();
+        for (text, trash) in &self.types {
+            let matches = summary.contains(text.as_str())
+                || categories.iter().any(|c| c.contains(text.as_str()));
+            if matches && !trashes.contains(trash) {
+                trashes.push(*trash);
+            }
+        }
+        trashes
+    }
+
+    fn collect(
+        &self,
+        calendar: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<HashMap<NaiveDate, Vec<TrashType>>, GstaldergeistError> {
+        let mut result: HashMap<NaiveDate, Vec<TrashType>> = HashMap::new();
+        for (event, dates) in parse_calendar(calendar, self.timezone)?.occurrences(to) {
+            let trashes = self.trash_types(event);
+            for date in dates.into_iter().filter(|date| *date > from) {
+                let entry = result.entry(date).or_default();
+                for trash in &trashes {
+                    if !entry.contains(trash) {
+                        entry.push(*trash);
+                    }
+                }
+            }
+        }
+        result.retain(|_, trashes| !trashes.is_empty());
+        Ok(result)
+    }
+}
+
+#[async_trait::async_trait]
+impl super::WasteGrabber for IcsWasteGrabber {
+    async fn get_trashes(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<HashMap<NaiveDate, Vec<TrashType>>, GstaldergeistError> {
+        let calendar = if self.source.starts_with("http://") || self.source.starts_with("https://")
+        {
+            super::fetch::get_text(&self.source).await?
+        } else {
+            let path = self.source.strip_prefix("file://").unwrap_or(&self.source);
+            tokio::fs::read_to_string(path).await?
+        };
+        self.collect(&calendar, from, to)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The parts of an RRULE that collection calendars use.
+#[derive(Debug, PartialEq)]
+struct Rule {
+    frequency: Frequency,
+    interval: i64,
+    count: Option<usize>,
+    until: Option<NaiveDate>,
+    /// Weekdays, optionally the n-th (or n-th last, if negative) of the month
+    /// or year.
+    by_day: Vec<(Option<i32>, Weekday)>,
+    /// Days of the month, counted from the end if negative.
+    by_month_day: Vec<i32>,
+    by_month: Vec<u32>,
+    week_start: Weekday,
+}
+
+#[derive(Debug, Default)]
+struct Event {
+    uid: Option<String>,
+    summary: String,
+    categories: Vec<String>,
+    start: Option<NaiveDate>,
+    rule: Option<Rule>,
+    extra_dates: Vec<NaiveDate>,
+    excluded_dates: Vec<NaiveDate>,
+    /// Occurrence of the recurring event with the same UID this one replaces.
+    recurrence_id: Option<NaiveDate>,
+    cancelled: bool,
+    /// Why its RRULE cannot be followed, which leaves the event out.
+    invalid_rule: Option<String>,
+}
+
+struct Calendar {
+    events: Vec<Event>,
+}
+
+impl Calendar {
+    /// Every event with its dates up to and including `to`.
+    fn occurrences(&self, to: NaiveDate) -> Vec<(&Event, Vec<NaiveDate>)> {
+        let mut result = Vec::new();
+        for event in &self.events {
+            let Some(start) = event.start else { continue };
+            if event.recurrence_id.is_some() {
+                if !event.cancelled && start <= to {
+                    result.push((event, vec![start]));
+                }
+                continue;
+            }
+            if event.cancelled {
+                continue;
+            }
+            // Moved or cancelled occurrences come as separate events.
+            let replaced: Vec<NaiveDate> = self
+                .events
+                .iter()
+                .filter(|other| other.uid.is_some() && other.uid == event.uid)
+                .filter_map(|other| other.recurrence_id)
+                .collect();
+            let mut dates = match &event.rule {
+                Some(rule) => rule.expand(start, to),
+                None => vec![start],
+            };
+            dates.extend(event.extra_dates.iter().filter(|date| **date <= to));
+            dates.retain(|date| {
+                !event.excluded_dates.contains(date) && !replaced.contains(date) && *date <= to
+            });
+            dates.sort();
+            dates.dedup();
+            result.push((event, dates));
+        }
+        result
+    }
+}
+
+impl Rule {
+    /// Dates of the rule from `start` up to and including `to`. Like RFC 5545
+    /// says, `start` is always the first occurrence.
+    fn expand(&self, start: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        let end = self.until.map_or(to, |until| until.min(to));
+        let mut dates = vec![start];
+        'periods: for period in 0..MAX_PERIODS {
+            let Some((begin, candidates)) = self.period(start, period) else {
+                break;
+            };
+            if begin > end {
+                break;
+            }
+            for date in candidates {
+                if date <= start {
+                    continue;
+                }
+                if date > end || self.count.is_some_and(|count| dates.len() >= count) {
+                    break 'periods;
+                }
+                dates.push(date);
+            }
+        }
+        dates
+    }
+
+    /// First day of the `index`-th period after the one `start` lies in,
+    /// with the rule's dates in it, sorted.
+    fn period(&self, start: NaiveDate, index: i64) -> Option<(NaiveDate, Vec<NaiveDate>)> {
+        let step = index.checked_mul(self.interval)?;
+        let (begin, mut dates) = match self.frequency {
+            Frequency::Daily => {
+                let day = start.checked_add_signed(Duration::try_days(step)?)?;
+                let keep = (self.by_month_day.is_empty() || self.matches_month_day(day))
+                    && (self.by_day.is_empty()
+                        || self
+                            .by_day
+                            .iter()
+                            .any(|(_, weekday)| *weekday == day.weekday()));
+                (day, if keep { vec![day] } else { Vec::new() })
+            }
+            Frequency::Weekly => {
+                let offset = (7 + start.weekday().num_days_from_monday()
+                    - self.week_start.num_days_from_monday())
+                    % 7;
+                let week = start.checked_add_signed(Duration::try_weeks(step)?)?
+                    - Duration::days(offset.into());
+                let weekdays: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![start.weekday()]
+                } else {
+                    self.by_day.iter().map(|(_, weekday)| *weekday).collect()
+                };
+                let dates = (0..7)
+                    .map(|day| week + Duration::days(day))
+                    .filter(|day| weekdays.contains(&day.weekday()))
+                    .collect();
+                (week, dates)
+            }
+            Frequency::Monthly => {
+                let months = i64::from(start.month0()).checked_add(step)?;
+                let year = start
+                    .year()
+                    .checked_add(months.div_euclid(12).try_into().ok()?)?;
+                let month = u32::try_from(months.rem_euclid(12)).ok()? + 1;
+                let days = days_of_month(year, month)?;
+                (days[0], self.select(&days, start.day()))
+            }
+            Frequency::Yearly => {
+                let year = start.year().checked_add(step.try_into().ok()?)?;
+                let begin = NaiveDate::from_ymd_opt(year, 1, 1)?;
+                let dates = if self.by_month.is_empty()
+                    && !self.by_day.is_empty()
+                    && self.by_month_day.is_empty()
+                {
+                    // The n-th weekday of the whole year.
+                    let days: Vec<NaiveDate> =
+                        begin.iter_days().take_while(|d| d.year() == year).collect();
+                    self.select(&days, start.day())
+                } else {
+                    let months = if !self.by_month.is_empty() {
+                        self.by_month.clone()
+                    } else if !self.by_month_day.is_empty() {
+                        (1..=12).collect()
+                    } else {
+                        vec![start.month()]
+                    };
+                    let mut dates = Vec::new();
+                    for month in months {
+                        dates.extend(self.select(&days_of_month(year, month)?, start.day()));
+                    }
+                    dates.sort();
+                    dates
+                };
+                (begin, dates)
+            }
+        };
+        dates.retain(|date| self.by_month.is_empty() || self.by_month.contains(&date.month()));
+        Some((begin, dates))
+    }
+
+    /// The dates among `days`, a whole month or year, that the BYDAY and
+    /// BYMONTHDAY parts pick, or the `default_day` of the month without them.
+    fn select(&self, days: &[NaiveDate], default_day: u32) -> Vec<NaiveDate> {
+        if self.by_day.is_empty() && self.by_month_day.is_empty() {
+            return days
+                .iter()
+                .copied()
+                .filter(|d| d.day() == default_day)
+                .collect();
+        }
+        days.iter()
+            .copied()
+            .filter(|day| self.by_month_day.is_empty() || self.matches_month_day(*day))
+            .filter(|day| {
+                self.by_day.is_empty()
+                    || self.by_day.iter().any(|(nth, weekday)| {
+                        *weekday == day.weekday()
+                            && nth.is_none_or(|nth| {
+                                let same: Vec<&NaiveDate> =
+                                    days.iter().filter(|d| d.weekday() == *weekday).collect();
+                                let index = if nth > 0 {
+                                    nth - 1
+                                } else {
+                                    same.len() as i32 + nth
+                                };
+                                usize::try_from(index)
+                                    .ok()
+                                    .and_then(|index| same.get(index))
+                                    .is_some_and(|d| **d == *day)
+                            })
+                    })
+            })
+            .collect()
+    }
+
+    fn matches_month_day(&self, day: NaiveDate) -> bool {
+        let length = days_of_month(day.year(), day.month()).map_or(0, |days| days.len() as i32);
+        self.by_month_day.iter().any(|month_day| {
+            if *month_day > 0 {
+                day.day() as i32 == *month_day
+            } else {
+                day.day() as i32 == length + month_day + 1
+            }
+        })
+    }
+}
+
+fn days_of_month(year: i32, month: u32) -> Option<Vec<NaiveDate>> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    Some(
+        first
+            .iter_days()
+            .take_while(|d| d.month() == month)
+            .collect(),
+    )
+}
+
+fn error(message: String) -> GstaldergeistError {
+    GstaldergeistError::Calendar(message)
+}
+
+/// Join folded lines: a line starting with a space or tab continues the
+/// previous one.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if line.trim().is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// A content line, e.g. `DTSTART;TZID=Europe/Zurich:20260105T070000`. Of
+/// the parameters, only the time zone is needed: dates are told apart from
+/// times by their length.
+struct Property<'a> {
+    name: String,
+    tzid: Option<&'a str>,
+    value: &'a str,
+}
+
+fn parse_property(line: &str) -> Option<Property<'_>> {
+    // Parameter values may be quoted and contain ':' or ';'.
+    let mut quoted = false;
+    let mut name_end = None;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                name_end.get_or_insert(i);
+            }
+            ':' if !quoted => {
+                let name_end = name_end.unwrap_or(i);
+                let tzid = line[name_end..i]
+                    .split(';')
+                    .filter_map(|parameter| parameter.split_once('='))
+                    .find(|(key, _)| key.eq_ignore_ascii_case("TZID"))
+                    .map(|(_, zone)| zone.trim_matches('"'));
+                return Some(Property {
+                    name: line[..name_end].to_uppercase(),
+                    tzid,
+                    value: &line[i + 1..],
+                });
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split a text value on `separator`s that are not escaped.
+fn split_text(value: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => parts.last_mut().unwrap().push('\n'),
+                Some(escaped) => parts.last_mut().unwrap().push(escaped),
+                None => {}
+            },
+            c if c == separator => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+fn unescape(value: &str) -> String {
+    split_text(value, '\0').concat()
+}
+
+/// The date of a DATE or DATE-TIME value in `timezone`. What counts for a
+/// collection is the day it happens on, so the time is dropped once it is
+/// converted from UTC (a trailing `Z`) or the zone `tzid` names. Times in a
+/// zone chrono-tz does not know are taken as they are.
+fn parse_date(
+    value: &str,
+    tzid: Option<&str>,
+    timezone: chrono_tz::Tz,
+) -> Result<NaiveDate, GstaldergeistError> {
+    let invalid = || error(format!("invalid date '{}'", value));
+    let date = value
+        .get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(invalid)?;
+    let Some(time) = value.get(8..).filter(|time| !time.is_empty()) else {
+        return Ok(date);
+    };
+    let (time, utc) = match time.strip_suffix(['Z', 'z']) {
+        Some(time) => (time, true),
+        None => (time, false),
+    };
+    let time = chrono::NaiveTime::parse_from_str(time, "T%H%M%S").map_err(|_| invalid())?;
+    let local = date.and_time(time);
+    let zone: Option<chrono_tz::Tz> = if utc {
+        Some(chrono_tz::UTC)
+    } else {
+        tzid.and_then(|zone| zone.trim_start_matches('/').parse().ok())
+    };
+    let Some(zone) = zone else {
+        return Ok(date);
+    };
+    // A time skipped by a clock change keeps the day it names.
+    Ok(zone
+        .from_local_datetime(&local)
+        .earliest()
+        .map_or(date, |at| at.with_timezone(&timezone).date_naive()))
+}
+
+/// The dates of a list of DATE, DATE-TIME or PERIOD values, the latter by
+/// their start.
+fn parse_dates(
+    value: &str,
+    tzid: Option<&str>,
+    timezone: chrono_tz::Tz,
+) -> Result<Vec<NaiveDate>, GstaldergeistError> {
+    value
+        .split(',')
+        .map(|value| {
+            let start = value.split('/').next().unwrap_or(value);
+            parse_date(start, tzid, timezone)
+        })
+        .collect()
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    Some(match value {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn parse_rule(value: &str, timezone: chrono_tz::Tz) -> Result<Rule, GstaldergeistError> {
+    let invalid = |part: &str| error(format!("invalid RRULE part '{}' in '{}'", part, value));
+    let mut frequency = None;
+    let mut rule = Rule {
+        frequency: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_day: Vec::new(),
+        by_month_day: Vec::new(),
+        by_month: Vec::new(),
+        week_start: Weekday::Mon,
+    };
+    for part in value.split(';').filter(|part| !part.is_empty()) {
+        let (key, values) = part.split_once('=').ok_or_else(|| invalid(part))?;
+        match key.to_uppercase().as_str() {
+            "FREQ" => {
+                frequency = Some(match values.to_uppercase().as_str() {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    _ => return Err(invalid(part)),
+                })
+            }
+            "INTERVAL" => {
+                rule.interval = values
+                    .parse()
+                    .ok()
+                    .filter(|interval| *interval > 0)
+                    .ok_or_else(|| invalid(part))?
+            }
+            "COUNT" => rule.count = Some(values.parse().map_err(|_| invalid(part))?),
+            "UNTIL" => rule.until = Some(parse_date(values, None, timezone)?),
+            "BYDAY" => {
+                for day in values.split(',') {
+                    let day = day.to_uppercase();
+                    let (nth, weekday) = day.split_at(day.len().saturating_sub(2));
+                    let weekday = parse_weekday(weekday).ok_or_else(|| invalid(part))?;
+                    let nth = match nth.trim_start_matches('+') {
+                        "" => None,
+                        nth => Some(
+                            nth.parse()
+                                .ok()
+                                .filter(|nth: &i32| *nth != 0 && nth.abs() <= 53)
+                                .ok_or_else(|| invalid(part))?,
+                        ),
+                    };
+                    rule.by_day.push((nth, weekday));
+                }
+            }
+            "BYMONTHDAY" => {
+                for day in values.split(',') {
+                    rule.by_month_day.push(
+                        day.parse()
+                            .ok()
+                            .filter(|day: &i32| *day != 0 && day.abs() <= 31)
+                            .ok_or_else(|| invalid(part))?,
+                    );
+                }
+            }
+            "BYMONTH" => {
+                for month in values.split(',') {
+                    rule.by_month.push(
+                        month
+                            .parse()
+                            .ok()
+                            .filter(|month| (1..=12).contains(month))
+                            .ok_or_else(|| invalid(part))?,
+                    );
+                }
+            }
+            "WKST" => {
+                rule.week_start =
+                    parse_weekday(&values.to_uppercase()).ok_or_else(|| invalid(part))?
+            }
+            _ => {
+                return Err(error(format!(
+                    "unsupported RRULE part '{}' in '{}'",
+                    part, value
+                )));
+            }
+        }
+    }
+    rule.frequency = frequency.ok_or_else(|| error(format!("RRULE without FREQ: '{}'", value)))?;
+    let numbered_days = rule.by_day.iter().any(|(nth, _)| nth.is_some());
+    if numbered_days && !matches!(rule.frequency, Frequency::Monthly | Frequency::Yearly) {
+        return Err(error(format!(
+            "numbered BYDAY needs FREQ=MONTHLY or YEARLY: '{}'",
+            value
+        )));
+    }
+    if rule.frequency == Frequency::Weekly && !rule.by_month_day.is_empty() {
+        return Err(error(format!(
+            "BYMONTHDAY does not work with FREQ=WEEKLY: '{}'",
+            value
+        )));
+    }
+    Ok(rule)
+}
+
+fn parse_calendar(text: &str, timezone: chrono_tz::Tz) -> Result<Calendar, GstaldergeistError> {
+    let mut events = Vec::new();
+    // Components can nest, e.g. an alarm inside an event, whose properties
+    // must not be taken for the event's.
+    let mut components: Vec<String> = Vec::new();
+    let mut event = Event::default();
+    for (number, line) in unfold(text).iter().enumerate() {
+        let property = parse_property(line)
+            .ok_or_else(|| error(format!("line {}: not a property: '{}'", number + 1, line)))?;
+        match property.name.as_str() {
+            "BEGIN" => {
+                let component = property.value.to_uppercase();
+                if component == "VEVENT" {
+                    event = Event::default();
+                }
+                components.push(component);
+                continue;
+            }
+            "END" => {
+                if components.pop().as_deref() == Some("VEVENT") {
+                    let done = std::mem::take(&mut event);
+                    if done.start.is_none() {
+                        return Err(error(format!(
+                            "line {}: event '{}' has no DTSTART",
+                            number + 1,
+                            done.summary
+                        )));
+                    }
+                    if let Some(reason) = &done.invalid_rule {
+                        tracing::warn!("Skipping the event '{}': {}", done.summary, reason);
+                        continue;
+                    }
+                    events.push(done);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        if components.last().map(String::as_str) != Some("VEVENT") {
+            continue;
+        }
+        let date = |value| parse_date(value, property.tzid, timezone);
+        let dates = |value| parse_dates(value, property.tzid, timezone);
+        let context = |e: GstaldergeistError| match e {
+            GstaldergeistError::Calendar(message) => {
+                error(format!("line {}: {}", number + 1, message))
+            }
+            e => e,
+        };
+        match property.name.as_str() {
+            "UID" => event.uid = Some(property.value.to_string()),
+            "SUMMARY" => event.summary = unescape(property.value),
+            "CATEGORIES" => event.categories.extend(split_text(property.value, ',')),
+            "STATUS" => event.cancelled = property.value.eq_ignore_ascii_case("CANCELLED"),
+            "DTSTART" => event.start = Some(date(property.value).map_err(context)?),
+            // One event's rule that cannot be followed is no reason to drop
+            // the others.
+            "RRULE" => match parse_rule(property.value, timezone) {
+                Ok(rule) => event.rule = Some(rule),
+                Err(e) => event.invalid_rule = Some(context(e).to_string()),
+            },
+            "RDATE" => event
+                .extra_dates
+                .extend(dates(property.value).map_err(context)?),
+            "EXDATE" => event
+                .excluded_dates
+                .extend(dates(property.value).map_err(context)?),
+            "RECURRENCE-ID" => event.recurrence_id = Some(date(property.value).map_err(context)?),
+            _ => {}
+        }
+    }
+    Ok(Calendar { events })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TZ: chrono_tz::Tz = chrono_tz::Europe::Zurich;
+    const FIXTURE: &str = include_str!("../../tests/fixtures/ics/waste-calendar.ics");
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn grabber() -> IcsWasteGrabber {
+        IcsWasteGrabber {
+            source: "calendar.ics".to_string(),
+            types: vec![
+                ("grüngut".to_string(), TrashType::Bio),
+                ("karton".to_string(), TrashType::Cardboard),
+                ("kehricht".to_string(), TrashType::Normal),
+                ("papier".to_string(), TrashType::Paper),
+            ],
+            timezone: TZ,
+        }
+    }
+
+    fn rule_dates(rule: &str, start: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        parse_rule(rule, TZ).unwrap().expand(start, to)
+    }
+
+    #[test]
+    fn weekly_events_with_exceptions() {
+        let trashes = grabber()
+            .collect(FIXTURE, date(2026, 1, 1), date(2026, 1, 31))
+            .unwrap();
+        let mut normal: Vec<NaiveDate> = trashes
+            .iter()
+            .filter(|(_, trashes)| trashes.contains(&TrashType::Normal))
+            .map(|(date, _)| *date)
+            .collect();
+        normal.sort();
+        // Every Tuesday, except the 6th (Epiphany) which moved to the 7th and
+        // the excluded 27th.
+        assert_eq!(
+            normal,
+            vec![date(2026, 1, 7), date(2026, 1, 13), date(2026, 1, 20)]
+        );
+    }
+
+    #[test]
+    fn monthly_and_single_events() {
+        let trashes = grabber()
+            .collect(FIXTURE, date(2026, 1, 1), date(2026, 2, 28))
+            .unwrap();
+        // Paper on the first Wednesday of the month, without the Kehricht its
+        // alarm mentions. Cardboard once, tagged by category only.
+        assert_eq!(
+            trashes[&date(2026, 1, 7)],
+            vec![TrashType::Normal, TrashType::Paper]
+        );
+        assert_eq!(trashes[&date(2026, 2, 4)], vec![TrashType::Paper]);
+        assert_eq!(trashes[&date(2026, 1, 16)], vec![TrashType::Cardboard]);
+        // The green waste is every other week until the end of January, and
+        // the cancelled collection and the unmapped event are left out.
+        assert_eq!(trashes[&date(2026, 1, 9)], vec![TrashType::Bio]);
+        assert_eq!(trashes[&date(2026, 1, 23)], vec![TrashType::Bio]);
+        assert!(!trashes.contains_key(&date(2026, 2, 6)));
+        assert!(!trashes.contains_key(&date(2026, 1, 29)));
+        assert!(!trashes.contains_key(&date(2026, 1, 10)));
+    }
+
+    #[test]
+    fn window_excludes_from_and_includes_to() {
+        let trashes = grabber()
+            .collect(FIXTURE, date(2026, 1, 13), date(2026, 1, 20))
+            .unwrap();
+        assert!(!trashes.contains_key(&date(2026, 1, 13)));
+        assert!(trashes.contains_key(&date(2026, 1, 20)));
+    }
+
+    #[tokio::test]
+    async fn reads_a_local_file() {
+        let grabber = IcsWasteGrabber {
+            source: concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/ics/waste-calendar.ics"
+            )
+            .to_string(),
+            ..grabber()
+        };
+        let trashes =
+            super::super::WasteGrabber::get_trashes(&grabber, date(2026, 1, 1), date(2026, 1, 8))
+                .await
+                .unwrap();
+        assert_eq!(
+            trashes[&date(2026, 1, 7)],
+            vec![TrashType::Normal, TrashType::Paper]
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_a_feed_from_its_url() {
+        use crate::data_grabber::stub_server::{Response, StubServer};
+        let server =
+            StubServer::start([("/abfall.ics", Response::fixture("ics/waste-calendar.ics"))]).await;
+        let grabber = IcsWasteGrabber {
+            source: format!("{}/abfall.ics", server.url),
+            ..grabber()
+        };
+        let trashes =
+            super::super::WasteGrabber::get_trashes(&grabber, date(2026, 1, 1), date(2026, 1, 8))
+                .await
+                .unwrap();
+        assert_eq!(
+            trashes[&date(2026, 1, 7)],
+            vec![TrashType::Normal, TrashType::Paper]
+        );
+    }
+
+    #[test]
+    fn count_and_until_limit_a_rule() {
+        assert_eq!(
+            rule_dates("FREQ=DAILY;COUNT=3", date(2026, 1, 1), date(2026, 12, 31)),
+            vec![date(2026, 1, 1), date(2026, 1, 2), date(2026, 1, 3)]
+        );
+        assert_eq!(
+            rule_dates(
+                "FREQ=WEEKLY;UNTIL=20260115T230000Z",
+                date(2026, 1, 1),
+                date(2026, 12, 31)
+            ),
+            vec![date(2026, 1, 1), date(2026, 1, 8), date(2026, 1, 15)]
+        );
+    }
+
+    #[test]
+    fn weekly_rule_on_several_days() {
+        assert_eq!(
+            rule_dates(
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH",
+                date(2026, 1, 5),
+                date(2026, 1, 31)
+            ),
+            vec![
+                date(2026, 1, 5),
+                date(2026, 1, 8),
+                date(2026, 1, 19),
+                date(2026, 1, 22)
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_rules() {
+        // Last Friday of the month.
+        assert_eq!(
+            rule_dates(
+                "FREQ=MONTHLY;BYDAY=-1FR",
+                date(2026, 1, 30),
+                date(2026, 3, 31)
+            ),
+            vec![date(2026, 1, 30), date(2026, 2, 27), date(2026, 3, 27)]
+        );
+        // Months without a 31st are skipped.
+        assert_eq!(
+            rule_dates("FREQ=MONTHLY", date(2026, 1, 31), date(2026, 5, 31)),
+            vec![date(2026, 1, 31), date(2026, 3, 31), date(2026, 5, 31)]
+        );
+        assert_eq!(
+            rule_dates(
+                "FREQ=MONTHLY;BYMONTHDAY=-1",
+                date(2026, 1, 31),
+                date(2026, 3, 31)
+            ),
+            vec![date(2026, 1, 31), date(2026, 2, 28), date(2026, 3, 31)]
+        );
+    }
+
+    #[test]
+    fn yearly_rules() {
+        assert_eq!(
+            rule_dates(
+                "FREQ=YEARLY;BYMONTH=3,10;BYDAY=1SA",
+                date(2026, 3, 7),
+                date(2027, 12, 31)
+            ),
+            vec![
+                date(2026, 3, 7),
+                date(2026, 10, 3),
+                date(2027, 3, 6),
+                date(2027, 10, 2)
+            ]
+        );
+        assert_eq!(
+            rule_dates("FREQ=YEARLY", date(2024, 2, 29), date(2029, 1, 1)),
+            vec![date(2024, 2, 29), date(2028, 2, 29)]
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_rules() {
+        let message = parse_rule("FREQ=MONTHLY;BYDAY=MO;BYSETPOS=-1", TZ)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(
+            message.contains("unsupported RRULE part 'BYSETPOS=-1'"),
+            "{}",
+            message
+        );
+        assert!(parse_rule("FREQ=HOURLY", TZ).is_err());
+        assert!(parse_rule("INTERVAL=2", TZ).is_err());
+        assert!(parse_rule("FREQ=WEEKLY;BYDAY=1MO", TZ).is_err());
+    }
+
+    #[test]
+    fn an_event_with_a_rule_it_cannot_follow_is_skipped() {
+        let calendar = "BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\nSUMMARY:Papier\nDTSTART:20260105\n\
+            RRULE:FREQ=MONTHLY;BYDAY=MO;BYSETPOS=1\nEND:VEVENT\n\
+            BEGIN:VEVENT\nSUMMARY:Kehricht\nDTSTART:20260106\nEND:VEVENT\n\
+            END:VCALENDAR\n";
+        let trashes = grabber()
+            .collect(calendar, date(2026, 1, 1), date(2026, 12, 31))
+            .unwrap();
+        assert_eq!(
+            trashes,
+            HashMap::from([(date(2026, 1, 6), vec![TrashType::Normal])])
+        );
+    }
+
+    #[test]
+    fn times_count_on_their_day_in_the_configured_timezone() {
+        assert_eq!(parse_date("20260105", None, TZ).unwrap(), date(2026, 1, 5));
+        // Floating times are local already.
+        assert_eq!(
+            parse_date("20260105T233000", None, TZ).unwrap(),
+            date(2026, 1, 5)
+        );
+        // 23:30 UTC is 00:30 in Zurich.
+        assert_eq!(
+            parse_date("20260105T233000Z", None, TZ).unwrap(),
+            date(2026, 1, 6)
+        );
+        assert_eq!(
+            parse_date("20260106T060000", Some("America/New_York"), TZ).unwrap(),
+            date(2026, 1, 6)
+        );
+        assert_eq!(
+            parse_date("20260105T200000", Some("America/New_York"), TZ).unwrap(),
+            date(2026, 1, 6)
+        );
+        assert_eq!(
+            parse_date("20260105T200000", Some("Custom Zone"), TZ).unwrap(),
+            date(2026, 1, 5)
+        );
+        assert!(parse_date("20260105T2000", None, TZ).is_err());
+        assert_eq!(
+            parse_dates("20260105T233000Z/PT1H,20260107", None, TZ).unwrap(),
+            vec![date(2026, 1, 6), date(2026, 1, 7)]
+        );
+    }
+
+    #[test]
+    fn dtstart_is_read_in_the_zone_it_names() {
+        let calendar = "BEGIN:VCALENDAR\nBEGIN:VEVENT\nSUMMARY:Kehricht\n\
+            DTSTART;TZID=\"America/New_York\":20260105T200000\n\
+            RRULE:FREQ=WEEKLY;UNTIL=20260114T010000Z\nEND:VEVENT\nEND:VCALENDAR\n";
+        let trashes = grabber()
+            .collect(calendar, date(2026, 1, 1), date(2026, 1, 31))
+            .unwrap();
+        let mut dates: Vec<NaiveDate> = trashes.keys().copied().collect();
+        dates.sort();
+        assert_eq!(dates, vec![date(2026, 1, 6), date(2026, 1, 13)]);
+    }
+
+    #[test]
+    fn invalid_dates_name_the_line() {
+        let calendar =
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART:2026-01-05\nEND:VEVENT\nEND:VCALENDAR\n";
+        let message = parse_calendar(calendar, TZ).err().unwrap().to_string();
+        assert!(
+            message.contains("line 3: invalid date '2026-01-05'"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn parses_quoted_parameters_and_escaped_text() {
+        let property =
+            parse_property(r#"categories;ALTREP="cid:a;b":Papier\, Karton,Altstoffe"#).unwrap();
+        assert_eq!(property.name, "CATEGORIES");
+        assert_eq!(property.tzid, None);
+        assert_eq!(
+            split_text(property.value, ','),
+            vec!["Papier, Karton", "Altstoffe"]
+        );
+        assert_eq!(unescape(property.value), "Papier, Karton,Altstoffe");
+    }
+
+    #[test]
+    fn options_are_validated() {
+        let options = |raw: &str| -> toml::Table { toml::from_str(raw).unwrap() };
+        assert!(
+            IcsWasteGrabber::from_options(
+                options("source = \"a.ics\"\n[types]\nKehricht = \"normal\""),
+                TZ
+            )
+            .is_ok()
+        );
+        let error = |raw: &str| match IcsWasteGrabber::from_options(options(raw), TZ) {
+            Ok(_) => panic!("accepted {}", raw),
+            Err(e) => e,
+        };
+        assert!(
+            error("source = \"a.ics\"\n[types]\nGlas = \"glass\"")
+                .starts_with("types.Glas: unknown trash type 'glass', expected one of")
+        );
+        assert!(error("source = \"a.ics\"\n[types]").contains("at least one"));
+        assert!(error("source = \"\"\n[types]\nKehricht = \"normal\"").contains("source"));
+    }
+}
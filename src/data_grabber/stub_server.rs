@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// A canned answer to a GET request.
+#[derive(Clone)]
+pub struct Response {
+    pub status: u16,
+    pub body: Vec<u8>,
+    /// Sent as `ETag`. A request naming it in `If-None-Match` gets
+    /// 304 Not Modified instead.
+    pub etag: Option<String>,
+}
+
+impl Response {
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Response {
+            status: 200,
+            body: body.into(),
+            etag: None,
+        }
+    }
+
+    /// The sample response in `tests/fixtures/{path}`.
+    pub fn fixture(path: &str) -> Self {
+        let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), path);
+        Response::ok(std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e)))
+    }
+
+    pub fn status(status: u16) -> Self {
+        Response {
+            status,
+            body: Vec::new(),
+            etag: None,
+        }
+    }
+
+    pub fn with_etag(self, etag: &str) -> Self {
+        Response {
+            etag: Some(etag.to_string()),
+            ..self
+        }
+    }
+}
+
+/// A request the server received.
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub path: String,
+    /// By lowercase name.
+    pub headers: HashMap<String, String>,
+}
+
+/// An HTTP server on a local port that answers GET requests by path with
+/// canned responses, and any other path with 404. It stops when dropped.
+pub struct StubServer {
+    /// Where it listens, like `http://127.0.0.1:4711`, without a trailing
+    /// slash.
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl StubServer {
+    pub async fn start<'a>(routes: impl IntoIterator<Item = (&'a str, Response)>) -> StubServer {
+        let routes: Arc<HashMap<String, Response>> = Arc::new(
+            routes
+                .into_iter()
+                .map(|(path, response)| (path.to_string(), response))
+                .collect(),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&requests);
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let routes = Arc::clone(&routes);
+                let received = Arc::clone(&received);
+                tokio::spawn(async move {
+                    if let Err(e) = answer(stream, &routes, &received).await {
+                        tracing::warn!("Stub server failed to answer: {}", e);
+                    }
+                });
+            }
+        });
+        StubServer {
+            url,
+            requests,
+            task,
+        }
+    }
+
+    /// The requests received so far, in order.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for StubServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Read one request from `stream` and answer it, closing the connection.
+async fn answer(
+    mut stream: tokio::net::TcpStream,
+    routes: &HashMap<String, Response>,
+    received: &Mutex<Vec<Request>>,
+) -> std::io::Result<()> {
+    let mut raw = Vec::new();
+    let mut buffer = [0; 4096];
+    while !raw.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        raw.extend_from_slice(&buffer[..read]);
+    }
+    let head = String::from_utf8_lossy(&raw);
+    let mut lines = head.lines();
+    let path = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or_default()
+        .to_string();
+    let headers: HashMap<String, String> = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    let not_modified = |response: &Response| {
+        response.etag.is_some() && response.etag.as_ref() == headers.get("if-none-match")
+    };
+    let response = match routes.get(&path) {
+        Some(response) if not_modified(response) => Response {
+            status: 304,
+            body: Vec::new(),
+            etag: response.etag.clone(),
+        },
+        Some(response) => response.clone(),
+        None => Response::status(404),
+    };
+    received.lock().unwrap().push(Request { path, headers });
+
+    let mut head = format!(
+        "HTTP/1.1 {} Stub\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    if let Some(etag) = &response.etag {
+        head.push_str(&format!("ETag: {}\r\n", etag));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&response.body).await?;
+    stream.shutdown().await
+}
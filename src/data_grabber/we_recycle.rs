@@ -1,11 +1,106 @@
-use super::TrashType;
+use super::{TrashType, fetch};
 use crate::error::GstaldergeistError;
-use chrono::{self, Datelike, NaiveDate};
+use chrono::{self, Datelike, NaiveDate, Weekday};
+use core::fmt;
 use lopdf::Document;
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
 
-pub struct WeRecycleWasteGrabber;
+/// The page linking the calendar PDFs.
+pub const CALENDARS_URL: &str = "https://www.werecycle.ch/en/abholdaten/";
+/// The calendars number their regions in the tens; anything far beyond is a
+/// mistake.
+const MAX_REGION: u32 = 999;
+
+pub struct WeRecycleWasteGrabber {
+    /// Collection regions of the flat, as printed in the calendar.
+    regions: Regions,
+    /// The page linking the calendar PDFs, `CALENDARS_URL` but in tests.
+    calendars_url: String,
+}
+
+/// A region in the configuration: a number, or text in the notation of the
+/// calendar like `"19"` or `"7 - 20 + 23"`.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Region {
+    Number(u32),
+    Text(String),
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum RegionOption {
+    One(Region),
+    Several(Vec<Region>),
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Options {
+    region: RegionOption,
+}
+
+/// The regions `option` lists, or what is wrong with it.
+fn configured_regions(option: RegionOption) -> Result<Regions, String> {
+    let regions = match option {
+        RegionOption::One(region) => vec![region],
+        RegionOption::Several(regions) => regions,
+    };
+    let mut ranges = Vec::new();
+    for region in regions {
+        match region {
+            Region::Number(number) if number > MAX_REGION => {
+                return Err(format!(
+                    "region must be the number of a We-Recycle collection region, \
+                     got {}, which is above {}",
+                    number, MAX_REGION
+                ));
+            }
+            Region::Number(number) => ranges.push(number..=number),
+            Region::Text(text) => match text.parse::<Regions>() {
+                Ok(Regions(parsed)) => ranges.extend(parsed),
+                Err(e) => {
+                    return Err(format!(
+                        "region must be the number of a We-Recycle collection region, \
+                         or several like \"7 - 20 + 23\", got '{}': {}",
+                        text, e
+                    ));
+                }
+            },
+        }
+    }
+    if ranges.is_empty() {
+        return Err("region lists no We-Recycle collection region".to_string());
+    }
+    Ok(Regions(ranges))
+}
+
+impl WeRecycleWasteGrabber {
+    pub fn from_options(
+        options: toml::Table,
+        _timezone: chrono_tz::Tz,
+    ) -> Result<Box<dyn super::WasteGrabber>, String> {
+        let options: Options = super::provider_options(options)?;
+        let regions = configured_regions(options.region)?;
+        Ok(Box::new(WeRecycleWasteGrabber {
+            regions,
+            calendars_url: CALENDARS_URL.to_string(),
+        }))
+    }
+
+    /// Every region the current calendars serve, read with years near
+    /// `reference`. Like a provider, it gets the default timeout to answer.
+    pub async fn regions(reference: NaiveDate) -> Result<Regions, GstaldergeistError> {
+        let timeout = super::FetchPolicy::default().timeout;
+        let calendars = tokio::time::timeout(timeout, download_calendars(CALENDARS_URL, reference))
+            .await
+            .map_err(|_| GstaldergeistError::Other(format!("no answer within {:?}", timeout)))??;
+        let regions: Vec<Regions> = calendars.iter().map(Calendar::regions).collect();
+        Ok(Regions::union(&regions))
+    }
+}
 
 #[async_trait::async_trait]
 impl super::WasteGrabber for WeRecycleWasteGrabber {
@@ -14,54 +109,287 @@ impl super::WasteGrabber for WeRecycleWasteGrabber {
         from: NaiveDate,
         to: NaiveDate,
     ) -> Result<HashMap<NaiveDate, Vec<TrashType>>, GstaldergeistError> {
-        let extracted_dates = download_pdf().await?;
         let mut result = HashMap::new();
-        for date in extracted_dates {
-            if date > from && date <= to {
-                result
-                    .entry(date)
-                    .or_insert_with(Vec::new)
-                    .push(TrashType::WeRecycle);
+        for calendar in download_calendars(&self.calendars_url, from).await? {
+            if !calendar.unparsed.is_empty() {
+                tracing::warn!(
+                    "Skipped {} lines of a We-Recycle calendar: {:?}",
+                    calendar.unparsed.len(),
+                    calendar.unparsed
+                );
+            }
+            for collection in calendar.collections {
+                if collection.date > from
+                    && collection.date <= to
+                    && self.regions.overlaps(&collection.regions)
+                {
+                    result
+                        .entry(collection.date)
+                        .or_insert_with(Vec::new)
+                        .push(TrashType::WeRecycle);
+                }
             }
         }
         Ok(result)
     }
 }
 
-fn regex_caps_to_datetime(caps: &regex::Captures) -> Option<NaiveDate> {
-    let date = &caps[1];
+/// The regions a collection serves, as printed in the calendar: numbers and
+/// ranges like `7 - 20`, separated by `+`, `,` or spaces.
+#[derive(Debug, Default, PartialEq)]
+pub struct Regions(Vec<RangeInclusive<u32>>);
 
-    if let Some(regions) = caps.get(3) {
-        if regions.as_str().contains("19") {
-            let current_year = chrono::Utc::now().date_naive().year();
-            let naive_date =
-                chrono::NaiveDate::parse_from_str(&format!("{}{}", date, current_year), "%d.%m.%Y")
-                    .ok()?;
-            return Some(naive_date);
+impl Regions {
+    /// Whether some region is in both.
+    pub fn overlaps(&self, other: &Regions) -> bool {
+        self.0.iter().any(|range| {
+            other
+                .0
+                .iter()
+                .any(|theirs| range.start() <= theirs.end() && theirs.start() <= range.end())
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Every region in any of `all`, written as briefly as possible: in
+    /// order, with consecutive ones as a range.
+    pub fn union<'a>(all: impl IntoIterator<Item = &'a Regions>) -> Self {
+        let mut ranges: Vec<RangeInclusive<u32>> = all
+            .into_iter()
+            .flat_map(|regions| regions.0.clone())
+            .collect();
+        ranges.sort_by_key(|range| *range.start());
+        let mut merged: Vec<RangeInclusive<u32>> = Vec::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if *range.start() <= last.end().saturating_add(1) => {
+                    *last = *last.start()..=*range.end().max(last.end());
+                }
+                _ => merged.push(range),
+            }
         }
+        Regions(merged)
     }
-    None
 }
 
-fn extract_dates_from_txt(text: String) -> Result<Vec<NaiveDate>, GstaldergeistError> {
-    let mut result = Vec::new();
+/// Written as in the calendar, e.g. `1 - 6 + 19`.
+impl fmt::Display for Regions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ranges: Vec<String> = self
+            .0
+            .iter()
+            .map(|range| {
+                if range.start() == range.end() {
+                    range.start().to_string()
+                } else {
+                    format!("{} - {}", range.start(), range.end())
+                }
+            })
+            .collect();
+        write!(f, "{}", ranges.join(" + "))
+    }
+}
+
+impl std::str::FromStr for Regions {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        #[derive(PartialEq)]
+        enum Token {
+            Number(u32),
+            Dash,
+            Separator,
+        }
+        let mut tokens = Vec::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '0'..='9' => {
+                    let mut number = c.to_string();
+                    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                        number.push(digit);
+                    }
+                    let number = number
+                        .parse()
+                        .ok()
+                        .filter(|number| *number <= MAX_REGION)
+                        .ok_or_else(|| format!("region {} is too large", number))?;
+                    tokens.push(Token::Number(number));
+                }
+                // PDFs often set ranges with an en dash.
+                '-' | '–' => tokens.push(Token::Dash),
+                '+' | ',' => tokens.push(Token::Separator),
+                c if c.is_whitespace() => {}
+                c => return Err(format!("unexpected '{}' among the regions", c)),
+            }
+        }
 
-    let date_pattern = r"(\d{1,2}\.\d{1,2}\.)";
-    let weekday_pattern = r"([A-Z]{2})";
-    let regions_pattern = r"([\d\s\+\-]+(?:\s+\d+\s*-\s*\d+)?(?:\s+\d+\s*-\s*\d+)*)?";
-    let regex = format!(
-        "{}\\s+{}\\s*{}?\\s+",
-        date_pattern, weekday_pattern, regions_pattern
-    );
-    let re = Regex::new(&regex)?;
-
-    for caps in re.captures_iter(&text) {
-        if let Some(datetime) = regex_caps_to_datetime(&caps) {
-            result.push(datetime);
+        let mut ranges = Vec::new();
+        let mut tokens = tokens.into_iter().peekable();
+        loop {
+            let Some(Token::Number(first)) = tokens.next() else {
+                return Err("expected a region number".to_string());
+            };
+            let last = if tokens.next_if(|token| *token == Token::Dash).is_some() {
+                match tokens.next() {
+                    Some(Token::Number(last)) if last >= first => last,
+                    Some(Token::Number(last)) => {
+                        return Err(format!("region range {} - {} is backwards", first, last));
+                    }
+                    _ => return Err(format!("region range from {} has no end", first)),
+                }
+            } else {
+                first
+            };
+            ranges.push(first..=last);
+            match tokens.peek() {
+                None => return Ok(Regions(ranges)),
+                Some(Token::Separator) => {
+                    tokens.next();
+                }
+                // Regions may also be separated by spaces alone.
+                Some(Token::Number(_)) => {}
+                Some(Token::Dash) => return Err(format!("unexpected '-' after {}", last)),
+            }
         }
     }
-    result.sort();
-    Ok(result)
+}
+
+/// A collection day and the regions it serves.
+#[derive(Debug, PartialEq)]
+pub struct Collection {
+    pub date: NaiveDate,
+    pub regions: Regions,
+}
+
+/// What could be read from a calendar PDF.
+#[derive(Debug, Default, PartialEq)]
+pub struct Calendar {
+    pub collections: Vec<Collection>,
+    /// Rows that looked like collections but could not be read, each with
+    /// the reason.
+    pub unparsed: Vec<String>,
+}
+
+impl Calendar {
+    /// Every region some collection serves.
+    pub fn regions(&self) -> Regions {
+        Regions::union(
+            self.collections
+                .iter()
+                .map(|collection| &collection.regions),
+        )
+    }
+}
+
+/// The day a weekday abbreviation stands for, in German or English.
+fn parse_weekday(abbreviation: &str) -> Option<Weekday> {
+    match abbreviation.trim_end_matches(['.', ',']) {
+        "MO" => Some(Weekday::Mon),
+        "DI" | "TU" => Some(Weekday::Tue),
+        "MI" | "WE" => Some(Weekday::Wed),
+        "DO" | "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SO" | "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// The years a row's date may fall in: those the document mentions and one
+/// on either side, or the year around `reference` if it mentions none.
+fn candidate_years(
+    text: &str,
+    reference: NaiveDate,
+) -> Result<RangeInclusive<i32>, GstaldergeistError> {
+    let years: Vec<i32> = Regex::new(r"\b(20\d{2})\b")?
+        .captures_iter(text)
+        .filter_map(|caps| caps[1].parse().ok())
+        .collect();
+    let (first, last) = match (years.iter().min(), years.iter().max()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => (reference.year(), reference.year()),
+    };
+    Ok(first - 1..=last + 1)
+}
+
+/// Read the row after the date `day`.`month`., e.g. `MO 7 - 20`. The year
+/// is the one among `years` in which the date falls on the printed weekday,
+/// the one closest to `reference` should there be several.
+fn parse_row(
+    day: u32,
+    month: u32,
+    rest: &str,
+    years: &RangeInclusive<i32>,
+    reference: NaiveDate,
+) -> Result<Collection, String> {
+    let rest = rest.trim();
+    let (weekday, regions) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let weekday = parse_weekday(weekday).ok_or_else(|| format!("no weekday in '{}'", rest))?;
+    let date = years
+        .clone()
+        .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+        .filter(|date| date.weekday() == weekday)
+        .min_by_key(|date| (*date - reference).num_days().abs())
+        .ok_or_else(|| {
+            format!(
+                "{:02}.{:02}. is no {:?} in {}-{}",
+                day,
+                month,
+                weekday,
+                years.start(),
+                years.end()
+            )
+        })?;
+    let regions = regions.parse()?;
+    Ok(Collection { date, regions })
+}
+
+/// Read the collections from the text of a calendar PDF. Each row is a date
+/// without a year, a weekday and the regions served, like
+/// `07.01. MI 1 - 6 + 19`. Rows may share a line, as when tables are set
+/// side by side. The year is worked out from each row's weekday, near the
+/// years the document mentions or else near `reference`.
+pub fn parse_calendar(text: &str, reference: NaiveDate) -> Result<Calendar, GstaldergeistError> {
+    let years = candidate_years(text, reference)?;
+    // A full date like "01.10.2025" is not a row.
+    let date = Regex::new(r"(\d{1,2})\.(\d{1,2})\.(\d?)")?;
+    let mut calendar = Calendar::default();
+    for line in text.lines() {
+        let rows: Vec<regex::Captures> = date
+            .captures_iter(line)
+            .filter(|caps| caps[3].is_empty())
+            .collect();
+        for (index, caps) in rows.iter().enumerate() {
+            let start = caps.get(0).expect("the whole match").start();
+            let end = rows.get(index + 1).map_or(line.len(), |next| {
+                next.get(0).expect("the whole match").start()
+            });
+            let rest = &line[caps.get(0).expect("the whole match").end()..end];
+            let row = parse_row(
+                caps[1].parse().unwrap_or_default(),
+                caps[2].parse().unwrap_or_default(),
+                rest,
+                &years,
+                reference,
+            );
+            match row {
+                Ok(collection) => calendar.collections.push(collection),
+                Err(reason) => {
+                    calendar
+                        .unparsed
+                        .push(format!("{}: {}", line[start..end].trim(), reason))
+                }
+            }
+        }
+    }
+    calendar
+        .collections
+        .sort_by_key(|collection| collection.date);
+    Ok(calendar)
 }
 
 fn extract_text_with_lopdf(pdf_bytes: &[u8]) -> Result<String, GstaldergeistError> {
@@ -74,82 +402,457 @@ fn extract_text_with_lopdf(pdf_bytes: &[u8]) -> Result<String, GstaldergeistErro
     Ok(pdf_text)
 }
 
-async fn download_pdf() -> Result<Vec<NaiveDate>, GstaldergeistError> {
-    let url = "https://www.werecycle.ch/en/abholdaten/";
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()?;
-    let response = client.get(url).send().await?;
-    let body = response.text().await?;
+/// Text of each calendar PDF by URL, with the fingerprint of the PDF it
+/// was extracted from, so an unchanged PDF is not read again. A new PDF
+/// comes out every few months, so these are never dropped.
+static PDF_TEXTS: std::sync::Mutex<BTreeMap<String, (u64, String)>> =
+    std::sync::Mutex::new(BTreeMap::new());
+
+/// Text of the calendar PDF `pdf` downloaded from `url`.
+fn pdf_text(url: &str, pdf: &[u8]) -> Result<String, GstaldergeistError> {
+    let fingerprint = fetch::fingerprint(pdf);
+    let mut texts = PDF_TEXTS.lock().unwrap();
+    if let Some((extracted_from, text)) = texts.get(url)
+        && *extracted_from == fingerprint
+    {
+        return Ok(text.clone());
+    }
+    let text = extract_text_with_lopdf(pdf)?;
+    texts.insert(url.to_string(), (fingerprint, text.clone()));
+    Ok(text)
+}
+
+/// Every calendar linked from the collection dates page at `page_url`,
+/// read with years near `reference`.
+async fn download_calendars(
+    page_url: &str,
+    reference: NaiveDate,
+) -> Result<Vec<Calendar>, GstaldergeistError> {
+    let page = reqwest::Url::parse(page_url)
+        .map_err(|e| GstaldergeistError::Other(format!("bad page URL {}: {}", page_url, e)))?;
+    let body = fetch::get_text(page_url).await?;
     let regex = Regex::new(r#"href="([^"]+\d+.pdf)""#)?;
     let caps = regex.captures_iter(&body);
 
     let mut result = Vec::new();
 
     for cap in caps {
-        let pdf_url = cap
+        let href = cap
             .get(1)
             .ok_or(GstaldergeistError::Other("pdf url corrupted".to_string()))?
             .as_str();
-        let pdf = client.get(pdf_url).send().await?;
-        let pdf_bytes = pdf.bytes().await?;
-        let pdf_text = extract_text_with_lopdf(&pdf_bytes)?;
-        let dates = extract_dates_from_txt(pdf_text)?;
-
-        result.extend(dates);
+        // Links may be relative to the page.
+        let pdf_url = page
+            .join(href)
+            .map_err(|e| GstaldergeistError::Other(format!("bad pdf url {}: {}", href, e)))?
+            .to_string();
+        let pdf_bytes = fetch::get(&pdf_url).await?;
+        let pdf_text = pdf_text(&pdf_url, &pdf_bytes)?;
+        result.push(parse_calendar(&pdf_text, reference)?);
     }
-    result.sort();
     Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data_grabber::WasteGrabber;
+    use crate::data_grabber::stub_server::{Response, StubServer};
 
-    /// The PDF dates carry no year, so `extract_dates_from_txt` stamps them with
-    /// the current year. Tests build their expectations against the same year.
-    fn date(day: u32, month: u32) -> NaiveDate {
-        let year = chrono::Utc::now().date_naive().year();
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
         NaiveDate::from_ymd_opt(year, month, day).unwrap()
     }
 
+    fn regions(text: &str) -> Regions {
+        text.parse().unwrap()
+    }
+
+    /// The sample calendar `name` in `tests/fixtures/we_recycle`, read with
+    /// years near 2026-11-10. The samples are PDFs written by hand after the
+    /// layout of the published calendars; none of them is a genuine one, so
+    /// quirks of the text lopdf extracts from those are not covered until one
+    /// is captured as `tests/fixtures/README.md` describes.
+    fn fixture(name: &str) -> Calendar {
+        let path = format!(
+            "{}/tests/fixtures/we_recycle/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        let text = extract_text_with_lopdf(&std::fs::read(path).unwrap()).unwrap();
+        parse_calendar(&text, date(2026, 11, 10)).unwrap()
+    }
+
+    /// The dates of the collections serving `region`.
+    fn dates_for(calendar: &Calendar, region: u32) -> Vec<NaiveDate> {
+        calendar
+            .collections
+            .iter()
+            .filter(|collection| collection.regions.overlaps(&Regions(vec![region..=region])))
+            .map(|collection| collection.date)
+            .collect()
+    }
+
+    /// The dates for `region` in `text`, read as a calendar for 2026.
+    fn dates(text: &str, region: u32) -> Vec<NaiveDate> {
+        dates_for(&parse_calendar(text, date(2026, 1, 1)).unwrap(), region)
+    }
+
     #[test]
     fn keeps_only_collection_dates_for_region_19() {
-        // The flat sits in collection region 19, so only rows whose region
-        // column mentions "19" are relevant.
-        let dates = extract_dates_from_txt("05.06. FR 19 ".to_string()).unwrap();
-        assert_eq!(dates, vec![date(5, 6)]);
+        assert_eq!(dates("05.06. FR 19 ", 19), vec![date(2026, 6, 5)]);
     }
 
     #[test]
     fn ignores_rows_for_other_regions() {
-        let dates = extract_dates_from_txt("01.01. MO 20 ".to_string()).unwrap();
-        assert!(dates.is_empty());
+        assert!(dates("01.01. DO 20 ", 19).is_empty());
     }
 
     #[test]
     fn matches_region_19_within_a_range_of_regions() {
-        // Region 19 can appear among several space/plus/dash separated regions.
-        let dates = extract_dates_from_txt("12.07. SA 7 + 19 - 20 ".to_string()).unwrap();
-        assert_eq!(dates, vec![date(12, 7)]);
+        assert_eq!(dates("12.07. SO 7 + 19 - 20 ", 19), vec![date(2026, 7, 12)]);
     }
 
     #[test]
     fn ignores_rows_without_a_region_column() {
-        let dates = extract_dates_from_txt("05.06. FR ".to_string()).unwrap();
-        assert!(dates.is_empty());
+        assert!(dates("05.06. FR ", 19).is_empty());
     }
 
     #[test]
     fn returns_dates_sorted_ascending() {
-        let text = "12.07. SA 19 \n05.06. FR 19 \n09.06. TU 5 + 19 ".to_string();
-        let dates = extract_dates_from_txt(text).unwrap();
-        assert_eq!(dates, vec![date(5, 6), date(9, 6), date(12, 7)]);
+        let text = "12.07. SO 19 \n05.06. FR 19 \n09.06. TU 5 + 19 ";
+        assert_eq!(
+            dates(text, 19),
+            vec![date(2026, 6, 5), date(2026, 6, 9), date(2026, 7, 12)]
+        );
+    }
+
+    #[test]
+    fn matches_whole_region_numbers_only() {
+        let text = "05.06. FR 119 \n09.06. TU 1 + 9 \n12.07. SO 7 + 19 ";
+        assert_eq!(dates(text, 19), vec![date(2026, 7, 12)]);
+        assert_eq!(dates(text, 7), vec![date(2026, 7, 12)]);
     }
 
     #[test]
     fn returns_empty_when_no_dates_present() {
-        let dates = extract_dates_from_txt("no dates in this text".to_string()).unwrap();
-        assert!(dates.is_empty());
+        assert_eq!(
+            parse_calendar("no dates in this text", date(2026, 1, 1)).unwrap(),
+            Calendar::default()
+        );
+    }
+
+    #[test]
+    fn region_ranges_are_numeric() {
+        let range = regions("7 - 20");
+        let serves = |region: &str| range.overlaps(&regions(region));
+        assert!(serves("7") && serves("19") && serves("20"));
+        assert!(!serves("6") && !serves("21"));
+        assert!(!regions("119").overlaps(&regions("19")));
+        assert!(!regions("190").overlaps(&regions("19")));
+        assert!(!regions("1 + 9").overlaps(&regions("19")));
+    }
+
+    #[test]
+    fn region_parser_reads_single_regions() {
+        assert_eq!(regions("19"), Regions(vec![19..=19]));
+        assert_eq!(regions("  19\t"), Regions(vec![19..=19]));
+        assert_eq!(regions("07"), Regions(vec![7..=7]));
+        assert_eq!(regions("0"), Regions(vec![0..=0]));
+        assert_eq!(regions("999"), Regions(vec![999..=999]));
+    }
+
+    #[test]
+    fn region_parser_reads_ranges_with_either_dash() {
+        for text in ["7 - 20", "7-20", "7 -20", "7- 20", "7 – 20", "7–20"] {
+            assert_eq!(regions(text), Regions(vec![7..=20]), "{}", text);
+        }
+        assert_eq!(regions("5 - 5"), Regions(vec![5..=5]));
+    }
+
+    #[test]
+    fn region_parser_reads_every_separator() {
+        for text in ["1 + 9", "1+9", "1,9", "1 , 9", "1 9", "1\t9"] {
+            assert_eq!(regions(text), Regions(vec![1..=1, 9..=9]), "{}", text);
+        }
+        assert_eq!(
+            regions("1 - 6 + 19 - 20, 23 25–26"),
+            Regions(vec![1..=6, 19..=20, 23..=23, 25..=26])
+        );
+    }
+
+    #[test]
+    fn region_parser_rejects_missing_numbers() {
+        for text in ["", "   ", "+ 19", ", 19", "19 +", "19 + + 20", "- 19", "–"] {
+            assert_eq!(
+                text.parse::<Regions>(),
+                Err("expected a region number".to_string()),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn region_parser_rejects_broken_ranges() {
+        let error = |text: &str| text.parse::<Regions>().unwrap_err();
+        assert_eq!(error("19 -"), "region range from 19 has no end");
+        assert_eq!(error("19 - + 20"), "region range from 19 has no end");
+        assert_eq!(error("19 - - 20"), "region range from 19 has no end");
+        assert_eq!(error("20 - 19"), "region range 20 - 19 is backwards");
+        assert_eq!(error("1 - 6 - 9"), "unexpected '-' after 6");
+    }
+
+    #[test]
+    fn region_parser_rejects_other_text() {
+        let error = |text: &str| text.parse::<Regions>().unwrap_err();
+        assert_eq!(error("19a"), "unexpected 'a' among the regions");
+        assert_eq!(error("nineteen"), "unexpected 'n' among the regions");
+        assert_eq!(error("1; 9"), "unexpected ';' among the regions");
+        assert_eq!(error("1.5"), "unexpected '.' among the regions");
+        assert_eq!(error("1000"), "region 1000 is too large");
+        assert_eq!(error("1 - 4294967296"), "region 4294967296 is too large");
+    }
+
+    #[test]
+    fn regions_are_written_as_in_the_calendar() {
+        let union = Regions::union(&[
+            regions("19 + 2 - 4"),
+            regions("1 + 3 - 6 + 22"),
+            regions("21"),
+        ]);
+        assert_eq!(union.to_string(), "1 - 6 + 19 + 21 - 22");
+        assert_eq!(regions(&union.to_string()), union);
+        assert_eq!(
+            Regions::union(&[regions("0 - 999"), regions("19")]),
+            regions("0 - 999")
+        );
+        assert!(Regions::union(&[]).is_empty());
+        assert_eq!(Regions::default().to_string(), "");
+    }
+
+    #[test]
+    fn regions_overlap_if_they_share_one() {
+        let flat = regions("19 + 23");
+        assert!(flat.overlaps(&regions("1 - 6 + 19")));
+        assert!(flat.overlaps(&regions("20 - 999")));
+        assert!(regions("0 - 999").overlaps(&regions("999")));
+        assert!(!flat.overlaps(&regions("1 - 18 + 20 - 22 + 24")));
+        assert!(!flat.overlaps(&Regions::default()));
+    }
+
+    /// The regions configured by `options`, or what is wrong with them.
+    fn configured(options: &str) -> Result<Regions, String> {
+        let options: Options = toml::from_str(options).unwrap();
+        configured_regions(options.region)
+    }
+
+    #[test]
+    fn configured_regions_may_be_one_or_a_list() {
+        assert_eq!(configured("region = \"19\""), Ok(Regions(vec![19..=19])));
+        assert_eq!(configured("region = 19"), Ok(Regions(vec![19..=19])));
+        assert_eq!(
+            configured("region = \"7 - 20 + 23\""),
+            Ok(Regions(vec![7..=20, 23..=23]))
+        );
+        assert_eq!(
+            configured("region = [\"19\", 2, \"23 - 25\"]"),
+            Ok(Regions(vec![19..=19, 2..=2, 23..=25]))
+        );
+    }
+
+    #[test]
+    fn configured_regions_must_be_valid() {
+        assert_eq!(
+            configured("region = []"),
+            Err("region lists no We-Recycle collection region".to_string())
+        );
+        let err = configured("region = [19, \"20 - 7\"]").unwrap_err();
+        assert!(err.starts_with("region must be the number"), "{}", err);
+        assert!(
+            err.ends_with("got '20 - 7': region range 20 - 7 is backwards"),
+            "{}",
+            err
+        );
+        let err = configured("region = 4294967295").unwrap_err();
+        assert!(
+            err.ends_with("got 4294967295, which is above 999"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn reads_the_year_from_the_weekday() {
+        let calendar = parse_calendar("07.01. MI 19\n07.01. TH 19\n", date(2026, 12, 20)).unwrap();
+        assert_eq!(
+            dates_for(&calendar, 19),
+            vec![date(2026, 1, 7), date(2027, 1, 7)]
+        );
+    }
+
+    #[test]
+    fn reads_rows_sharing_a_line() {
+        let calendar =
+            parse_calendar("07.01. MI 1 - 6 07.07. DI 7 - 20", date(2026, 1, 1)).unwrap();
+        assert_eq!(dates_for(&calendar, 3), vec![date(2026, 1, 7)]);
+        assert_eq!(dates_for(&calendar, 19), vec![date(2026, 7, 7)]);
+        assert!(calendar.unparsed.is_empty());
+    }
+
+    #[test]
+    fn lists_rows_it_cannot_read() {
+        let calendar = parse_calendar(
+            "Stand 01.10.2025\n05.06. FR\n05.06. XX 19\n31.02. MO 19\n05.06. FR 19 -\n",
+            date(2026, 1, 1),
+        )
+        .unwrap();
+        assert!(calendar.collections.is_empty());
+        assert_eq!(
+            calendar.unparsed,
+            vec![
+                "05.06. FR: expected a region number",
+                "05.06. XX 19: no weekday in 'XX 19'",
+                "31.02. MO 19: 31.02. is no Mon in 2024-2026",
+                "05.06. FR 19 -: region range from 19 has no end",
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_a_pdf_again_only_once_it_changed() {
+        let url = "https://example.com/reads_a_pdf_again_only_once_it_changed.pdf";
+        PDF_TEXTS.lock().unwrap().insert(
+            url.to_string(),
+            (fetch::fingerprint(b"old pdf"), "05.06. FR 19".to_string()),
+        );
+        assert_eq!(pdf_text(url, b"old pdf").unwrap(), "05.06. FR 19");
+        assert!(pdf_text(url, b"new pdf").is_err());
+    }
+
+    /// The sample collection dates page and the calendars it links, less
+    /// the one at `missing` if any.
+    async fn calendars_server(missing: Option<&str>) -> StubServer {
+        let routes = [
+            ("/en/abholdaten/", "we_recycle/abholdaten.html"),
+            (
+                "/wp-content/uploads/2025/10/abholdaten-2026.pdf",
+                "we_recycle/abholdaten-2026.pdf",
+            ),
+            (
+                "/wp-content/uploads/2026/09/collection-dates-winter-2026.pdf",
+                "we_recycle/collection-dates-winter.pdf",
+            ),
+        ];
+        StubServer::start(
+            routes
+                .into_iter()
+                .filter(|(path, _)| Some(*path) != missing)
+                .map(|(path, fixture)| (path, Response::fixture(fixture))),
+        )
+        .await
+    }
+
+    fn grabber(server: &StubServer, region: &str) -> WeRecycleWasteGrabber {
+        WeRecycleWasteGrabber {
+            regions: regions(region),
+            calendars_url: format!("{}/en/abholdaten/", server.url),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetches_the_collections_of_the_region_from_the_sample_site() {
+        let server = calendars_server(None).await;
+        let trashes = grabber(&server, "19")
+            .get_trashes(date(2026, 11, 10), date(2026, 12, 31))
+            .await
+            .unwrap();
+        let mut dates: Vec<NaiveDate> = trashes.keys().copied().collect();
+        dates.sort();
+        assert_eq!(
+            dates,
+            vec![date(2026, 11, 16), date(2026, 12, 1), date(2026, 12, 29)]
+        );
+        assert!(
+            trashes
+                .values()
+                .all(|trashes| *trashes == vec![TrashType::WeRecycle])
+        );
+        let paths: Vec<String> = server
+            .requests()
+            .into_iter()
+            .map(|request| request.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/en/abholdaten/",
+                "/wp-content/uploads/2025/10/abholdaten-2026.pdf",
+                "/wp-content/uploads/2026/09/collection-dates-winter-2026.pdf",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn lists_the_regions_of_every_linked_calendar() {
+        let server = calendars_server(None).await;
+        let calendars =
+            download_calendars(&grabber(&server, "19").calendars_url, date(2026, 11, 10))
+                .await
+                .unwrap();
+        let regions: Vec<Regions> = calendars.iter().map(Calendar::regions).collect();
+        assert_eq!(
+            Regions::union(&regions).to_string(),
+            "1 - 21 + 23 - 25 + 119 + 190"
+        );
+    }
+
+    #[tokio::test]
+    async fn a_calendar_the_site_cannot_send_is_an_error() {
+        let server = calendars_server(Some(
+            "/wp-content/uploads/2026/09/collection-dates-winter-2026.pdf",
+        ))
+        .await;
+        let result = grabber(&server, "19")
+            .get_trashes(date(2026, 11, 10), date(2026, 12, 31))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parses_the_side_by_side_tables_of_a_calendar_year() {
+        let calendar = fixture("abholdaten-2026.pdf");
+        assert_eq!(
+            dates_for(&calendar, 19),
+            vec![
+                date(2026, 2, 8),
+                date(2026, 4, 7),
+                date(2026, 6, 6),
+                date(2026, 7, 7),
+                date(2026, 9, 6),
+                date(2026, 11, 8),
+            ]
+        );
+        assert_eq!(calendar.collections.len(), 12);
+        assert_eq!(calendar.regions().to_string(), "1 - 21 + 23 - 25");
+        assert_eq!(calendar.unparsed.len(), 2, "{:?}", calendar.unparsed);
+    }
+
+    #[test]
+    fn parses_a_calendar_across_the_turn_of_the_year() {
+        let calendar = fixture("collection-dates-winter.pdf");
+        assert_eq!(
+            dates_for(&calendar, 19),
+            vec![
+                date(2026, 11, 16),
+                date(2026, 12, 1),
+                date(2026, 12, 29),
+                date(2027, 1, 12),
+                date(2027, 2, 9),
+            ]
+        );
+        assert_eq!(
+            calendar.unparsed,
+            vec!["08.03. XX 19: no weekday in 'XX 19'"]
+        );
     }
 }
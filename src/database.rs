@@ -1,23 +1,216 @@
-use crate::data_grabber::TrashType;
+use crate::chores::{ChoreState, ChoreStates};
+use crate::data_grabber::{ProviderTrashes, TrashType};
 use crate::error::GstaldergeistError;
+use crate::roster::{Absence, Debt, Roster, RosterMember};
+use crate::scheduler::JobRecord;
+use crate::stats::DutyLogEntry;
+use crate::sync::{self, ScheduleChange};
+use crate::{SharedTaskState, TaskState};
 use chrono::NaiveDate;
-use rusqlite::Connection;
+use rusqlite::{Connection, OptionalExtension};
 use std::collections::HashMap;
 
-const DB_PATH: &str = "/data/gstaldergeist.db";
+/// Set once at startup from the configuration.
+static DB_PATH: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
 
-/// Open the database and make sure the `trashes` table exists.
+/// Use the database at `path` from now on. Only the first call counts.
+pub fn set_path(path: std::path::PathBuf) {
+    if DB_PATH.set(path).is_err() {
+        tracing::warn!("The database path is already set");
+    }
+}
+
+/// Open the database. The schema is brought up to date once at startup by
+/// `migrate`.
 fn open_db() -> Result<Connection, GstaldergeistError> {
-    let conn = Connection::open(DB_PATH)?;
+    let path = DB_PATH.get().ok_or_else(|| {
+        GstaldergeistError::ConfigError("the database path is not set".to_string())
+    })?;
+    Ok(Connection::open(path)?)
+}
+
+/// The schema changes in order; the schema version is how many of them ran.
+/// Append new ones, never edit one that was released.
+const MIGRATIONS: &[&str] = &[
+    // 1: The tables as they were before migrations, so an existing database
+    // keeps its data.
+    "CREATE TABLE IF NOT EXISTS trashes (date DATE, waste_type INTEGER);
+    -- Single-row table: the `id = 0` check keeps it from ever growing.
+    -- It replaces `task_state`, whose trigger the scheduled jobs took over.
+    CREATE TABLE IF NOT EXISTS trash_task (
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        state INTEGER NOT NULL,
+        message_id INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS chore_state (
+        chore_id TEXT PRIMARY KEY,
+        state INTEGER NOT NULL,
+        turn INTEGER NOT NULL,
+        next_trigger DATETIME NOT NULL,
+        message_id INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS roster_members (
+        position INTEGER PRIMARY KEY,
+        chat_id INTEGER NOT NULL UNIQUE,
+        away INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS roster_state (
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        current INTEGER NOT NULL,
+        week DATE NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS roster_substitutes (
+        week DATE PRIMARY KEY,
+        chat_id INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS roster_debts (
+        id INTEGER PRIMARY KEY,
+        debtor INTEGER NOT NULL,
+        creditor INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS absences (
+        id INTEGER PRIMARY KEY,
+        chat_id INTEGER NOT NULL,
+        from_date DATE NOT NULL,
+        until_date DATE NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS duty_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp DATETIME NOT NULL,
+        chat_id INTEGER NOT NULL,
+        chore TEXT NOT NULL,
+        trashes TEXT NOT NULL,
+        outcome INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS swap_requests (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        requester INTEGER NOT NULL,
+        week DATE NOT NULL,
+        accepted_by INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS swap_offers (
+        swap_id INTEGER NOT NULL,
+        chat_id INTEGER NOT NULL,
+        message_id INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS jobs (
+        name TEXT PRIMARY KEY,
+        schedule TEXT NOT NULL,
+        next_run DATETIME,
+        last_run DATETIME
+    );",
+    // 2: Trashes remember their provider and when they were fetched, and a
+    // date lists each type once.
+    "CREATE TABLE trashes_new (
+        date DATE NOT NULL,
+        waste_type INTEGER NOT NULL,
+        source TEXT NOT NULL,
+        fetched_at DATETIME NOT NULL,
+        UNIQUE (date, waste_type)
+    );
+    INSERT OR IGNORE INTO trashes_new (date, waste_type, source, fetched_at)
+        SELECT date, waste_type, 'unknown', strftime('%Y-%m-%d %H:%M:%S+00:00', 'now')
+        FROM trashes WHERE date IS NOT NULL AND waste_type IS NOT NULL;
+    DROP TABLE trashes;
+    ALTER TABLE trashes_new RENAME TO trashes;",
+    // 3: When each provider was last synced, so the stored collections can
+    // stand in while it is unreachable.
+    "CREATE TABLE trash_sync (
+        source TEXT PRIMARY KEY,
+        synced_at DATETIME NOT NULL,
+        until DATE NOT NULL
+    );",
+    // 4: Who answered a request to step in for the food master, so only the
+    // first helper counts.
+    "CREATE TABLE covers (
+        day DATE NOT NULL,
+        master_id INTEGER NOT NULL,
+        covered_by INTEGER NOT NULL,
+        PRIMARY KEY (day, master_id)
+    );",
+    // 5: The day the trash reminder was sent on.
+    "ALTER TABLE trash_task ADD COLUMN sent_on DATE;",
+];
+
+/// Run the migrations the database at the configured path has not seen yet.
+pub fn migrate() -> Result<(), GstaldergeistError> {
+    let mut conn = open_db()?;
+    migrate_db(&mut conn)
+}
+
+fn migrate_db(conn: &mut Connection) -> Result<(), GstaldergeistError> {
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS trashes (date DATE, waste_type INTEGER)",
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            id INTEGER PRIMARY KEY CHECK (id = 0),
+            version INTEGER NOT NULL
+        )",
         [],
     )?;
-    Ok(conn)
+    let version: usize = conn
+        .query_row(
+            "SELECT version FROM schema_version WHERE id = 0",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?
+        .unwrap_or(0);
+    if version > MIGRATIONS.len() {
+        return Err(GstaldergeistError::Other(format!(
+            "the database has schema version {}, but this build only knows up to {}",
+            version,
+            MIGRATIONS.len()
+        )));
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        // Each migration applies fully or not at all.
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO schema_version (id, version) VALUES (0, ?1)",
+            [index + 1],
+        )?;
+        tx.commit()?;
+        tracing::info!("Migrated the database to schema version {}", index + 1);
+    }
+    Ok(())
+}
+
+/// Check that the database can be opened and answers a query.
+#[cfg(feature = "http")]
+pub fn ping() -> Result<(), GstaldergeistError> {
+    let conn = open_db()?;
+    conn.query_row("SELECT 1", [], |_| Ok(()))?;
+    Ok(())
 }
 
 pub fn get_all_trashes() -> Result<HashMap<NaiveDate, Vec<TrashType>>, GstaldergeistError> {
     let conn = open_db()?;
+    read_trashes(&conn)
+}
+
+/// Store what each provider reported at `fetched_at` for the days after
+/// `from` until its `until`, and return how that changed the collections
+/// stored for those days before. Other days are kept as they are.
+pub fn sync_trashes(
+    by_provider: &[ProviderTrashes],
+    from: NaiveDate,
+    fetched_at: chrono::DateTime<chrono_tz::Tz>,
+) -> Result<Vec<ScheduleChange>, GstaldergeistError> {
+    let mut conn = open_db()?;
+    sync_trashes_in(&mut conn, by_provider, from, fetched_at)
+}
+
+/// When each provider was last synced, and up to which day, by name.
+pub fn load_trash_syncs(
+    tz: chrono_tz::Tz,
+) -> Result<HashMap<String, (chrono::DateTime<chrono_tz::Tz>, NaiveDate)>, GstaldergeistError> {
+    let conn = open_db()?;
+    read_trash_syncs(&conn, tz)
+}
+
+fn read_trashes(
+    conn: &Connection,
+) -> Result<HashMap<NaiveDate, Vec<TrashType>>, GstaldergeistError> {
     let mut stmt = conn.prepare("SELECT date, waste_type FROM trashes")?;
     let rows = stmt.query_map([], |row| {
         let date: NaiveDate = row.get(0)?;
@@ -32,17 +225,1006 @@ pub fn get_all_trashes() -> Result<HashMap<NaiveDate, Vec<TrashType>>, Gstalderg
     Ok(trashes)
 }
 
-pub fn set_trashes(trashes: &HashMap<NaiveDate, Vec<TrashType>>) -> Result<(), GstaldergeistError> {
+fn sync_trashes_in(
+    conn: &mut Connection,
+    by_provider: &[ProviderTrashes],
+    from: NaiveDate,
+    fetched_at: chrono::DateTime<chrono_tz::Tz>,
+) -> Result<Vec<ScheduleChange>, GstaldergeistError> {
+    let tx = conn.transaction()?;
+    let mut changes = Vec::new();
+    for provider in by_provider {
+        let (source, to) = (&provider.name, provider.until);
+        // Collections stored before their source was recorded are replaced
+        // without a word; any provider may have reported them.
+        tx.execute(
+            "DELETE FROM trashes WHERE source = 'unknown' AND date > ?1 AND date <= ?2",
+            rusqlite::params![from, to],
+        )?;
+        let stored = {
+            let mut stmt = tx.prepare(
+                "SELECT date, waste_type FROM trashes
+                WHERE source = ?1 AND date > ?2 AND date <= ?3
+                ORDER BY date, waste_type",
+            )?;
+            stmt.query_map(rusqlite::params![source, from, to], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<Result<Vec<(NaiveDate, TrashType)>, _>>()?
+        };
+        let mut fetched: Vec<(NaiveDate, TrashType)> = provider
+            .dates
+            .iter()
+            .flat_map(|(date, types)| types.iter().map(|trash| (*date, *trash)))
+            .collect();
+        fetched.sort_by_key(|(date, _)| *date);
+        for (date, trash) in stored.iter().filter(|entry| !fetched.contains(entry)) {
+            tx.execute(
+                "DELETE FROM trashes WHERE date = ?1 AND waste_type = ?2 AND source = ?3",
+                rusqlite::params![date, trash, source],
+            )?;
+        }
+        // Two providers reporting the same type on the same day make one
+        // collection, credited to the later one.
+        for (date, trash) in &fetched {
+            tx.execute(
+                "INSERT INTO trashes (date, waste_type, source, fetched_at) VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT (date, waste_type) DO UPDATE
+                SET source = excluded.source, fetched_at = excluded.fetched_at",
+                rusqlite::params![date, trash, source, utc(fetched_at)],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO trash_sync (source, synced_at, until) VALUES (?1, ?2, ?3)",
+            rusqlite::params![source, utc(fetched_at), to],
+        )?;
+        changes.extend(sync::diff(&stored, &fetched, to));
+    }
+    tx.commit()?;
+    Ok(changes)
+}
+
+fn read_trash_syncs(
+    conn: &Connection,
+    tz: chrono_tz::Tz,
+) -> Result<HashMap<String, (chrono::DateTime<chrono_tz::Tz>, NaiveDate)>, GstaldergeistError> {
+    let mut stmt = conn.prepare("SELECT source, synced_at, until FROM trash_sync")?;
+    let rows = stmt.query_map([], |row| {
+        let synced_at: chrono::DateTime<chrono::Utc> = row.get(1)?;
+        Ok((row.get(0)?, (in_tz(synced_at, tz), row.get(2)?)))
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+pub fn load_task_state() -> Result<Option<SharedTaskState>, GstaldergeistError> {
+    let conn = open_db()?;
+    read_task_state(&conn)
+}
+
+pub fn save_task_state(task: &SharedTaskState) -> Result<(), GstaldergeistError> {
+    let conn = open_db()?;
+    write_task_state(&conn, task)
+}
+
+fn read_task_state(conn: &Connection) -> Result<Option<SharedTaskState>, GstaldergeistError> {
+    let task = conn
+        .query_row(
+            "SELECT state, message_id, sent_on FROM trash_task WHERE id = 0",
+            [],
+            |row| {
+                let state: TaskState = row.get(0)?;
+                Ok(SharedTaskState {
+                    state,
+                    message_id: row.get(1)?,
+                    sent_on: row.get(2)?,
+                })
+            },
+        )
+        .optional()?;
+    Ok(task)
+}
+
+fn write_task_state(conn: &Connection, task: &SharedTaskState) -> Result<(), GstaldergeistError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO trash_task (id, state, message_id, sent_on)
+         VALUES (0, ?1, ?2, ?3)",
+        rusqlite::params![task.state, task.message_id, task.sent_on],
+    )?;
+    Ok(())
+}
+
+pub fn load_chore_states(tz: chrono_tz::Tz) -> Result<ChoreStates, GstaldergeistError> {
+    let conn = open_db()?;
+    read_chore_states(&conn, tz)
+}
+
+pub fn save_chore_state(chore_id: &str, chore: &ChoreState) -> Result<(), GstaldergeistError> {
+    let conn = open_db()?;
+    write_chore_state(&conn, chore_id, chore)
+}
+
+fn read_chore_states(
+    conn: &Connection,
+    tz: chrono_tz::Tz,
+) -> Result<ChoreStates, GstaldergeistError> {
+    let mut stmt =
+        conn.prepare("SELECT chore_id, state, turn, next_trigger, message_id FROM chore_state")?;
+    let rows = stmt.query_map([], |row| {
+        let chore_id: String = row.get(0)?;
+        let turn: i64 = row.get(2)?;
+        Ok((
+            chore_id,
+            ChoreState {
+                state: row.get(1)?,
+                turn: turn as usize,
+                next_trigger: in_tz(row.get(3)?, tz),
+                message_id: row.get(4)?,
+            },
+        ))
+    })?;
+    let mut chores = ChoreStates::new();
+    for row in rows {
+        let (chore_id, chore) = row?;
+        chores.insert(chore_id, chore);
+    }
+    Ok(chores)
+}
+
+fn write_chore_state(
+    conn: &Connection,
+    chore_id: &str,
+    chore: &ChoreState,
+) -> Result<(), GstaldergeistError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO chore_state (chore_id, state, turn, next_trigger, message_id)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            chore_id,
+            chore.state,
+            chore.turn as i64,
+            utc(chore.next_trigger),
+            chore.message_id
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn load_roster() -> Result<Option<Roster>, GstaldergeistError> {
     let conn = open_db()?;
+    read_roster(&conn)
+}
+
+/// Replace the stored roster with what `update` makes of it, see
+/// `update_roster_in`.
+pub fn update_roster<T>(
+    update: impl FnOnce(Option<Roster>) -> (Roster, T),
+) -> Result<T, GstaldergeistError> {
+    let mut conn = open_db()?;
+    update_roster_in(&mut conn, update)
+}
 
-    // delete all rows
-    conn.execute("DELETE FROM trashes", [])?;
+/// Read the roster, let `update` change it and write it back in one
+/// transaction. The transaction takes the write lock before reading, so two
+/// updates at once run one after the other and neither loses the other's
+/// change.
+fn update_roster_in<T>(
+    conn: &mut Connection,
+    update: impl FnOnce(Option<Roster>) -> (Roster, T),
+) -> Result<T, GstaldergeistError> {
+    let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+    let (roster, result) = update(read_roster(&tx)?);
+    write_roster(&tx, &roster)?;
+    tx.commit()?;
+    Ok(result)
+}
+
+fn read_roster(conn: &Connection) -> Result<Option<Roster>, GstaldergeistError> {
+    let state = conn
+        .query_row(
+            "SELECT current, week FROM roster_state WHERE id = 0",
+            [],
+            |row| {
+                let current: i64 = row.get(0)?;
+                let week: NaiveDate = row.get(1)?;
+                Ok((current as usize, week))
+            },
+        )
+        .optional()?;
+    let Some((current, week)) = state else {
+        return Ok(None);
+    };
+    let mut stmt = conn.prepare("SELECT chat_id, away FROM roster_members ORDER BY position")?;
+    let members = stmt
+        .query_map([], |row| {
+            Ok(RosterMember {
+                chat_id: row.get(0)?,
+                away: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let substitute = conn
+        .query_row(
+            "SELECT chat_id FROM roster_substitutes WHERE week = ?1",
+            [week],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let mut stmt = conn.prepare("SELECT debtor, creditor FROM roster_debts ORDER BY id")?;
+    let debts = stmt
+        .query_map([], |row| {
+            Ok(Debt {
+                debtor: row.get(0)?,
+                creditor: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut stmt =
+        conn.prepare("SELECT chat_id, from_date, until_date FROM absences ORDER BY id")?;
+    let absences = stmt
+        .query_map([], |row| {
+            Ok(Absence {
+                chat_id: row.get(0)?,
+                from: row.get(1)?,
+                until: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some(Roster {
+        members,
+        current,
+        week,
+        substitute,
+        debts,
+        absences,
+    }))
+}
 
-    let mut stmt = conn.prepare("INSERT INTO trashes (date, waste_type) VALUES (?1, ?2)")?;
-    for (date, waste_types) in trashes {
-        for waste_type in waste_types {
-            stmt.execute(rusqlite::params![date, waste_type])?;
+/// Replace the stored roster. Run inside a transaction so a crash never
+/// leaves a half-written order behind.
+fn write_roster(tx: &Connection, roster: &Roster) -> Result<(), GstaldergeistError> {
+    tx.execute("DELETE FROM roster_members", [])?;
+    {
+        let mut stmt =
+            tx.prepare("INSERT INTO roster_members (position, chat_id, away) VALUES (?1, ?2, ?3)")?;
+        for (position, member) in roster.members.iter().enumerate() {
+            stmt.execute(rusqlite::params![
+                position as i64,
+                member.chat_id,
+                member.away
+            ])?;
         }
     }
+    tx.execute(
+        "INSERT OR REPLACE INTO roster_state (id, current, week) VALUES (0, ?1, ?2)",
+        rusqlite::params![roster.current as i64, roster.week],
+    )?;
+    // Substitutes are kept per week as a record of past swaps.
+    match roster.substitute {
+        Some(chat_id) => tx.execute(
+            "INSERT OR REPLACE INTO roster_substitutes (week, chat_id) VALUES (?1, ?2)",
+            rusqlite::params![roster.week, chat_id],
+        )?,
+        None => tx.execute(
+            "DELETE FROM roster_substitutes WHERE week = ?1",
+            [roster.week],
+        )?,
+    };
+    tx.execute("DELETE FROM roster_debts", [])?;
+    {
+        let mut stmt =
+            tx.prepare("INSERT INTO roster_debts (id, debtor, creditor) VALUES (?1, ?2, ?3)")?;
+        for (id, debt) in roster.debts.iter().enumerate() {
+            stmt.execute(rusqlite::params![id as i64, debt.debtor, debt.creditor])?;
+        }
+    }
+    tx.execute("DELETE FROM absences", [])?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO absences (id, chat_id, from_date, until_date) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (id, absence) in roster.absences.iter().enumerate() {
+            stmt.execute(rusqlite::params![
+                id as i64,
+                absence.chat_id,
+                absence.from,
+                absence.until
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapRequest {
+    pub id: i64,
+    pub requester: i64,
+    /// Monday of the week the requester wants to hand over.
+    pub week: NaiveDate,
+}
+
+pub fn create_swap_request(requester: i64, week: NaiveDate) -> Result<i64, GstaldergeistError> {
+    let conn = open_db()?;
+    insert_swap_request(&conn, requester, week)
+}
+
+pub fn add_swap_offer(
+    swap_id: i64,
+    chat_id: i64,
+    message_id: i32,
+) -> Result<(), GstaldergeistError> {
+    let conn = open_db()?;
+    conn.execute(
+        "INSERT INTO swap_offers (swap_id, chat_id, message_id) VALUES (?1, ?2, ?3)",
+        rusqlite::params![swap_id, chat_id, message_id],
+    )?;
+    Ok(())
+}
+
+/// Offers sent for a swap request, as `(chat_id, message_id)` pairs.
+pub fn get_swap_offers(swap_id: i64) -> Result<Vec<(i64, i32)>, GstaldergeistError> {
+    let conn = open_db()?;
+    let mut stmt =
+        conn.prepare("SELECT chat_id, message_id FROM swap_offers WHERE swap_id = ?1")?;
+    let offers = stmt
+        .query_map([swap_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(offers)
+}
+
+/// Mark an open swap request as accepted by `chat_id`. Returns `None` if it
+/// does not exist or someone else was faster.
+pub fn claim_swap_request(
+    swap_id: i64,
+    chat_id: i64,
+) -> Result<Option<SwapRequest>, GstaldergeistError> {
+    let conn = open_db()?;
+    claim_swap_request_in(&conn, swap_id, chat_id)
+}
+
+/// Undo `chat_id`'s claim on a swap request whose swap could not be made,
+/// so that someone else can still accept it.
+pub fn release_swap_request(swap_id: i64, chat_id: i64) -> Result<(), GstaldergeistError> {
+    let conn = open_db()?;
+    release_swap_request_in(&conn, swap_id, chat_id)
+}
+
+fn insert_swap_request(
+    conn: &Connection,
+    requester: i64,
+    week: NaiveDate,
+) -> Result<i64, GstaldergeistError> {
+    conn.execute(
+        "INSERT INTO swap_requests (requester, week) VALUES (?1, ?2)",
+        rusqlite::params![requester, week],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn claim_swap_request_in(
+    conn: &Connection,
+    swap_id: i64,
+    chat_id: i64,
+) -> Result<Option<SwapRequest>, GstaldergeistError> {
+    // The `accepted_by IS NULL` condition makes the claim atomic: only the
+    // first acceptance updates the row.
+    let claimed = conn.execute(
+        "UPDATE swap_requests SET accepted_by = ?1 WHERE id = ?2 AND accepted_by IS NULL",
+        rusqlite::params![chat_id, swap_id],
+    )?;
+    if claimed == 0 {
+        return Ok(None);
+    }
+    let request = conn.query_row(
+        "SELECT id, requester, week FROM swap_requests WHERE id = ?1",
+        [swap_id],
+        |row| {
+            Ok(SwapRequest {
+                id: row.get(0)?,
+                requester: row.get(1)?,
+                week: row.get(2)?,
+            })
+        },
+    )?;
+    Ok(Some(request))
+}
+
+/// Record `chat_id` as stepping in for `master_id` on the request for help
+/// sent on `day`. Returns `false` if someone else already did.
+pub fn claim_cover(
+    day: NaiveDate,
+    master_id: i64,
+    chat_id: i64,
+) -> Result<bool, GstaldergeistError> {
+    let conn = open_db()?;
+    claim_cover_in(&conn, day, master_id, chat_id)
+}
+
+fn claim_cover_in(
+    conn: &Connection,
+    day: NaiveDate,
+    master_id: i64,
+    chat_id: i64,
+) -> Result<bool, GstaldergeistError> {
+    // The primary key makes the claim atomic: only the first insert of a
+    // request adds a row.
+    let claimed = conn.execute(
+        "INSERT OR IGNORE INTO covers (day, master_id, covered_by) VALUES (?1, ?2, ?3)",
+        rusqlite::params![day, master_id, chat_id],
+    )?;
+    Ok(claimed == 1)
+}
+
+fn release_swap_request_in(
+    conn: &Connection,
+    swap_id: i64,
+    chat_id: i64,
+) -> Result<(), GstaldergeistError> {
+    conn.execute(
+        "UPDATE swap_requests SET accepted_by = NULL WHERE id = ?1 AND accepted_by = ?2",
+        rusqlite::params![swap_id, chat_id],
+    )?;
+    Ok(())
+}
+
+pub fn load_jobs(tz: chrono_tz::Tz) -> Result<Vec<JobRecord>, GstaldergeistError> {
+    let conn = open_db()?;
+    read_jobs(&conn, tz)
+}
+
+/// Replace the stored jobs with `jobs`.
+pub fn save_jobs(jobs: &[JobRecord]) -> Result<(), GstaldergeistError> {
+    let mut conn = open_db()?;
+    write_jobs(&mut conn, jobs)
+}
+
+fn read_jobs(conn: &Connection, tz: chrono_tz::Tz) -> Result<Vec<JobRecord>, GstaldergeistError> {
+    let mut stmt =
+        conn.prepare("SELECT name, schedule, next_run, last_run FROM jobs ORDER BY name")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(JobRecord {
+            name: row.get(0)?,
+            schedule: row.get(1)?,
+            next_run: row.get::<_, Option<_>>(2)?.map(|run| in_tz(run, tz)),
+            last_run: row.get::<_, Option<_>>(3)?.map(|run| in_tz(run, tz)),
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+fn write_jobs(conn: &mut Connection, jobs: &[JobRecord]) -> Result<(), GstaldergeistError> {
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM jobs", [])?;
+    for job in jobs {
+        tx.execute(
+            "INSERT INTO jobs (name, schedule, next_run, last_run) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![
+                job.name,
+                job.schedule,
+                job.next_run.map(utc),
+                job.last_run.map(utc)
+            ],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn insert_duty_log(entry: &DutyLogEntry) -> Result<(), GstaldergeistError> {
+    let conn = open_db()?;
+    write_duty_log(&conn, entry)
+}
+
+/// The whole duty log, oldest first.
+pub fn get_duty_log(tz: chrono_tz::Tz) -> Result<Vec<DutyLogEntry>, GstaldergeistError> {
+    let conn = open_db()?;
+    read_duty_log(&conn, tz)
+}
+
+fn write_duty_log(conn: &Connection, entry: &DutyLogEntry) -> Result<(), GstaldergeistError> {
+    conn.execute(
+        "INSERT INTO duty_log (timestamp, chat_id, chore, trashes, outcome)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            utc(entry.timestamp),
+            entry.chat_id,
+            entry.chore,
+            entry.trashes,
+            entry.outcome
+        ],
+    )?;
     Ok(())
 }
+
+fn read_duty_log(
+    conn: &Connection,
+    tz: chrono_tz::Tz,
+) -> Result<Vec<DutyLogEntry>, GstaldergeistError> {
+    let mut stmt = conn.prepare(
+        "SELECT timestamp, chat_id, chore, trashes, outcome FROM duty_log ORDER BY timestamp, id",
+    )?;
+    let entries = stmt
+        .query_map([], |row| {
+            Ok(DutyLogEntry {
+                timestamp: in_tz(row.get(0)?, tz),
+                chat_id: row.get(1)?,
+                chore: row.get(2)?,
+                trashes: row.get(3)?,
+                outcome: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
+/// Timestamps are stored in UTC, so the data survives a timezone change.
+fn utc(dt: chrono::DateTime<chrono_tz::Tz>) -> chrono::DateTime<chrono::Utc> {
+    dt.with_timezone(&chrono::Utc)
+}
+
+fn in_tz(dt: chrono::DateTime<chrono::Utc>, tz: chrono_tz::Tz) -> chrono::DateTime<chrono_tz::Tz> {
+    dt.with_timezone(&tz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn memory_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate_db(&mut conn).unwrap();
+        conn
+    }
+
+    fn task(state: TaskState, message_id: Option<i32>) -> SharedTaskState {
+        SharedTaskState {
+            state,
+            message_id,
+            sent_on: message_id.map(|_| NaiveDate::from_ymd_opt(2026, 6, 7).unwrap()),
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn evening() -> chrono::DateTime<chrono_tz::Tz> {
+        chrono_tz::Europe::Zurich
+            .with_ymd_and_hms(2026, 6, 7, 19, 0, 0)
+            .single()
+            .unwrap()
+    }
+
+    #[test]
+    fn empty_database_has_no_task_state() {
+        assert_eq!(read_task_state(&memory_db()).unwrap(), None);
+    }
+
+    #[test]
+    fn pending_task_round_trips() {
+        let conn = memory_db();
+        let pending = task(TaskState::Pending, Some(1234));
+        write_task_state(&conn, &pending).unwrap();
+        assert_eq!(read_task_state(&conn).unwrap(), Some(pending));
+    }
+
+    #[test]
+    fn saving_again_replaces_the_previous_state() {
+        let conn = memory_db();
+        write_task_state(&conn, &task(TaskState::Pending, Some(1234))).unwrap();
+        write_task_state(&conn, &task(TaskState::None, None)).unwrap();
+        assert_eq!(
+            read_task_state(&conn).unwrap(),
+            Some(task(TaskState::None, None))
+        );
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM trash_task", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn chore_states_are_stored_per_chore() {
+        let conn = memory_db();
+        let bathroom = ChoreState {
+            state: TaskState::Pending,
+            turn: 2,
+            next_trigger: evening(),
+            message_id: Some(7),
+        };
+        let plants = ChoreState {
+            state: TaskState::Done,
+            turn: 0,
+            next_trigger: evening(),
+            message_id: None,
+        };
+        write_chore_state(&conn, "bathroom", &bathroom).unwrap();
+        write_chore_state(&conn, "plants", &plants).unwrap();
+        write_chore_state(&conn, "plants", &plants).unwrap();
+
+        let chores = read_chore_states(&conn, chrono_tz::Europe::Zurich).unwrap();
+        assert_eq!(chores.len(), 2);
+        assert_eq!(chores["bathroom"], bathroom);
+        assert_eq!(chores["plants"], plants);
+    }
+
+    #[test]
+    fn timestamps_are_stored_in_utc_and_read_in_the_given_timezone() {
+        use chrono::Timelike;
+        let conn = memory_db();
+        let chore = ChoreState {
+            state: TaskState::None,
+            turn: 0,
+            next_trigger: evening(),
+            message_id: None,
+        };
+        write_chore_state(&conn, "bathroom", &chore).unwrap();
+        let stored: String = conn
+            .query_row("SELECT next_trigger FROM chore_state", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, "2026-06-07 17:00:00+00:00");
+
+        let read = read_chore_states(&conn, chrono_tz::UTC).unwrap();
+        assert_eq!(read["bathroom"].next_trigger, evening());
+        assert_eq!(read["bathroom"].next_trigger.hour(), 17);
+    }
+
+    #[test]
+    fn jobs_round_trip_and_replace_the_stored_ones() {
+        let mut conn = memory_db();
+        let reminder = JobRecord {
+            name: "trash_reminder".to_string(),
+            schedule: "0 16 * * *".to_string(),
+            next_run: Some(evening()),
+            last_run: None,
+        };
+        let check = JobRecord {
+            name: "escalation_19".to_string(),
+            schedule: "0 19 * * *".to_string(),
+            next_run: None,
+            last_run: Some(evening()),
+        };
+        write_jobs(&mut conn, &[reminder.clone(), check.clone()]).unwrap();
+        assert_eq!(
+            read_jobs(&conn, chrono_tz::Europe::Zurich).unwrap(),
+            vec![check, reminder.clone()]
+        );
+
+        write_jobs(&mut conn, std::slice::from_ref(&reminder)).unwrap();
+        assert_eq!(
+            read_jobs(&conn, chrono_tz::Europe::Zurich).unwrap(),
+            vec![reminder]
+        );
+    }
+
+    #[test]
+    fn empty_database_has_no_roster() {
+        assert_eq!(read_roster(&memory_db()).unwrap(), None);
+    }
+
+    #[test]
+    fn roster_round_trips_in_order() {
+        let conn = memory_db();
+        let mut roster = Roster::new(&[30, 10, 20], NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        roster.set_away(10, true);
+        write_roster(&conn, &roster).unwrap();
+        assert_eq!(read_roster(&conn).unwrap(), Some(roster.clone()));
+
+        roster.remove(30);
+        write_roster(&conn, &roster).unwrap();
+        assert_eq!(read_roster(&conn).unwrap(), Some(roster));
+    }
+
+    #[test]
+    fn roster_substitute_debts_and_absences_round_trip() {
+        let conn = memory_db();
+        let mut roster = Roster::new(&[10, 20, 30], NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        roster.swap(30);
+        roster.add_absence(Absence {
+            chat_id: 10,
+            from: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            until: NaiveDate::from_ymd_opt(2024, 3, 10).unwrap(),
+        });
+        write_roster(&conn, &roster).unwrap();
+        assert_eq!(read_roster(&conn).unwrap(), Some(roster.clone()));
+
+        roster.advance_to(NaiveDate::from_ymd_opt(2024, 1, 8).unwrap());
+        roster.advance_to(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+        write_roster(&conn, &roster).unwrap();
+        assert_eq!(read_roster(&conn).unwrap(), Some(roster));
+    }
+
+    #[test]
+    fn updating_the_roster_sees_the_previous_update() {
+        let mut conn = memory_db();
+        let seeded = update_roster_in(&mut conn, |stored| {
+            let seeded = stored.is_none();
+            (
+                Roster::new(&[10, 20, 30], NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+                seeded,
+            )
+        })
+        .unwrap();
+        assert!(seeded);
+        update_roster_in(&mut conn, |stored| {
+            let mut roster = stored.unwrap();
+            roster.swap(30);
+            (roster, ())
+        })
+        .unwrap();
+        let substitute = update_roster_in(&mut conn, |stored| {
+            let roster = stored.unwrap();
+            let substitute = roster.substitute;
+            (roster, substitute)
+        })
+        .unwrap();
+        assert_eq!(substitute, Some(30));
+    }
+
+    #[test]
+    fn only_the_first_helper_claims_a_request() {
+        let conn = memory_db();
+        let day = NaiveDate::from_ymd_opt(2026, 6, 8).unwrap();
+        assert!(claim_cover_in(&conn, day, 10, 20).unwrap());
+        assert!(!claim_cover_in(&conn, day, 10, 30).unwrap());
+        // An older request is claimed on its own.
+        assert!(claim_cover_in(&conn, day - chrono::Duration::days(1), 10, 30).unwrap());
+    }
+
+    #[test]
+    fn only_the_first_acceptance_claims_a_swap() {
+        let conn = memory_db();
+        let week = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let swap_id = insert_swap_request(&conn, 20, week).unwrap();
+
+        let claimed = claim_swap_request_in(&conn, swap_id, 30).unwrap();
+        assert_eq!(
+            claimed,
+            Some(SwapRequest {
+                id: swap_id,
+                requester: 20,
+                week
+            })
+        );
+        assert_eq!(claim_swap_request_in(&conn, swap_id, 10).unwrap(), None);
+        assert_eq!(claim_swap_request_in(&conn, swap_id + 1, 10).unwrap(), None);
+    }
+
+    #[test]
+    fn a_released_swap_can_be_accepted_by_someone_else() {
+        let conn = memory_db();
+        let week = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let swap_id = insert_swap_request(&conn, 20, week).unwrap();
+
+        // The roster check failed for 30, who releases the claim.
+        assert!(claim_swap_request_in(&conn, swap_id, 30).unwrap().is_some());
+        release_swap_request_in(&conn, swap_id, 30).unwrap();
+        // Only the claimant's release counts.
+        assert!(claim_swap_request_in(&conn, swap_id, 10).unwrap().is_some());
+        release_swap_request_in(&conn, swap_id, 30).unwrap();
+        assert_eq!(claim_swap_request_in(&conn, swap_id, 40).unwrap(), None);
+    }
+
+    #[test]
+    fn duty_log_is_read_back_oldest_first() {
+        use crate::stats::DutyOutcome;
+        let conn = memory_db();
+        let at = |hour| {
+            chrono_tz::Europe::Zurich
+                .with_ymd_and_hms(2026, 6, 7, hour, 0, 0)
+                .single()
+                .unwrap()
+        };
+        let late = DutyLogEntry {
+            timestamp: at(20),
+            chat_id: 10,
+            chore: "trash".to_string(),
+            trashes: "Bio,Paper".to_string(),
+            outcome: DutyOutcome::Done,
+        };
+        let early = DutyLogEntry {
+            timestamp: at(17),
+            chat_id: 20,
+            chore: "bathroom".to_string(),
+            trashes: String::new(),
+            outcome: DutyOutcome::Cant,
+        };
+        write_duty_log(&conn, &late).unwrap();
+        write_duty_log(&conn, &early).unwrap();
+        assert_eq!(
+            read_duty_log(&conn, chrono_tz::Europe::Zurich).unwrap(),
+            vec![early, late]
+        );
+    }
+
+    fn schema_version(conn: &Connection) -> usize {
+        conn.query_row("SELECT version FROM schema_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn migrations_run_once() {
+        let mut conn = memory_db();
+        assert_eq!(schema_version(&conn), MIGRATIONS.len());
+        write_task_state(&conn, &task(TaskState::Pending, Some(1234))).unwrap();
+        migrate_db(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn), MIGRATIONS.len());
+        assert_eq!(
+            read_task_state(&conn).unwrap(),
+            Some(task(TaskState::Pending, Some(1234)))
+        );
+    }
+
+    #[test]
+    fn a_database_from_before_migrations_keeps_its_data() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE trashes (date DATE, waste_type INTEGER);
+            INSERT INTO trashes VALUES ('2026-06-08', 1), ('2026-06-08', 1), ('2026-06-09', 2);
+            CREATE TABLE trash_task (
+                id INTEGER PRIMARY KEY CHECK (id = 0),
+                state INTEGER NOT NULL,
+                message_id INTEGER
+            );
+            INSERT INTO trash_task VALUES (0, 2, NULL);",
+        )
+        .unwrap();
+        migrate_db(&mut conn).unwrap();
+        let trashes = read_trashes(&conn).unwrap();
+        assert_eq!(trashes[&date(2026, 6, 8)], vec![TrashType::Normal]);
+        assert_eq!(trashes[&date(2026, 6, 9)], vec![TrashType::Bio]);
+        let source: String = conn
+            .query_row("SELECT source FROM trashes LIMIT 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(source, "unknown");
+        assert_eq!(
+            read_task_state(&conn).unwrap(),
+            Some(task(TaskState::Failed, None))
+        );
+    }
+
+    #[test]
+    fn a_newer_schema_is_refused() {
+        let mut conn = memory_db();
+        conn.execute("UPDATE schema_version SET version = version + 1", [])
+            .unwrap();
+        assert!(migrate_db(&mut conn).is_err());
+    }
+
+    fn provider(
+        name: &str,
+        until: NaiveDate,
+        trashes: &[(NaiveDate, TrashType)],
+    ) -> ProviderTrashes {
+        let mut dates: HashMap<NaiveDate, Vec<TrashType>> = HashMap::new();
+        for (date, trash) in trashes {
+            dates.entry(*date).or_default().push(*trash);
+        }
+        ProviderTrashes {
+            name: name.to_string(),
+            until,
+            dates,
+        }
+    }
+
+    #[test]
+    fn trashes_are_stored_once_per_day_and_type_with_their_source() {
+        let mut conn = memory_db();
+        let by_provider = [
+            provider(
+                "adliswil",
+                date(2026, 6, 8),
+                &[
+                    (date(2026, 6, 8), TrashType::Normal),
+                    (date(2026, 6, 8), TrashType::Paper),
+                ],
+            ),
+            provider(
+                "ics",
+                date(2026, 6, 8),
+                &[(date(2026, 6, 8), TrashType::Paper)],
+            ),
+        ];
+        let changes =
+            sync_trashes_in(&mut conn, &by_provider, date(2026, 6, 7), evening()).unwrap();
+        assert_eq!(changes, vec![]);
+        let mut trashes = read_trashes(&conn).unwrap();
+        trashes
+            .get_mut(&date(2026, 6, 8))
+            .unwrap()
+            .sort_by_key(|t| t.to_string());
+        assert_eq!(
+            trashes,
+            HashMap::from([(date(2026, 6, 8), vec![TrashType::Normal, TrashType::Paper])])
+        );
+        let (source, fetched_at): (String, chrono::DateTime<chrono::Utc>) = conn
+            .query_row(
+                "SELECT source, fetched_at FROM trashes WHERE waste_type = 4",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(source, "ics");
+        assert_eq!(fetched_at, utc(evening()));
+    }
+
+    #[test]
+    fn syncing_keeps_other_days_and_reports_changes_in_the_window() {
+        let mut conn = memory_db();
+        let week = [provider(
+            "adliswil",
+            date(2026, 6, 14),
+            &[
+                (date(2026, 6, 2), TrashType::Bio),
+                (date(2026, 6, 9), TrashType::Paper),
+                (date(2026, 6, 11), TrashType::Cardboard),
+            ],
+        )];
+        sync_trashes_in(&mut conn, &week, date(2026, 6, 1), evening()).unwrap();
+        // Paper moves a day later, the cardboard collection is cancelled.
+        let update = [provider(
+            "adliswil",
+            date(2026, 6, 18),
+            &[
+                (date(2026, 6, 10), TrashType::Paper),
+                (date(2026, 6, 12), TrashType::Normal),
+            ],
+        )];
+        let changes = sync_trashes_in(&mut conn, &update, date(2026, 6, 7), evening()).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                ScheduleChange::Moved {
+                    trash: TrashType::Paper,
+                    from: date(2026, 6, 9),
+                    to: date(2026, 6, 10),
+                },
+                ScheduleChange::Cancelled {
+                    trash: TrashType::Cardboard,
+                    date: date(2026, 6, 11),
+                },
+            ]
+        );
+        let trashes = read_trashes(&conn).unwrap();
+        assert_eq!(
+            trashes,
+            HashMap::from([
+                (date(2026, 6, 2), vec![TrashType::Bio]),
+                (date(2026, 6, 10), vec![TrashType::Paper]),
+                (date(2026, 6, 12), vec![TrashType::Normal]),
+            ])
+        );
+        assert_eq!(
+            read_trash_syncs(&conn, chrono_tz::Europe::Zurich).unwrap(),
+            HashMap::from([("adliswil".to_string(), (evening(), date(2026, 6, 18)))])
+        );
+    }
+
+    #[test]
+    fn a_collection_moving_past_the_window_is_not_cancelled() {
+        let mut conn = memory_db();
+        let june = [provider(
+            "adliswil",
+            date(2026, 6, 30),
+            &[(date(2026, 6, 30), TrashType::Paper)],
+        )];
+        sync_trashes_in(&mut conn, &june, date(2026, 6, 21), evening()).unwrap();
+        // Paper moves to July 1st, which the provider does not report yet.
+        let update = [provider("adliswil", date(2026, 6, 30), &[])];
+        let changes = sync_trashes_in(&mut conn, &update, date(2026, 6, 21), evening()).unwrap();
+        assert_eq!(changes, vec![]);
+        assert_eq!(read_trashes(&conn).unwrap(), HashMap::new());
+        // Once July is reported, the collection is there again.
+        let july = [provider(
+            "adliswil",
+            date(2026, 7, 31),
+            &[(date(2026, 7, 1), TrashType::Paper)],
+        )];
+        let changes = sync_trashes_in(&mut conn, &july, date(2026, 6, 21), evening()).unwrap();
+        assert_eq!(changes, vec![]);
+        assert_eq!(
+            read_trashes(&conn).unwrap(),
+            HashMap::from([(date(2026, 7, 1), vec![TrashType::Paper])])
+        );
+    }
+}
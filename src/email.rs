@@ -3,37 +3,23 @@ use lettre::transport::smtp::authentication::Credentials;
 use lettre::{Message, SmtpTransport, Transport};
 use std::error::Error;
 
+/// How to reach We-Recycle by email, from the `[email]` section of the
+/// configuration.
+#[derive(Clone, PartialEq)]
 pub struct EmailConfig {
-    smtp_server: String,
-    smtp_port: u16,
-    username: String,
-    password: String,
-    from_email: String,
-    from_name: String,
-    address: String,
-    to_email: String,
+    pub smtp_server: String,
+    pub smtp_port: u16,
+    pub username: String,
+    pub password: String,
+    pub from_email: String,
+    pub from_name: String,
+    pub address: String,
+    pub to_email: String,
 }
 
-impl EmailConfig {
-    pub fn from_env() -> Result<Self, Box<dyn Error>> {
-        Ok(Self {
-            smtp_server: std::env::var("EMAIL_SMTP_SERVER")?,
-            smtp_port: 465,
-            username: std::env::var("EMAIL_ADDRESS")?,
-            password: std::env::var("EMAIL_PASSWORD")?,
-            from_email: std::env::var("EMAIL_ADDRESS")?,
-            from_name: std::env::var("EMAIL_NAME")?,
-            address: std::env::var("ADDRESS")?,
-            to_email: std::env::var("TO_EMAIL")?,
-        })
-    }
-}
-
-pub fn request_new_bags() -> Result<(), Box<dyn Error>> {
-    let email_config = EmailConfig::from_env()?;
-
+pub fn request_new_bags(email_config: &EmailConfig) -> Result<(), Box<dyn Error>> {
     send_email(
-        &email_config,
+        email_config,
         email_config.to_email.as_str(),
         "We Recycle",
         "Request for new bags",
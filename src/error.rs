@@ -15,6 +15,9 @@ pub enum GstaldergeistError {
     #[error("Pdf extract error: {0}")]
     PdfExtract(String),
 
+    #[error("Calendar error: {0}")]
+    Calendar(String),
+
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -0,0 +1,207 @@
+use crate::error::GstaldergeistError;
+
+/// Hour of the daily trash reminder.
+pub const DEFAULT_REMINDER_HOUR: u32 = 16;
+
+/// What to do when the flatmate on duty still hasn't pressed Done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscalationAction {
+    /// Remind the flatmate on duty again, privately.
+    Nudge,
+    /// Ask the whole group to step in.
+    Group,
+    /// Ask the next flatmate in the rotation directly.
+    NextInRotation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscalationStep {
+    pub hour: u32,
+    pub action: EscalationAction,
+}
+
+/// The daily reminder followed by the steps taken, in order, while it stays
+/// unanswered. Pressing Done cancels every step not taken yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscalationPolicy {
+    pub reminder_hour: u32,
+    /// Steps sorted by strictly increasing hour, all after the reminder.
+    pub steps: Vec<EscalationStep>,
+}
+
+impl Default for EscalationPolicy {
+    /// Ask the group at 19:00, three hours after the reminder.
+    fn default() -> Self {
+        EscalationPolicy {
+            reminder_hour: DEFAULT_REMINDER_HOUR,
+            steps: vec![EscalationStep {
+                hour: 19,
+                action: EscalationAction::Group,
+            }],
+        }
+    }
+}
+
+impl EscalationAction {
+    /// The action named `name` in the configuration: nudge, group or next.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "nudge" => Some(EscalationAction::Nudge),
+            "group" => Some(EscalationAction::Group),
+            "next" => Some(EscalationAction::NextInRotation),
+            _ => None,
+        }
+    }
+}
+
+impl EscalationPolicy {
+    /// The policy taking `steps` after the reminder at `reminder_hour`. Fails
+    /// with the index of the first step not later than the one before it, or
+    /// than the reminder.
+    pub fn new(reminder_hour: u32, steps: Vec<EscalationStep>) -> Result<Self, usize> {
+        let mut previous = reminder_hour;
+        for (index, step) in steps.iter().enumerate() {
+            if step.hour <= previous {
+                return Err(index);
+            }
+            previous = step.hour;
+        }
+        Ok(EscalationPolicy {
+            reminder_hour,
+            steps,
+        })
+    }
+
+    /// The step taken at `hour`, and whether it is the last one.
+    pub fn step_at(&self, hour: u32) -> Option<(&EscalationStep, bool)> {
+        let index = self.steps.iter().position(|step| step.hour == hour)?;
+        Some((&self.steps[index], index + 1 == self.steps.len()))
+    }
+
+    /// Hour at which the group is first asked to step in, if ever.
+    pub fn first_group_hour(&self) -> Option<u32> {
+        self.steps
+            .iter()
+            .find(|step| step.action == EscalationAction::Group)
+            .map(|step| step.hour)
+    }
+}
+
+/// Parse a comma-separated escalation ladder, e.g.
+/// "18:nudge, 20:nudge, 21:group, 22:next". Hours must be strictly increasing
+/// and later than the reminder. An empty string means never escalating.
+pub fn parse_escalation(
+    raw: &str,
+    reminder_hour: u32,
+) -> Result<EscalationPolicy, GstaldergeistError> {
+    let entries: Vec<&str> = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .collect();
+    let invalid = |entry: &str, reason: &str| {
+        GstaldergeistError::ConfigError(format!(
+            "ESCALATION entries must look like hour:nudge|group|next, got '{}': {}",
+            entry, reason
+        ))
+    };
+    let mut steps: Vec<EscalationStep> = Vec::new();
+    for entry in &entries {
+        let (hour, action) = entry
+            .split_once(':')
+            .ok_or_else(|| invalid(entry, "missing ':'"))?;
+        let hour: u32 = hour
+            .trim()
+            .parse()
+            .ok()
+            .filter(|hour| *hour < 24)
+            .ok_or_else(|| invalid(entry, "the hour must be between 0 and 23"))?;
+        let action =
+            EscalationAction::parse(action).ok_or_else(|| invalid(entry, "unknown action"))?;
+        steps.push(EscalationStep { hour, action });
+    }
+    EscalationPolicy::new(reminder_hour, steps).map_err(|index| {
+        invalid(
+            entries[index],
+            &format!(
+                "steps must come after the {}:00 reminder, in increasing order",
+                reminder_hour
+            ),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(hour: u32, action: EscalationAction) -> EscalationStep {
+        EscalationStep { hour, action }
+    }
+
+    #[test]
+    fn parses_a_full_ladder() {
+        use EscalationAction::*;
+        let policy = parse_escalation("18:nudge, 20:nudge, 21:group, 22:next", 16).unwrap();
+        assert_eq!(
+            policy.steps,
+            vec![
+                step(18, Nudge),
+                step(20, Nudge),
+                step(21, Group),
+                step(22, NextInRotation)
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_ladder_never_escalates() {
+        let policy = parse_escalation("", 16).unwrap();
+        assert!(policy.steps.is_empty());
+        assert_eq!(policy.first_group_hour(), None);
+    }
+
+    #[test]
+    fn default_asks_the_group_at_19() {
+        assert_eq!(
+            parse_escalation("19:group", DEFAULT_REMINDER_HOUR).unwrap(),
+            EscalationPolicy::default()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_ladders() {
+        for raw in [
+            "18",
+            "18:shout",
+            "24:group",
+            "abc:group",
+            "15:group",
+            "16:group",
+            "20:nudge, 19:group",
+            "20:nudge, 20:group",
+        ] {
+            let err = parse_escalation(raw, 16).unwrap_err();
+            assert!(
+                matches!(err, GstaldergeistError::ConfigError(_)),
+                "{} should be rejected",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn finds_the_step_for_an_hour() {
+        let policy = parse_escalation("18:nudge, 21:group", 16).unwrap();
+        assert_eq!(
+            policy.step_at(18),
+            Some((&step(18, EscalationAction::Nudge), false))
+        );
+        assert_eq!(
+            policy.step_at(21),
+            Some((&step(21, EscalationAction::Group), true))
+        );
+        assert_eq!(policy.step_at(16), None);
+        assert_eq!(policy.first_group_hour(), Some(21));
+    }
+}
@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicI64, Ordering};
+
+/// The scheduler counts as stalled once its loop has not come round for this
+/// long. It sleeps at most an hour at a time, and fetching the collection
+/// dates takes as long as the slowest provider's fetch policy allows on top,
+/// under two minutes by default.
+#[cfg(feature = "http")]
+const MAX_HEARTBEAT_AGE_SECS: i64 = 2 * 3600;
+
+/// Unix timestamp of an event, or 0 until it happens.
+struct Beat(AtomicI64);
+
+impl Beat {
+    const fn new() -> Self {
+        Beat(AtomicI64::new(0))
+    }
+
+    fn set(&self, at: DateTime<Utc>) {
+        self.0.store(at.timestamp(), Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        self.0.store(0, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "http")]
+    fn get(&self) -> Option<DateTime<Utc>> {
+        match self.0.load(Ordering::Relaxed) {
+            0 => None,
+            timestamp => DateTime::from_timestamp(timestamp, 0),
+        }
+    }
+}
+
+/// When the scheduler loop last came round, unset while it is down.
+static SCHEDULER_HEARTBEAT: Beat = Beat::new();
+/// When a message was last sent to Telegram.
+static TELEGRAM_SUCCESS: Beat = Beat::new();
+
+/// Note that the scheduler loop is running at `now`.
+pub fn scheduler_heartbeat(now: DateTime<chrono_tz::Tz>) {
+    SCHEDULER_HEARTBEAT.set(now.with_timezone(&Utc));
+}
+
+/// Note that the scheduler stopped, until it beats again.
+pub fn scheduler_stopped() {
+    SCHEDULER_HEARTBEAT.clear();
+}
+
+/// Note that the Telegram API just accepted a message.
+pub fn telegram_succeeded() {
+    TELEGRAM_SUCCESS.set(Utc::now());
+}
+
+/// How the bot is doing.
+#[cfg(feature = "http")]
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Health {
+    /// Seconds since the scheduler loop last came round, `None` while it is
+    /// down.
+    pub scheduler_heartbeat_age_secs: Option<i64>,
+    pub database_reachable: bool,
+    pub last_telegram_success: Option<DateTime<Utc>>,
+}
+
+#[cfg(feature = "http")]
+impl Health {
+    /// Whether the scheduler is running and recently came round.
+    pub fn live(&self) -> bool {
+        self.scheduler_heartbeat_age_secs
+            .is_some_and(|age| age <= MAX_HEARTBEAT_AGE_SECS)
+    }
+
+    /// Whether the bot can do its job: it is live and its database answers.
+    pub fn ready(&self) -> bool {
+        self.live() && self.database_reachable
+    }
+}
+
+/// How the bot is doing as of `now`.
+#[cfg(feature = "http")]
+pub fn check(now: DateTime<chrono_tz::Tz>) -> Health {
+    let database_reachable = match crate::database::ping() {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!("Health check cannot reach the database: {}", e);
+            false
+        }
+    };
+    Health {
+        scheduler_heartbeat_age_secs: SCHEDULER_HEARTBEAT
+            .get()
+            .map(|beat| (now.with_timezone(&Utc) - beat).num_seconds()),
+        database_reachable,
+        last_telegram_success: TELEGRAM_SUCCESS.get(),
+    }
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+    use super::*;
+
+    fn health(age: Option<i64>, database_reachable: bool) -> Health {
+        Health {
+            scheduler_heartbeat_age_secs: age,
+            database_reachable,
+            last_telegram_success: None,
+        }
+    }
+
+    #[test]
+    fn a_stalled_or_stopped_scheduler_is_not_live() {
+        assert!(health(Some(30), true).live());
+        assert!(health(Some(MAX_HEARTBEAT_AGE_SECS), true).live());
+        assert!(!health(Some(MAX_HEARTBEAT_AGE_SECS + 1), true).live());
+        assert!(!health(None, true).live());
+    }
+
+    #[test]
+    fn ready_needs_the_database_too() {
+        assert!(health(Some(30), true).ready());
+        assert!(!health(Some(30), false).ready());
+        assert!(!health(None, true).ready());
+    }
+
+    #[test]
+    fn beats_start_unset() {
+        let beat = Beat::new();
+        assert_eq!(beat.get(), None);
+        let at = DateTime::from_timestamp(1767632400, 0).unwrap();
+        beat.set(at);
+        assert_eq!(beat.get(), Some(at));
+        beat.clear();
+        assert_eq!(beat.get(), None);
+    }
+}
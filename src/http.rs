@@ -0,0 +1,412 @@
+use crate::clock::Clock;
+use crate::config::ConfigHandle;
+use crate::error::GstaldergeistError;
+use crate::{SharedTaskState, TaskState, calendar, health, metrics, roster, stats};
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::NaiveDate;
+use serde::Serialize;
+use teloxide::prelude::*;
+
+/// What the request handlers share with the bot.
+#[derive(Clone)]
+pub struct AppState {
+    pub config: ConfigHandle,
+    pub clock: std::sync::Arc<dyn Clock>,
+    pub task_state: std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
+    pub bot: Bot,
+}
+
+/// A failed request, answered as `{"error": "..."}`.
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<GstaldergeistError> for ApiError {
+    fn from(error: GstaldergeistError) -> Self {
+        tracing::error!("HTTP request failed: {}", error);
+        ApiError(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+    }
+}
+
+#[derive(Serialize)]
+struct CollectionJson {
+    date: NaiveDate,
+    trashes: Vec<String>,
+    on_duty: String,
+}
+
+#[derive(Serialize)]
+struct StateJson {
+    state: &'static str,
+    /// Whether the reminder message still carries its buttons.
+    reminder_open: bool,
+}
+
+#[derive(Serialize)]
+struct MemberJson {
+    chat_id: i64,
+    name: String,
+    away: bool,
+}
+
+#[derive(Serialize)]
+struct AbsenceJson {
+    chat_id: i64,
+    from: NaiveDate,
+    until: NaiveDate,
+}
+
+#[derive(Serialize)]
+struct DebtJson {
+    debtor: i64,
+    creditor: i64,
+}
+
+#[derive(Serialize)]
+struct RosterJson {
+    week: NaiveDate,
+    on_duty: Option<i64>,
+    members: Vec<MemberJson>,
+    absences: Vec<AbsenceJson>,
+    debts: Vec<DebtJson>,
+}
+
+fn state_json(task: &SharedTaskState) -> StateJson {
+    StateJson {
+        state: match task.state {
+            TaskState::None => "none",
+            TaskState::Pending => "pending",
+            TaskState::Failed => "failed",
+            TaskState::Done => "done",
+        },
+        reminder_open: task.message_id.is_some(),
+    }
+}
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/api/schedule", get(schedule))
+        .route("/api/state", get(task_state))
+        .route("/api/roster", get(roster))
+        .route("/api/duty/done", post(duty_done))
+        .route("/calendar.ics", get(calendar_feed))
+        .route("/metrics", get(metrics_text))
+        .route("/health/live", get(liveness))
+        .route("/health/ready", get(readiness))
+        .with_state(state)
+}
+
+/// Serve the API on `listen` until the bot stops.
+pub async fn serve(listen: std::net::SocketAddr, state: AppState) {
+    let listener = match tokio::net::TcpListener::bind(listen).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to listen on {}: {}", listen, e);
+            return;
+        }
+    };
+    tracing::info!("HTTP server listening on {}", listen);
+    if let Err(e) = axum::serve(listener, router(state)).await {
+        tracing::error!("HTTP server failed: {}", e);
+    }
+}
+
+/// Every stored collection from today on.
+async fn schedule(State(state): State<AppState>) -> Result<Json<Vec<CollectionJson>>, ApiError> {
+    let config = state.config.current();
+    let today = state.clock.now().date_naive();
+    let collections = calendar::upcoming_collections(&config, today).await?;
+    Ok(Json(
+        collections
+            .into_iter()
+            .map(|collection| CollectionJson {
+                date: collection.date,
+                trashes: collection.trashes.iter().map(|t| t.to_string()).collect(),
+                on_duty: collection.on_duty,
+            })
+            .collect(),
+    ))
+}
+
+/// Where today's trash reminder stands.
+async fn task_state(State(state): State<AppState>) -> Json<StateJson> {
+    Json(state_json(&state.task_state.lock().unwrap()))
+}
+
+/// The trash duty rotation as of this week.
+async fn roster(State(state): State<AppState>) -> Result<Json<RosterJson>, ApiError> {
+    let config = state.config.current();
+    let today = state.clock.now().date_naive();
+    let roster = roster::load_roster_at(&config.flatmates, today)?;
+    let mut members = Vec::new();
+    for member in &roster.members {
+        members.push(MemberJson {
+            chat_id: member.chat_id,
+            name: crate::data_grabber::grab_food_master_name(&config, member.chat_id).await,
+            away: member.away,
+        });
+    }
+    Ok(Json(RosterJson {
+        week: roster.week,
+        on_duty: roster.on_duty(),
+        members,
+        absences: roster
+            .absences
+            .iter()
+            .map(|absence| AbsenceJson {
+                chat_id: absence.chat_id,
+                from: absence.from,
+                until: absence.until,
+            })
+            .collect(),
+        debts: roster
+            .debts
+            .iter()
+            .map(|debt| DebtJson {
+                debtor: debt.debtor,
+                creditor: debt.creditor,
+            })
+            .collect(),
+    }))
+}
+
+/// Check the bearer token that changes need.
+fn authorize(state: &AppState, headers: &HeaderMap) -> Result<(), ApiError> {
+    let config = state.config.current();
+    let Some(token) = config.http.as_ref().and_then(|http| http.token.as_deref()) else {
+        return Err(ApiError(
+            StatusCode::FORBIDDEN,
+            "the API is read-only; set http.token to allow changes".to_string(),
+        ));
+    };
+    let given = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if given != Some(token) {
+        return Err(ApiError(
+            StatusCode::UNAUTHORIZED,
+            "missing or wrong bearer token".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Settle the outstanding trash reminder as done, like pressing its Done
+/// button, on behalf of tomorrow's flatmate on duty.
+async fn duty_done(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<StateJson>, ApiError> {
+    authorize(&state, &headers)?;
+    let nothing_pending = || {
+        ApiError(
+            StatusCode::CONFLICT,
+            "no trash reminder is waiting for an answer".to_string(),
+        )
+    };
+    if state.task_state.lock().unwrap().state != TaskState::Pending {
+        return Err(nothing_pending());
+    }
+    let config = state.config.current();
+    let now = state.clock.now();
+    let tomorrow = now.date_naive() + chrono::Duration::days(1);
+    let master_id = roster::flatmate_on_duty(&config.flatmates, tomorrow)?;
+    let (reminder, settled, trashes) = {
+        let mut task = state.task_state.lock().unwrap();
+        // Someone may have answered on Telegram in the meantime.
+        if task.state != TaskState::Pending {
+            return Err(nothing_pending());
+        }
+        task.state = TaskState::None;
+        let reminder = task.message_id.take();
+        task.persist();
+        (reminder, state_json(&task), task.reminder_trashes(now))
+    };
+    stats::record(
+        now,
+        master_id,
+        stats::TRASH_CHORE,
+        &trashes,
+        stats::DutyOutcome::Done,
+    );
+    metrics::answered("done");
+    if let Some(message_id) = reminder
+        && let Err(e) = state
+            .bot
+            .edit_message_text(
+                ChatId(master_id),
+                teloxide::types::MessageId(message_id),
+                "Marked as done. Thank you! Have a nice evening. <3",
+            )
+            .await
+    {
+        tracing::warn!("Failed to clear the reminder buttons: {}", e);
+    }
+    Ok(Json(settled))
+}
+
+/// The upcoming collections for calendar apps to subscribe to.
+async fn calendar_feed(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let config = state.config.current();
+    let ics = calendar::feed(&config, state.clock.now()).await?;
+    Ok((
+        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        ics,
+    )
+        .into_response())
+}
+
+/// Counters and gauges for Prometheus to scrape.
+async fn metrics_text(State(state): State<AppState>) -> Response {
+    let text = metrics::render(&state.task_state.lock().unwrap().state);
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        text,
+    )
+        .into_response()
+}
+
+/// Whether the scheduler is running, for a supervisor to restart the bot
+/// when it is not.
+async fn liveness(State(state): State<AppState>) -> (StatusCode, Json<health::Health>) {
+    let health = health::check(state.clock.now());
+    (health_status(health.live()), Json(health))
+}
+
+/// Whether the bot is running and its database answers.
+async fn readiness(State(state): State<AppState>) -> (StatusCode, Json<health::Health>) {
+    let health = health::check(state.clock.now());
+    (health_status(health.ready()), Json(health))
+}
+
+fn health_status(healthy: bool) -> StatusCode {
+    if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FakeClock;
+    use crate::config::{Config, HttpConfig, test_config};
+    use axum::body::Body;
+    use axum::http::Request;
+    use chrono::TimeZone;
+    use tower::ServiceExt;
+
+    fn app(token: Option<&str>, state: TaskState) -> Router {
+        let config = Config {
+            http: Some(HttpConfig {
+                listen: "127.0.0.1:0".parse().unwrap(),
+                token: token.map(str::to_string),
+            }),
+            ..test_config(vec![10, 20])
+        };
+        let now = chrono_tz::Europe::Zurich
+            .with_ymd_and_hms(2026, 1, 5, 17, 0, 0)
+            .unwrap();
+        router(AppState {
+            config: ConfigHandle::new(config),
+            clock: std::sync::Arc::new(FakeClock::new(now)),
+            task_state: std::sync::Arc::new(std::sync::Mutex::new(SharedTaskState {
+                state,
+                message_id: Some(7),
+                sent_on: None,
+            })),
+            bot: Bot::new("token"),
+        })
+    }
+
+    async fn send(
+        app: Router,
+        method: &str,
+        uri: &str,
+        token: Option<&str>,
+    ) -> (StatusCode, String) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn reports_the_reminder_state() {
+        let (status, body) = send(app(None, TaskState::Pending), "GET", "/api/state", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, r#"{"state":"pending","reminder_open":true}"#);
+    }
+
+    #[tokio::test]
+    async fn changes_need_the_configured_token() {
+        let (status, _) = send(
+            app(None, TaskState::Pending),
+            "POST",
+            "/api/duty/done",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let app = || app(Some("kitchen"), TaskState::Pending);
+        let (status, _) = send(app(), "POST", "/api/duty/done", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = send(app(), "POST", "/api/duty/done", Some("hallway")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body, r#"{"error":"missing or wrong bearer token"}"#);
+    }
+
+    #[tokio::test]
+    async fn done_without_a_pending_reminder_is_a_conflict() {
+        let app = app(Some("kitchen"), TaskState::None);
+        let (status, _) = send(app, "POST", "/api/duty/done", Some("kitchen")).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn exports_metrics_with_the_reminder_state() {
+        let (status, body) = send(app(None, TaskState::Failed), "GET", "/metrics", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("# TYPE gstaldergeist_task_state gauge\n"));
+        assert!(body.contains("gstaldergeist_task_state{state=\"failed\"} 1\n"));
+        assert!(body.contains("gstaldergeist_task_state{state=\"pending\"} 0\n"));
+    }
+
+    #[tokio::test]
+    async fn not_ready_without_a_database() {
+        // Tests never set the database path.
+        let (status, body) = send(app(None, TaskState::None), "GET", "/health/ready", None).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body.contains(r#""database_reachable":false"#), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn unknown_paths_and_methods_are_rejected() {
+        let (status, _) = send(app(None, TaskState::None), "GET", "/api/nope", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send(app(None, TaskState::None), "GET", "/api/duty/done", None).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
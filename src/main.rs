@@ -1,84 +1,154 @@
-use chrono::{Datelike, TimeZone, Timelike, Weekday};
-use std::env;
-use telegram_writer::{send_update, shame_update};
+use config::Config;
+use escalation::{EscalationAction, EscalationPolicy, EscalationStep};
+use telegram_writer::shame_update;
 
 use teloxide::prelude::*;
 mod answer_handler;
+mod calendar;
+mod chores;
+mod clock;
+mod config;
 mod data_grabber;
 mod database;
 mod email;
 mod error;
+mod escalation;
+mod health;
+#[cfg(feature = "http")]
+mod http;
+mod metrics;
+mod roster;
+mod scheduler;
+mod stats;
+mod swap;
+mod sync;
 mod telegram_writer;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum TaskState {
     Failed,
     Pending,
+    Done,
     None,
 }
 
-pub struct SharedTaskState {
-    state: TaskState,
-    next_trigger: chrono::DateTime<chrono::Local>,
+impl rusqlite::types::FromSql for TaskState {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        match value.as_i64()? {
+            0 => Ok(TaskState::None),
+            1 => Ok(TaskState::Pending),
+            2 => Ok(TaskState::Failed),
+            3 => Ok(TaskState::Done),
+            _ => Err(rusqlite::types::FromSqlError::InvalidType),
+        }
+    }
 }
 
-pub struct Config {
-    pub flatmates: Vec<i64>,
-    pub global_channel_id: i64,
-    pub bot_token: String,
+impl rusqlite::types::ToSql for TaskState {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(rusqlite::types::ToSqlOutput::Owned(
+            rusqlite::types::Value::Integer(match self {
+                TaskState::None => 0,
+                TaskState::Pending => 1,
+                TaskState::Failed => 2,
+                TaskState::Done => 3,
+            }),
+        ))
+    }
 }
 
-fn required_env(name: &str) -> Result<String, error::GstaldergeistError> {
-    env::var(name)
-        .map_err(|_| error::GstaldergeistError::ConfigError(format!("{} not set", name)))
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedTaskState {
+    state: TaskState,
+    /// Id of the reminder message carrying the Done / I can't buttons, while
+    /// that reminder is still outstanding.
+    message_id: Option<i32>,
+    /// Day the latest reminder was sent on. It asks for the trashes collected
+    /// the day after, even when answered after midnight.
+    sent_on: Option<chrono::NaiveDate>,
 }
 
-/// Parse a comma-separated list of Telegram chat ids, e.g. "123, 456, 789".
-fn parse_flatmates(raw: &str) -> Result<Vec<i64>, error::GstaldergeistError> {
-    raw.split(',')
-        .map(|s| {
-            let trimmed = s.trim();
-            trimmed.parse::<i64>().map_err(|_| {
-                error::GstaldergeistError::ConfigError(format!(
-                    "TELEGRAM_FLATMATES must be a comma-separated list of numbers like \
-                     123,456,789, got '{}'",
-                    trimmed
-                ))
-            })
-        })
-        .collect()
-}
+impl SharedTaskState {
+    /// The trashes the latest reminder asks to put out, or those of tomorrow
+    /// if it is not known when it was sent.
+    pub fn reminder_trashes(
+        &self,
+        now: chrono::DateTime<chrono_tz::Tz>,
+    ) -> Vec<data_grabber::TrashType> {
+        stats::tomorrow_trashes(self.sent_on.unwrap_or(now.date_naive()))
+    }
 
-fn config() -> Result<Config, error::GstaldergeistError> {
-    let bot_token = required_env("TELEGRAM_BOT_TOKEN")?;
-    let channel_id_str = required_env("TELEGRAM_CHANNEL_ID")?;
-    let channel_id: i64 = channel_id_str.trim().parse().map_err(|_| {
-        error::GstaldergeistError::ConfigError("TELEGRAM_CHANNEL_ID must be a number".to_string())
-    })?;
-    let flatmates = parse_flatmates(&required_env("TELEGRAM_FLATMATES")?)?;
-
-    Ok(Config {
-        flatmates,
-        global_channel_id: channel_id,
-        bot_token,
-    })
+    /// Save the state to the database so a restart resumes where we left off.
+    /// A failed write is logged rather than propagated: losing persistence is
+    /// better than losing the reminder itself.
+    pub fn persist(&self) {
+        if let Err(e) = database::save_task_state(self) {
+            tracing::error!("Failed to persist task state: {}", e);
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), error::GstaldergeistError> {
-    let app = config()?;
+    let config_path = config::config_path();
+    let app = config::load(&config_path)?;
     tracing::subscriber::set_global_default(
         tracing_subscriber::fmt::Subscriber::builder().finish(),
     )
     .unwrap();
+    database::set_path(app.database_path.clone());
+    database::migrate()?;
 
     let bot = Bot::new(&app.bot_token);
-    let task_state = std::sync::Arc::new(std::sync::Mutex::new(SharedTaskState {
-        state: TaskState::None,
-        next_trigger: compute_next_trigger(),
-    }));
-    let app_state = dptree::deps![std::sync::Arc::clone(&task_state)];
-    let scheduled_task = tokio::spawn(send_scheduled_messages(app, task_state, bot.clone()));
+    let clock: std::sync::Arc<dyn clock::Clock> =
+        std::sync::Arc::new(clock::SystemClock { tz: app.timezone });
+    data_grabber::fetch::set_cache(app.cache.clone(), clock.clone());
+    let task_state = std::sync::Arc::new(std::sync::Mutex::new(restore_task_state()));
+    let chore_states = std::sync::Arc::new(std::sync::Mutex::new(chores::restore_chore_states(
+        &app,
+        clock.now(),
+    )));
+    let config = config::ConfigHandle::new(app);
+    // Lets button presses that need the group's help, and configuration
+    // changes, wake the scheduler.
+    let wakeup = std::sync::Arc::new(tokio::sync::Notify::new());
+    let app_state = dptree::deps![
+        std::sync::Arc::clone(&task_state),
+        std::sync::Arc::clone(&chore_states),
+        config.clone(),
+        std::sync::Arc::clone(&wakeup),
+        std::sync::Arc::clone(&clock)
+    ];
+    match config.current().http.clone() {
+        #[cfg(feature = "http")]
+        Some(http_config) => {
+            tokio::spawn(http::serve(
+                http_config.listen,
+                http::AppState {
+                    config: config.clone(),
+                    clock: std::sync::Arc::clone(&clock),
+                    task_state: std::sync::Arc::clone(&task_state),
+                    bot: bot.clone(),
+                },
+            ));
+        }
+        #[cfg(not(feature = "http"))]
+        Some(_) => tracing::warn!("http is configured, but this build lacks the http feature"),
+        None => {}
+    }
+    tokio::spawn(config::watch(
+        config_path,
+        config.clone(),
+        std::sync::Arc::clone(&wakeup),
+    ));
+    let scheduled_task = tokio::spawn(supervise_scheduler(
+        config,
+        clock,
+        task_state,
+        chore_states,
+        wakeup,
+        bot.clone(),
+    ));
 
     let message_handler = Update::filter_message().endpoint(answer_handler::handle_message);
     let callback_handler =
@@ -92,262 +162,700 @@ async fn main() -> Result<(), error::GstaldergeistError> {
         .build()
         .dispatch()
         .await;
-    if let Err(e) = scheduled_task.await {
-        tracing::error!("Scheduled task failed: {}", e);
+    // The scheduler stops with the bot.
+    scheduled_task.abort();
+    Ok(())
+}
+
+/// Load the task state saved before the last shutdown, or start fresh if there
+/// is none. A pending reminder stays pending, so the escalation steps still
+/// ahead carry on after a restart.
+fn restore_task_state() -> SharedTaskState {
+    match database::load_task_state() {
+        Ok(Some(task)) => {
+            tracing::info!("Restored task state {:?}", task.state);
+            task
+        }
+        Ok(None) => SharedTaskState {
+            state: TaskState::None,
+            message_id: None,
+            sent_on: None,
+        },
+        Err(e) => {
+            tracing::error!("Failed to restore task state, starting fresh: {}", e);
+            SharedTaskState {
+                state: TaskState::None,
+                message_id: None,
+                sent_on: None,
+            }
+        }
+    }
+}
+
+/// Sunday's overview of the week ahead for the new food master.
+const WEEKLY_ROTATION_JOB: &str = "weekly_rotation";
+/// The daily reminder to put tomorrow's trashes out.
+const TRASH_REMINDER_JOB: &str = "trash_reminder";
+/// Prefix of the escalation steps' jobs, followed by the step's hour.
+const ESCALATION_JOB_PREFIX: &str = "escalation_";
+/// A late reminder is still useful: the trashes only go out in the morning.
+const REMINDER_CATCH_UP_HOURS: i64 = 6;
+/// A late escalation step would pile up on top of the reminder caught up
+/// with it.
+const STEP_CATCH_UP_MINUTES: i64 = 15;
+
+/// Register the weekly rotation, the daily reminder and one job per
+/// escalation step.
+fn register_jobs(
+    scheduler: &mut scheduler::Scheduler,
+    policy: &EscalationPolicy,
+) -> Result<(), error::GstaldergeistError> {
+    let reminder_catch_up = chrono::Duration::hours(REMINDER_CATCH_UP_HOURS);
+    // Registered first, so on Sundays the overview comes before the reminder.
+    scheduler.register(
+        WEEKLY_ROTATION_JOB,
+        &format!("0 {} * * 0", policy.reminder_hour),
+        reminder_catch_up,
+    )?;
+    scheduler.register(
+        TRASH_REMINDER_JOB,
+        &format!("0 {} * * *", policy.reminder_hour),
+        reminder_catch_up,
+    )?;
+    for step in &policy.steps {
+        scheduler.register(
+            &format!("{}{}", ESCALATION_JOB_PREFIX, step.hour),
+            &format!("0 {} * * *", step.hour),
+            chrono::Duration::minutes(STEP_CATCH_UP_MINUTES),
+        )?;
     }
     Ok(())
 }
 
-async fn send_order_to_human(
+async fn run_job(
     config: &Config,
-    shared_task: std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
+    shared_task: &std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
     bot: &Bot,
     schedule: &data_grabber::TrashesSchedule,
+    name: &str,
+    now: chrono::DateTime<chrono_tz::Tz>,
 ) {
-    send_update(
-        bot,
-        config,
-        schedule,
-        chrono::Local::now().weekday() == Weekday::Sun,
-        shared_task,
-    )
-    .await;
+    match name {
+        WEEKLY_ROTATION_JOB => {
+            record_absence_cover(config, schedule, now);
+            telegram_writer::weekly_update(bot, config, schedule).await
+        }
+        TRASH_REMINDER_JOB => {
+            // Left over from a day whose last escalation step never ran.
+            if shared_task.lock().unwrap().state == TaskState::Pending {
+                miss_reminder(shared_task, bot, schedule, now).await;
+            }
+            telegram_writer::daily_update(bot, config, schedule, shared_task.clone()).await;
+        }
+        _ => {
+            let step = name
+                .strip_prefix(ESCALATION_JOB_PREFIX)
+                .and_then(|hour| hour.parse().ok())
+                .and_then(|hour| config.escalation.step_at(hour));
+            match step {
+                Some((step, last)) => {
+                    escalate(config, shared_task, bot, schedule, step, last, now).await
+                }
+                None => tracing::warn!("Ignoring unknown job {}", name),
+            }
+        }
+    }
 }
 
-async fn control_human_accomplishment(
+/// Log whoever covers the coming week for a flatmate away. Covers through a
+/// swap are logged when the swap is accepted.
+fn record_absence_cover(
     config: &Config,
-    shared_task: std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
+    schedule: &data_grabber::TrashesSchedule,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) {
+    let tomorrow = schedule.today + chrono::Duration::days(1);
+    match roster::load_roster_at(&config.flatmates, tomorrow) {
+        Ok(roster) => {
+            if let Some(cover) = roster.absence_cover() {
+                stats::record(
+                    now,
+                    cover,
+                    stats::TRASH_CHORE,
+                    &[],
+                    stats::DutyOutcome::Covered,
+                );
+            }
+        }
+        Err(e) => tracing::error!("Failed to load the roster: {}", e),
+    }
+}
+
+/// Take an escalation step for a reminder nobody has answered yet. Pressing
+/// Done settles the reminder, which cancels every step still ahead. After the
+/// last step the duty counts as missed.
+async fn escalate(
+    config: &Config,
+    shared_task: &std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
     bot: &Bot,
     schedule: &data_grabber::TrashesSchedule,
+    step: &EscalationStep,
+    last: bool,
+    now: chrono::DateTime<chrono_tz::Tz>,
 ) {
-    if shared_task.lock().unwrap().state == TaskState::Pending {
-        shame_update(bot, config, schedule).await;
-        shared_task.lock().unwrap().state = TaskState::None;
+    if shared_task.lock().unwrap().state != TaskState::Pending {
+        return;
+    }
+    match step.action {
+        EscalationAction::Nudge => telegram_writer::nudge_update(bot, schedule).await,
+        EscalationAction::Group => shame_update(bot, config, schedule).await,
+        EscalationAction::NextInRotation => {
+            let tomorrow = schedule.today + chrono::Duration::days(1);
+            match roster::next_in_rotation(&config.flatmates, tomorrow, schedule.tomorrow_master_id)
+            {
+                Ok(Some(next_id)) => {
+                    telegram_writer::next_in_rotation_update(bot, schedule, next_id).await
+                }
+                // Nobody else is home: the group is the only one left to ask.
+                Ok(None) => shame_update(bot, config, schedule).await,
+                Err(e) => {
+                    tracing::error!("Failed to find the next flatmate in the rotation: {}", e);
+                    shame_update(bot, config, schedule).await;
+                }
+            }
+        }
+    }
+    if last {
+        miss_reminder(shared_task, bot, schedule, now).await;
     }
 }
 
-fn compute_next_trigger() -> chrono::DateTime<chrono::Local> {
-    next_trigger_after(chrono::Local::now())
+/// Record the unanswered reminder as a missed duty and close it.
+async fn miss_reminder(
+    shared_task: &std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
+    bot: &Bot,
+    schedule: &data_grabber::TrashesSchedule,
+    now: chrono::DateTime<chrono_tz::Tz>,
+) {
+    stats::record(
+        now,
+        schedule.tomorrow_master_id,
+        stats::TRASH_CHORE,
+        schedule.tomorrow_trashes().map_or(&[], |trashes| trashes),
+        stats::DutyOutcome::Missed,
+    );
+    close_reminder(shared_task, bot, schedule.tomorrow_master_id).await;
 }
 
-/// Next trigger relative to `now`: today at 16:00, today at 19:00, or 16:00
-/// tomorrow.
-fn next_trigger_after(
-    now: chrono::DateTime<chrono::Local>,
-) -> chrono::DateTime<chrono::Local> {
-    if now.hour() < 16 {
-        at_hour(now, 16)
-    } else if now.hour() < 19 {
-        at_hour(now, 19)
-    } else {
-        at_hour(now + chrono::Duration::days(1), 16)
+/// Settle the outstanding reminder and clear its now stale buttons.
+async fn close_reminder(
+    shared_task: &std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
+    bot: &Bot,
+    master_id: i64,
+) {
+    let reminder = {
+        let mut task = shared_task.lock().unwrap();
+        task.state = TaskState::None;
+        let reminder = task.message_id.take();
+        task.persist();
+        reminder
+    };
+    if let Some(message_id) = reminder
+        && let Err(e) = bot
+            .edit_message_reply_markup(ChatId(master_id), teloxide::types::MessageId(message_id))
+            .await
+    {
+        tracing::warn!("Failed to clear the reminder buttons: {}", e);
     }
 }
 
-/// `dt` at the given whole hour, with minutes and below zeroed.
+/// `date` at the given whole hour in `tz`, see `clock::local_time` for
+/// daylight saving changes.
 fn at_hour(
-    dt: chrono::DateTime<chrono::Local>,
+    tz: chrono_tz::Tz,
+    date: chrono::NaiveDate,
     hour: u32,
-) -> chrono::DateTime<chrono::Local> {
-    dt.date_naive()
+) -> chrono::DateTime<chrono_tz::Tz> {
+    let naive = date
         .and_hms_opt(hour, 0, 0)
-        .and_then(|naive| dt.timezone().from_local_datetime(&naive).single())
-        .expect("16:00 and 19:00 are always valid, unambiguous local times")
+        .expect("the hours the bot runs at are all below 24");
+    clock::local_time(tz, naive)
 }
 
-const MAX_COLLECT_ATTEMPTS: u32 = 5;
+/// Backoff of the scheduler's restarts.
 const INITIAL_BACKOFF_SECS: u64 = 60;
 const MAX_BACKOFF_SECS: u64 = 900;
 
-async fn collect_trashes_data_with_retries(
+/// The schedule from the stored collections when fetching them failed with
+/// `error`, if they are fresh enough. Otherwise the group is told to check
+/// the bins themselves.
+async fn fall_back_to_stored_schedule(
     config: &Config,
-) -> Result<data_grabber::TrashesSchedule, error::GstaldergeistError> {
-    let mut backoff = INITIAL_BACKOFF_SECS;
-    for attempt in 1..=MAX_COLLECT_ATTEMPTS {
-        match collect_trashes_data(config).await {
-            Ok(schedule) => return Ok(schedule),
-            Err(e) => {
-                if attempt == MAX_COLLECT_ATTEMPTS {
-                    tracing::error!(
-                        "Failed to collect trashes data after {} attempts: {}",
-                        MAX_COLLECT_ATTEMPTS,
-                        e
-                    );
-                    return Err(e);
-                }
-                tracing::warn!(
-                    "Failed to collect trashes data (attempt {}/{}): {}; retrying in {}s",
-                    attempt,
-                    MAX_COLLECT_ATTEMPTS,
-                    e,
-                    backoff
-                );
-                tokio::time::sleep(tokio::time::Duration::from_secs(backoff)).await;
-                backoff = (backoff * 2).min(MAX_BACKOFF_SECS);
-            }
+    now: chrono::DateTime<chrono_tz::Tz>,
+    bot: &Bot,
+    error: error::GstaldergeistError,
+) -> Option<data_grabber::TrashesSchedule> {
+    match data_grabber::cached_schedule(config, now).await {
+        Ok(Some(schedule)) => {
+            tracing::warn!("Using the stored schedule, as fetching failed: {}", error);
+            return Some(schedule);
         }
+        Ok(None) => tracing::warn!("The stored schedule is too old to fall back to"),
+        Err(e) => tracing::error!("Failed to load the stored schedule: {}", e),
     }
-    unreachable!("the final attempt returns from the loop");
+    telegram_writer::notify_group(
+        bot,
+        config,
+        &format!(
+            "⚠️ I couldn't fetch the trash schedule ({}). \
+             Please check the bins yourselves today.",
+            error
+        ),
+    )
+    .await;
+    None
+}
+
+/// Days ahead each collection fetches. Comparing the same days fetch after
+/// fetch is what reveals a collection moved within them. The days just past
+/// them are fetched too, to tell a collection moved there from a cancelled
+/// one.
+const COLLECT_DAYS: i64 = 7;
+
+/// The providers that failed in the last collection, by name.
+static FAILED_PROVIDERS: std::sync::Mutex<std::collections::BTreeSet<String>> =
+    std::sync::Mutex::new(std::collections::BTreeSet::new());
+
+/// Whether `failed` are other providers than the ones in `last`, which are
+/// replaced with them. The group hears about failed providers only then,
+/// not again with every collection.
+fn failed_providers_changed(
+    last: &std::sync::Mutex<std::collections::BTreeSet<String>>,
+    failed: &[data_grabber::ProviderError],
+) -> bool {
+    let names: std::collections::BTreeSet<String> =
+        failed.iter().map(|failure| failure.name.clone()).collect();
+    let mut last = last.lock().unwrap();
+    if *last == names {
+        return false;
+    }
+    *last = names;
+    true
 }
 
+/// Fetch the trashes for the coming week. Tell the group about collections
+/// that moved or were cancelled, and bring the calendar feed up to date.
 async fn collect_trashes_data(
     config: &Config,
+    now: chrono::DateTime<chrono_tz::Tz>,
+    bot: &Bot,
 ) -> Result<data_grabber::TrashesSchedule, error::GstaldergeistError> {
-    let now = chrono::Local::now();
     let today = now.date_naive();
-    let weekly = today.weekday() == chrono::Weekday::Sun;
-    let until_date = if weekly {
-        today + chrono::Duration::days(7)
-    } else {
-        today + chrono::Duration::days(1)
-    };
-    let trashes_schedule = data_grabber::get_trashes(&config, today, until_date).await?;
-    database::set_trashes(&trashes_schedule.dates)?;
+    let until_date = today + chrono::Duration::days(COLLECT_DAYS + sync::MAX_MOVE_DAYS);
+    let trashes_schedule = data_grabber::get_trashes(config, today, until_date).await?;
+    let changes = database::sync_trashes(&trashes_schedule.by_provider, today, now)?;
+    if trashes_schedule.failed.is_empty() {
+        metrics::collection_succeeded(now);
+    }
+    if let Some(message) = sync::changes_message(&changes) {
+        telegram_writer::notify_group(bot, config, &message).await;
+    }
+    if failed_providers_changed(&FAILED_PROVIDERS, &trashes_schedule.failed)
+        && let Some(message) = telegram_writer::missing_providers_txt(&trashes_schedule.failed)
+    {
+        telegram_writer::notify_group(bot, config, &message).await;
+    }
+    if let Err(e) = calendar::write_feed(config, now).await {
+        tracing::error!("Failed to write the calendar feed: {}", e);
+    }
     Ok(trashes_schedule)
 }
 
+/// Run the scheduler, restarting it whenever it fails or panics, and tell
+/// the group about each crash.
+async fn supervise_scheduler(
+    config_handle: config::ConfigHandle,
+    clock: std::sync::Arc<dyn clock::Clock>,
+    shared_task: std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
+    chore_states: std::sync::Arc<std::sync::Mutex<chores::ChoreStates>>,
+    wakeup: std::sync::Arc<tokio::sync::Notify>,
+    bot: Bot,
+) {
+    let mut backoff = INITIAL_BACKOFF_SECS;
+    loop {
+        let started = clock.now();
+        let scheduler = tokio::spawn(send_scheduled_messages(
+            config_handle.clone(),
+            std::sync::Arc::clone(&clock),
+            std::sync::Arc::clone(&shared_task),
+            std::sync::Arc::clone(&chore_states),
+            std::sync::Arc::clone(&wakeup),
+            bot.clone(),
+        ));
+        let crash = match scheduler.await {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => e.to_string(),
+            Err(e) => e.to_string(),
+        };
+        health::scheduler_stopped();
+        // A scheduler that ran for a while crashed anew rather than again
+        // right at startup.
+        if clock.now() - started > chrono::Duration::seconds(MAX_BACKOFF_SECS as i64) {
+            backoff = INITIAL_BACKOFF_SECS;
+        }
+        tracing::error!(
+            "The scheduler crashed, restarting it in {}s: {}",
+            backoff,
+            crash
+        );
+        telegram_writer::notify_group(
+            &bot,
+            &config_handle.current(),
+            &format!(
+                "⚠️ My scheduler crashed ({}). I'm restarting it, but reminders may be late.",
+                crash
+            ),
+        )
+        .await;
+        // A panic while holding a lock poisons it, and every later `unwrap()`
+        // would panic too. The state inside is the last one written.
+        shared_task.clear_poison();
+        chore_states.clear_poison();
+        tokio::time::sleep(tokio::time::Duration::from_secs(backoff)).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF_SECS);
+    }
+}
+
+/// Longest single sleep, so the loop notices a system suspend or a change of
+/// the wall clock.
+const MAX_SLEEP_SECS: u64 = 3600;
+
 // Function to send messages on a schedule
 async fn send_scheduled_messages(
-    config: Config,
+    config_handle: config::ConfigHandle,
+    clock: std::sync::Arc<dyn clock::Clock>,
     shared_task: std::sync::Arc<std::sync::Mutex<SharedTaskState>>,
+    chore_states: std::sync::Arc<std::sync::Mutex<chores::ChoreStates>>,
+    wakeup: std::sync::Arc<tokio::sync::Notify>,
     bot: Bot,
 ) -> Result<(), error::GstaldergeistError> {
-    if let Err(e) = collect_trashes_data(&config).await {
+    let mut config = config_handle.current();
+    let mut scheduler = scheduler::Scheduler::restore(clock.clone());
+    register_jobs(&mut scheduler, &config.escalation)?;
+    scheduler.persist();
+    let now = clock.now();
+    if let Err(e) = collect_trashes_data(&config, now, &bot).await {
         tracing::error!("Initial trash data collection failed: {}", e);
     }
     loop {
-        let now = chrono::Local::now();
-        let mut next_trigger = shared_task.lock().unwrap().next_trigger;
-        if now < next_trigger {
-            tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
-            continue;
+        health::scheduler_heartbeat(clock.now());
+        let latest = config_handle.current();
+        if !std::sync::Arc::ptr_eq(&latest, &config) {
+            config = latest;
+            // Jobs whose schedule is unchanged keep their next run.
+            scheduler = scheduler::Scheduler::new(clock.clone(), scheduler.records());
+            register_jobs(&mut scheduler, &config.escalation)?;
+            scheduler.persist();
+            chores::reload_chore_states(&config, &mut chore_states.lock().unwrap(), clock.now());
         }
-        // A failed collection must not kill the scheduler, or all future
-        // reminders silently stop while the bot keeps running.
-        let trashes_schedule = match collect_trashes_data_with_retries(&config).await {
-            Ok(schedule) => schedule,
-            Err(e) => {
-                telegram_writer::notify_group(
-                    &bot,
-                    &config,
-                    &format!(
-                        "⚠️ I couldn't fetch the trash schedule after {} attempts ({}). \
-                         Please check the bins yourselves today.",
-                        MAX_COLLECT_ATTEMPTS, e
-                    ),
-                )
-                .await;
-                next_trigger = compute_next_trigger();
-                shared_task.lock().unwrap().next_trigger = next_trigger;
-                continue;
+        chores::run_due_chores(&config, clock.as_ref(), &chore_states, &bot).await;
+        let due = scheduler.take_due();
+        scheduler.persist();
+        // "I can't" asks the group right away instead of waiting for a job.
+        let failed = shared_task.lock().unwrap().state == TaskState::Failed;
+        if failed || !due.is_empty() {
+            // A failed collection must not kill the scheduler, or all future
+            // reminders silently stop while the bot keeps running. Each
+            // provider was already retried as its fetch policy says.
+            let trashes_schedule = match collect_trashes_data(&config, clock.now(), &bot).await {
+                Ok(trashes_schedule) => Some(trashes_schedule),
+                Err(e) => {
+                    tracing::error!("Failed to collect trashes data: {}", e);
+                    fall_back_to_stored_schedule(&config, clock.now(), &bot, e).await
+                }
+            };
+            match trashes_schedule {
+                Some(trashes_schedule) => {
+                    if failed {
+                        close_reminder(&shared_task, &bot, trashes_schedule.tomorrow_master_id)
+                            .await;
+                        shame_update(&bot, &config, &trashes_schedule).await;
+                    }
+                    for job in &due {
+                        run_job(
+                            &config,
+                            &shared_task,
+                            &bot,
+                            &trashes_schedule,
+                            &job.name,
+                            clock.now(),
+                        )
+                        .await;
+                    }
+                }
+                None => {
+                    if failed {
+                        let mut task = shared_task.lock().unwrap();
+                        task.state = TaskState::None;
+                        task.persist();
+                    }
+                }
             }
-        };
-        if next_trigger.hour() >= 19 {
-            control_human_accomplishment(&config, shared_task.clone(), &bot, &trashes_schedule)
-                .await;
-        } else {
-            send_order_to_human(&config, shared_task.clone(), &bot, &trashes_schedule).await;
         }
-        next_trigger = compute_next_trigger();
-        shared_task.lock().unwrap().next_trigger = next_trigger;
+        let next_run = [scheduler.next_run(), chores::next_trigger(&chore_states)]
+            .into_iter()
+            .flatten()
+            .min();
+        let max_sleep = std::time::Duration::from_secs(MAX_SLEEP_SECS);
+        let sleep = next_run.map_or(max_sleep, |next_run| {
+            (next_run - clock.now())
+                .to_std()
+                .unwrap_or_default()
+                .min(max_sleep)
+        });
+        tokio::select! {
+            _ = tokio::time::sleep(sleep) => {}
+            _ = wakeup.notified() => {}
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::TimeZone;
+    use chrono::{TimeZone, Timelike};
+    use clock::Clock;
 
-    fn local(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> chrono::DateTime<chrono::Local> {
-        chrono::Local
+    fn local(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> chrono::DateTime<chrono_tz::Tz> {
+        chrono_tz::Europe::Zurich
             .with_ymd_and_hms(year, month, day, hour, min, sec)
             .single()
             .expect("unambiguous local time")
     }
 
+    fn jobs(
+        clock: &std::sync::Arc<clock::FakeClock>,
+        policy: &EscalationPolicy,
+    ) -> scheduler::Scheduler {
+        let mut scheduler = scheduler::Scheduler::new(clock.clone(), Vec::new());
+        register_jobs(&mut scheduler, policy).unwrap();
+        scheduler
+    }
+
+    fn default_next_run(now: chrono::DateTime<chrono_tz::Tz>) -> chrono::DateTime<chrono_tz::Tz> {
+        let clock = std::sync::Arc::new(clock::FakeClock::new(now));
+        jobs(&clock, &EscalationPolicy::default())
+            .next_run()
+            .unwrap()
+    }
+
+    fn run_at(
+        scheduler: &mut scheduler::Scheduler,
+        clock: &clock::FakeClock,
+        now: chrono::DateTime<chrono_tz::Tz>,
+    ) -> Vec<String> {
+        clock.set(now);
+        scheduler
+            .take_due()
+            .into_iter()
+            .map(|job| job.name)
+            .collect()
+    }
+
+    fn failure(name: &str) -> data_grabber::ProviderError {
+        data_grabber::ProviderError {
+            name: name.to_string(),
+            error: error::GstaldergeistError::Other("no answer within 30s".to_string()),
+        }
+    }
+
+    #[test]
+    fn failed_providers_are_only_announced_when_they_change() {
+        let last = std::sync::Mutex::new(std::collections::BTreeSet::new());
+        assert!(!failed_providers_changed(&last, &[]));
+        assert!(failed_providers_changed(&last, &[failure("we_recycle")]));
+        assert!(!failed_providers_changed(&last, &[failure("we_recycle")]));
+        assert!(failed_providers_changed(
+            &last,
+            &[failure("adliswil"), failure("we_recycle")]
+        ));
+        assert!(failed_providers_changed(&last, &[]));
+        assert!(failed_providers_changed(&last, &[failure("we_recycle")]));
+    }
+
     #[test]
     fn before_first_trigger_schedules_today_at_16() {
-        let now = local(2026, 6, 7, 9, 30, 12);
-        assert_eq!(next_trigger_after(now), local(2026, 6, 7, 16, 0, 0));
+        let now = local(2026, 6, 8, 9, 30, 12);
+        assert_eq!(default_next_run(now), local(2026, 6, 8, 16, 0, 0));
     }
 
     #[test]
     fn between_triggers_schedules_today_at_19() {
-        let now = local(2026, 6, 7, 16, 30, 45);
-        assert_eq!(next_trigger_after(now), local(2026, 6, 7, 19, 0, 0));
+        let now = local(2026, 6, 8, 16, 30, 45);
+        assert_eq!(default_next_run(now), local(2026, 6, 8, 19, 0, 0));
     }
 
     #[test]
     fn after_last_trigger_rolls_over_to_next_day_at_16() {
-        let now = local(2026, 6, 7, 19, 5, 0);
-        assert_eq!(next_trigger_after(now), local(2026, 6, 8, 16, 0, 0));
+        let now = local(2026, 6, 8, 19, 5, 0);
+        assert_eq!(default_next_run(now), local(2026, 6, 9, 16, 0, 0));
     }
 
     #[test]
     fn at_16_exactly_schedules_19_same_day() {
-        let now = local(2026, 6, 7, 16, 0, 0);
-        assert_eq!(next_trigger_after(now), local(2026, 6, 7, 19, 0, 0));
+        let clock = std::sync::Arc::new(clock::FakeClock::new(local(2026, 6, 8, 15, 0, 0)));
+        let mut scheduler = jobs(&clock, &EscalationPolicy::default());
+        assert_eq!(
+            run_at(&mut scheduler, &clock, local(2026, 6, 8, 16, 0, 0)),
+            vec![TRASH_REMINDER_JOB]
+        );
+        assert_eq!(scheduler.next_run(), Some(local(2026, 6, 8, 19, 0, 0)));
     }
 
     #[test]
     fn at_19_exactly_rolls_over_to_next_day() {
-        let now = local(2026, 6, 7, 19, 0, 0);
-        assert_eq!(next_trigger_after(now), local(2026, 6, 8, 16, 0, 0));
+        let clock = std::sync::Arc::new(clock::FakeClock::new(local(2026, 6, 8, 17, 0, 0)));
+        let mut scheduler = jobs(&clock, &EscalationPolicy::default());
+        assert_eq!(
+            run_at(&mut scheduler, &clock, local(2026, 6, 8, 19, 0, 0)),
+            vec!["escalation_19"]
+        );
+        assert_eq!(scheduler.next_run(), Some(local(2026, 6, 9, 16, 0, 0)));
     }
 
     #[test]
     fn trigger_zeroes_minutes_seconds_and_subseconds() {
-        let now = local(2026, 6, 7, 15, 59, 59)
+        let now = local(2026, 6, 8, 15, 59, 59)
             .with_nanosecond(987_654_321)
             .unwrap();
-        let next = next_trigger_after(now);
+        let next = default_next_run(now);
         assert_eq!(next.minute(), 0);
         assert_eq!(next.second(), 0);
         assert_eq!(next.nanosecond(), 0);
-        assert_eq!(next, local(2026, 6, 7, 16, 0, 0));
+        assert_eq!(next, local(2026, 6, 8, 16, 0, 0));
     }
 
     #[test]
     fn rollover_keeps_month_and_year_boundaries() {
         let now = local(2026, 12, 31, 20, 0, 0);
-        assert_eq!(next_trigger_after(now), local(2027, 1, 1, 16, 0, 0));
+        assert_eq!(default_next_run(now), local(2027, 1, 1, 16, 0, 0));
     }
 
     #[test]
-    fn parse_flatmates_single_value() {
-        assert_eq!(parse_flatmates("123").unwrap(), vec![123]);
+    fn sunday_overview_comes_before_the_reminder() {
+        // 2026-06-07 is a Sunday.
+        let clock = std::sync::Arc::new(clock::FakeClock::new(local(2026, 6, 7, 9, 0, 0)));
+        let mut scheduler = jobs(&clock, &EscalationPolicy::default());
+        assert_eq!(
+            run_at(&mut scheduler, &clock, local(2026, 6, 7, 16, 0, 0)),
+            vec![WEEKLY_ROTATION_JOB, TRASH_REMINDER_JOB]
+        );
+        assert_eq!(
+            run_at(&mut scheduler, &clock, local(2026, 6, 8, 16, 0, 0)),
+            vec![TRASH_REMINDER_JOB]
+        );
     }
 
     #[test]
-    fn parse_flatmates_multiple_values() {
-        assert_eq!(parse_flatmates("123,456,789").unwrap(), vec![123, 456, 789]);
+    fn walks_through_every_escalation_step() {
+        let policy =
+            escalation::parse_escalation("18:nudge, 20:nudge, 21:group, 22:next", 16).unwrap();
+        let clock = std::sync::Arc::new(clock::FakeClock::new(local(2026, 6, 8, 15, 0, 0)));
+        let mut scheduler = jobs(&clock, &policy);
+        let runs: Vec<_> = [16, 18, 20, 21, 22]
+            .into_iter()
+            .flat_map(|hour| run_at(&mut scheduler, &clock, local(2026, 6, 8, hour, 0, 0)))
+            .collect();
+        assert_eq!(
+            runs,
+            vec![
+                TRASH_REMINDER_JOB,
+                "escalation_18",
+                "escalation_20",
+                "escalation_21",
+                "escalation_22"
+            ]
+        );
+        assert_eq!(scheduler.next_run(), Some(local(2026, 6, 9, 16, 0, 0)));
     }
 
     #[test]
-    fn parse_flatmates_trims_whitespace() {
+    fn sunday_rotation_then_monday_shame() {
+        // 2026-06-07 is a Sunday; Normal goes out Monday, Bio on Tuesday.
+        let schedule_on = |today: chrono::NaiveDate| data_grabber::TrashesSchedule {
+            today,
+            dates: [
+                (date(2026, 6, 8), vec![data_grabber::TrashType::Normal]),
+                (date(2026, 6, 9), vec![data_grabber::TrashType::Bio]),
+            ]
+            .into_iter()
+            .collect(),
+            by_provider: Vec::new(),
+            tomorrow_master_name: "Alice".to_string(),
+            tomorrow_master_id: 10,
+            away: Vec::new(),
+            cached: None,
+            failed: Vec::new(),
+        };
+        let config = config::test_config(vec![10, 20]);
+        let clock = std::sync::Arc::new(clock::FakeClock::new(local(2026, 6, 7, 9, 0, 0)));
+        let mut scheduler = jobs(&clock, &config.escalation);
+
         assert_eq!(
-            parse_flatmates(" 123 , 456 ,789 ").unwrap(),
-            vec![123, 456, 789]
+            run_at(&mut scheduler, &clock, local(2026, 6, 7, 16, 0, 0)),
+            vec![WEEKLY_ROTATION_JOB, TRASH_REMINDER_JOB]
+        );
+        let sunday = schedule_on(clock.now().date_naive());
+        assert!(
+            telegram_writer::weekly_update_txt(&sunday).ends_with("Normal on Mon,\nBio on Tue,\n")
+        );
+        assert!(
+            telegram_writer::daily_update_txt(&config, &sunday)
+                .unwrap()
+                .contains("the Normal trashes")
         );
-    }
 
-    #[test]
-    fn parse_flatmates_accepts_negative_ids() {
-        // Telegram group/channel chat ids are negative.
         assert_eq!(
-            parse_flatmates("-1001234567890,42").unwrap(),
-            vec![-1001234567890, 42]
+            run_at(&mut scheduler, &clock, local(2026, 6, 7, 19, 0, 0)),
+            vec!["escalation_19"]
+        );
+        assert_eq!(
+            run_at(&mut scheduler, &clock, local(2026, 6, 8, 16, 0, 0)),
+            vec![TRASH_REMINDER_JOB]
+        );
+        assert_eq!(
+            run_at(&mut scheduler, &clock, local(2026, 6, 8, 19, 0, 0)),
+            vec!["escalation_19"]
+        );
+        let monday = schedule_on(clock.now().date_naive());
+        assert!(
+            telegram_writer::shame_update_txt(&monday)
+                .unwrap()
+                .contains("Alice is not able")
+        );
+        assert!(
+            telegram_writer::shame_update_txt(&monday)
+                .unwrap()
+                .contains("the Bio trashes")
         );
     }
 
-    #[test]
-    fn parse_flatmates_rejects_non_numeric() {
-        let err = parse_flatmates("123,abc,789").unwrap_err();
-        assert!(matches!(err, error::GstaldergeistError::ConfigError(_)));
+    fn date(year: i32, month: u32, day: u32) -> chrono::NaiveDate {
+        chrono::NaiveDate::from_ymd_opt(year, month, day).unwrap()
     }
 
     #[test]
-    fn parse_flatmates_rejects_empty_string() {
-        assert!(parse_flatmates("").is_err());
+    fn at_hour_handles_daylight_saving_changes() {
+        let tz = chrono_tz::Europe::Zurich;
+        let utc = |month, day, hour| {
+            chrono::Utc
+                .with_ymd_and_hms(2026, month, day, hour, 0, 0)
+                .unwrap()
+        };
+        // 02:00 is skipped on 2026-03-29 and happens twice on 2026-10-25.
+        assert_eq!(at_hour(tz, date(2026, 3, 29), 2), utc(3, 29, 1));
+        assert_eq!(at_hour(tz, date(2026, 3, 29), 2).hour(), 3);
+        assert_eq!(at_hour(tz, date(2026, 10, 25), 2), utc(10, 25, 0));
+        assert_eq!(at_hour(tz, date(2026, 10, 25), 16), utc(10, 25, 15));
     }
 }
@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+const PROVIDER_FETCHES: &str = "gstaldergeist_provider_fetches_total";
+const PROVIDER_FAILURES: &str = "gstaldergeist_provider_failures_total";
+const LAST_COLLECTION: &str = "gstaldergeist_last_successful_collection_timestamp_seconds";
+const REMINDERS_SENT: &str = "gstaldergeist_reminders_sent_total";
+const ANSWERS: &str = "gstaldergeist_answers_total";
+const TELEGRAM_SEND_ERRORS: &str = "gstaldergeist_telegram_send_errors_total";
+#[cfg(feature = "http")]
+const TASK_STATE: &str = "gstaldergeist_task_state";
+
+/// A metric as exported.
+#[cfg(feature = "http")]
+struct Metric {
+    name: &'static str,
+    kind: &'static str,
+    help: &'static str,
+    /// Labelled metrics appear with their first value, the others are 0
+    /// until then.
+    labelled: bool,
+}
+
+#[cfg(feature = "http")]
+const METRICS: &[Metric] = &[
+    Metric {
+        name: PROVIDER_FETCHES,
+        kind: "counter",
+        help: "Collection dates requested from a provider.",
+        labelled: true,
+    },
+    Metric {
+        name: PROVIDER_FAILURES,
+        kind: "counter",
+        help: "Requests to a provider that failed.",
+        labelled: true,
+    },
+    Metric {
+        name: LAST_COLLECTION,
+        kind: "gauge",
+        help: "When every provider last delivered its collection dates.",
+        labelled: false,
+    },
+    Metric {
+        name: REMINDERS_SENT,
+        kind: "counter",
+        help: "Trash reminders and escalation messages sent, by kind.",
+        labelled: true,
+    },
+    Metric {
+        name: ANSWERS,
+        kind: "counter",
+        help: "Answers to the trash reminder.",
+        labelled: true,
+    },
+    Metric {
+        name: TELEGRAM_SEND_ERRORS,
+        kind: "counter",
+        help: "Messages the bot failed to send.",
+        labelled: false,
+    },
+    Metric {
+        name: TASK_STATE,
+        kind: "gauge",
+        help: "1 for the state the trash reminder is in, 0 for the others.",
+        labelled: true,
+    },
+];
+
+type Labels = Vec<(&'static str, String)>;
+
+/// Current values of the metrics, by name and labels.
+struct Registry {
+    values: Mutex<BTreeMap<(&'static str, Labels), f64>>,
+}
+
+static REGISTRY: Registry = Registry::new();
+
+impl Registry {
+    const fn new() -> Self {
+        Registry {
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn labels(labels: &[(&'static str, &str)]) -> Labels {
+        labels
+            .iter()
+            .map(|(name, value)| (*name, value.to_string()))
+            .collect()
+    }
+
+    fn add(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+        *self
+            .values
+            .lock()
+            .unwrap()
+            .entry((name, Self::labels(labels)))
+            .or_default() += value;
+    }
+
+    fn set(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+        self.values
+            .lock()
+            .unwrap()
+            .insert((name, Self::labels(labels)), value);
+    }
+
+    /// The metrics in the Prometheus text format.
+    #[cfg(feature = "http")]
+    fn render(&self) -> String {
+        let values = self.values.lock().unwrap();
+        let mut text = String::new();
+        for metric in METRICS {
+            let name = metric.name;
+            text.push_str(&format!("# HELP {} {}\n", name, metric.help));
+            text.push_str(&format!("# TYPE {} {}\n", name, metric.kind));
+            let mut samples = values
+                .iter()
+                .filter(|((sample, _), _)| *sample == name)
+                .peekable();
+            if samples.peek().is_none() && !metric.labelled {
+                text.push_str(&format!("{} 0\n", name));
+            }
+            for ((_, labels), value) in samples {
+                text.push_str(name);
+                if !labels.is_empty() {
+                    let labels: Vec<String> = labels
+                        .iter()
+                        .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+                        .collect();
+                    text.push_str(&format!("{{{}}}", labels.join(",")));
+                }
+                text.push_str(&format!(" {}\n", value));
+            }
+        }
+        text
+    }
+}
+
+/// Escape a label value.
+#[cfg(feature = "http")]
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Count a request to `provider` and whether it failed.
+pub fn provider_fetched(provider: &str, failed: bool) {
+    REGISTRY.add(PROVIDER_FETCHES, &[("provider", provider)], 1.0);
+    if failed {
+        REGISTRY.add(PROVIDER_FAILURES, &[("provider", provider)], 1.0);
+    }
+}
+
+/// Note that every provider delivered its collection dates at `at`.
+pub fn collection_succeeded(at: chrono::DateTime<chrono_tz::Tz>) {
+    REGISTRY.set(LAST_COLLECTION, &[], at.timestamp() as f64);
+}
+
+/// Count a trash reminder or escalation message of the given `kind` sent.
+pub fn reminder_sent(kind: &str) {
+    REGISTRY.add(REMINDERS_SENT, &[("kind", kind)], 1.0);
+}
+
+/// Count an `answer` to the trash reminder, like "done" or "cant".
+pub fn answered(answer: &str) {
+    REGISTRY.add(ANSWERS, &[("answer", answer)], 1.0);
+}
+
+pub fn telegram_send_failed() {
+    REGISTRY.add(TELEGRAM_SEND_ERRORS, &[], 1.0);
+}
+
+/// Every metric in the Prometheus text format, with `state` as the state of
+/// the trash reminder.
+#[cfg(feature = "http")]
+pub fn render(state: &crate::TaskState) -> String {
+    use crate::TaskState;
+    for (name, candidate) in [
+        ("none", TaskState::None),
+        ("pending", TaskState::Pending),
+        ("failed", TaskState::Failed),
+        ("done", TaskState::Done),
+    ] {
+        let value = if *state == candidate { 1.0 } else { 0.0 };
+        REGISTRY.set(TASK_STATE, &[("state", name)], value);
+    }
+    REGISTRY.render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_add_up_per_label() {
+        let registry = Registry::new();
+        registry.add(PROVIDER_FETCHES, &[("provider", "adliswil")], 1.0);
+        registry.add(PROVIDER_FETCHES, &[("provider", "adliswil")], 1.0);
+        registry.add(PROVIDER_FETCHES, &[("provider", "ics")], 1.0);
+        let values = registry.values.lock().unwrap();
+        let value =
+            |provider: &str| values[&(PROVIDER_FETCHES, vec![("provider", provider.to_string())])];
+        assert_eq!(value("adliswil"), 2.0);
+        assert_eq!(value("ics"), 1.0);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn renders_the_prometheus_text_format() {
+        let registry = Registry::new();
+        registry.add(PROVIDER_FAILURES, &[("provider", "we\"recycle")], 1.0);
+        registry.set(LAST_COLLECTION, &[], 1767632400.0);
+        let text = registry.render();
+        assert!(text.contains(
+            "# HELP gstaldergeist_provider_failures_total Requests to a provider that failed.\n\
+             # TYPE gstaldergeist_provider_failures_total counter\n\
+             gstaldergeist_provider_failures_total{provider=\"we\\\"recycle\"} 1\n"
+        ));
+        assert!(
+            text.contains(
+                "gstaldergeist_last_successful_collection_timestamp_seconds 1767632400\n"
+            )
+        );
+        // Unlabelled metrics are there from the start, labelled ones only
+        // with a value.
+        assert!(text.contains("gstaldergeist_telegram_send_errors_total 0\n"));
+        assert!(!text.contains("gstaldergeist_provider_fetches_total{"));
+    }
+}
@@ -0,0 +1,788 @@
+use crate::error::GstaldergeistError;
+use chrono::{Datelike, NaiveDate};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RosterMember {
+    pub chat_id: i64,
+    /// Away members are skipped when the rotation reaches them.
+    pub away: bool,
+}
+
+/// A week of duty one flatmate took over from another through a swap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Debt {
+    /// Flatmate who handed their week over and owes one back.
+    pub debtor: i64,
+    /// Flatmate who covered and is owed a week.
+    pub creditor: i64,
+}
+
+/// A planned absence, e.g. a holiday, with both ends inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Absence {
+    pub chat_id: i64,
+    pub from: NaiveDate,
+    pub until: NaiveDate,
+}
+
+impl Absence {
+    fn overlaps(&self, from: NaiveDate, until: NaiveDate) -> bool {
+        self.from <= until && from <= self.until
+    }
+}
+
+/// The trash duty rotation: flatmates in an explicit order plus a pointer to
+/// whoever is on duty. Unlike deriving the slot from the ISO week number,
+/// adding or removing someone leaves everyone else's turn untouched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Roster {
+    pub members: Vec<RosterMember>,
+    /// Index into `members` of the flatmate on duty, before skipping anyone
+    /// away.
+    pub current: usize,
+    /// Monday of the week `current` is on duty for.
+    pub week: NaiveDate,
+    /// Flatmate covering this week instead of the one the rotation picked,
+    /// after a swap or to pay back a debt.
+    pub substitute: Option<i64>,
+    /// Outstanding swap debts, oldest first.
+    pub debts: Vec<Debt>,
+    /// Planned absences. A flatmate away for any day of a week is skipped
+    /// that week.
+    pub absences: Vec<Absence>,
+}
+
+/// Monday of the week containing `date`.
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+impl Roster {
+    /// A roster for `flatmates` in the given order. The flatmate on duty is the
+    /// one the former ISO-week formula picked for `date`, so switching to the
+    /// roster does not hand the current week to someone else.
+    pub fn new(flatmates: &[i64], date: NaiveDate) -> Self {
+        if flatmates.is_empty() {
+            return Roster {
+                members: Vec::new(),
+                current: 0,
+                week: week_start(date),
+                substitute: None,
+                debts: Vec::new(),
+                absences: Vec::new(),
+            };
+        }
+        let on_duty = crate::data_grabber::food_master_id(flatmates, date);
+        Roster {
+            members: flatmates
+                .iter()
+                .map(|&chat_id| RosterMember {
+                    chat_id,
+                    away: false,
+                })
+                .collect(),
+            current: flatmates
+                .iter()
+                .position(|&chat_id| chat_id == on_duty)
+                .unwrap_or(0),
+            week: week_start(date),
+            substitute: None,
+            debts: Vec::new(),
+            absences: Vec::new(),
+        }
+    }
+
+    fn position(&self, chat_id: i64) -> Option<usize> {
+        self.members
+            .iter()
+            .position(|member| member.chat_id == chat_id)
+    }
+
+    /// Whether `chat_id` is away on any day of the roster's week, either
+    /// until further notice or through a planned absence.
+    pub fn is_away(&self, chat_id: i64) -> bool {
+        let week_end = self.week + chrono::Duration::days(6);
+        self.members
+            .iter()
+            .any(|member| member.chat_id == chat_id && member.away)
+            || self
+                .absences
+                .iter()
+                .any(|absence| absence.chat_id == chat_id && absence.overlaps(self.week, week_end))
+    }
+
+    fn is_available(&self, chat_id: i64) -> bool {
+        self.position(chat_id).is_some() && !self.is_away(chat_id)
+    }
+
+    /// Chat id of the flatmate on duty, or `None` for an empty roster.
+    pub fn on_duty(&self) -> Option<i64> {
+        if self.members.is_empty() {
+            return None;
+        }
+        self.substitute.or(Some(self.members[self.current].chat_id))
+    }
+
+    /// Who covers this week because the flatmate the rotation picked is
+    /// away, if anyone.
+    pub fn absence_cover(&self) -> Option<i64> {
+        let scheduled = self.members.get(self.current)?.chat_id;
+        if !self.is_away(scheduled) {
+            return None;
+        }
+        self.substitute
+    }
+
+    /// Let `chat_id` cover this week for whoever is on duty, who then owes
+    /// them a week. Returns `false` if `chat_id` is not on the roster or is
+    /// already on duty.
+    pub fn swap(&mut self, chat_id: i64) -> bool {
+        let Some(on_duty) = self.on_duty() else {
+            return false;
+        };
+        if on_duty == chat_id || self.position(chat_id).is_none() {
+            return false;
+        }
+        self.debts.push(Debt {
+            debtor: on_duty,
+            creditor: chat_id,
+        });
+        self.substitute = Some(chat_id);
+        true
+    }
+
+    /// Insert a flatmate at `position` (clamped to the end of the roster).
+    /// Whoever is on duty stays on duty. Inserting someone already on the
+    /// roster does nothing.
+    pub fn insert(&mut self, chat_id: i64, position: usize) {
+        if self.position(chat_id).is_some() {
+            return;
+        }
+        let position = position.min(self.members.len());
+        self.members.insert(
+            position,
+            RosterMember {
+                chat_id,
+                away: false,
+            },
+        );
+        if position <= self.current && self.members.len() > 1 {
+            self.current += 1;
+        }
+    }
+
+    /// Remove a flatmate. If they were on duty, the next flatmate takes over;
+    /// otherwise whoever is on duty stays on duty.
+    pub fn remove(&mut self, chat_id: i64) {
+        let Some(position) = self.position(chat_id) else {
+            return;
+        };
+        self.members.remove(position);
+        self.debts
+            .retain(|debt| debt.debtor != chat_id && debt.creditor != chat_id);
+        if self.substitute == Some(chat_id) {
+            self.substitute = None;
+        }
+        if position < self.current {
+            self.current -= 1;
+        }
+        if self.current >= self.members.len() {
+            self.current = 0;
+        }
+    }
+
+    /// Mark a flatmate as away until further notice, or back. Returns `false`
+    /// if they are not on the roster.
+    pub fn set_away(&mut self, chat_id: i64, away: bool) -> bool {
+        let Some(position) = self.position(chat_id) else {
+            return false;
+        };
+        self.members[position].away = away;
+        self.cover_absence();
+        true
+    }
+
+    /// Plan an absence. Returns `false` if the flatmate is not on the roster.
+    pub fn add_absence(&mut self, absence: Absence) -> bool {
+        if self.position(absence.chat_id).is_none() {
+            return false;
+        }
+        self.absences.push(absence);
+        self.cover_absence();
+        true
+    }
+
+    /// Cut short the planned absences of `chat_id` that are still running on
+    /// `today`, so they end yesterday.
+    pub fn end_absences(&mut self, chat_id: i64, today: NaiveDate) {
+        let yesterday = today - chrono::Duration::days(1);
+        for absence in &mut self.absences {
+            if absence.chat_id == chat_id && absence.from <= today && absence.until >= today {
+                absence.until = yesterday;
+            }
+        }
+        self.absences
+            .retain(|absence| absence.from <= absence.until);
+    }
+
+    /// Forget absences that ended before `date`.
+    fn prune_absences(&mut self, date: NaiveDate) {
+        self.absences.retain(|absence| absence.until >= date);
+    }
+
+    /// Add flatmates missing from the roster at the end and drop the ones no
+    /// longer configured, keeping everyone else's order.
+    pub fn sync(&mut self, flatmates: &[i64]) {
+        let removed: Vec<i64> = self
+            .members
+            .iter()
+            .map(|member| member.chat_id)
+            .filter(|chat_id| !flatmates.contains(chat_id))
+            .collect();
+        for chat_id in removed {
+            self.remove(chat_id);
+        }
+        for &chat_id in flatmates {
+            self.insert(chat_id, self.members.len());
+        }
+    }
+
+    /// Move the duty on by one flatmate for every week boundary between the
+    /// roster's week and the week of `date`. Dates in the same or an earlier
+    /// week leave the roster untouched.
+    ///
+    /// Substitutes only cover a single week. When the rotation reaches a
+    /// flatmate who is owed a week, the debtor covers it and the debt is
+    /// settled.
+    pub fn advance_to(&mut self, date: NaiveDate) {
+        if self.members.is_empty() {
+            return;
+        }
+        let target = week_start(date);
+        while self.week < target {
+            self.current = (self.current + 1) % self.members.len();
+            self.week += chrono::Duration::days(7);
+            self.substitute = None;
+            if !self.cover_absence() {
+                self.settle_debt();
+            }
+        }
+        self.prune_absences(self.week);
+    }
+
+    /// If the flatmate whose turn it is is away and nobody covers yet, hand
+    /// the week to the next available flatmate. Like a swap, the absent
+    /// flatmate then owes a week, so they get their turn back once home.
+    /// Returns whether a cover was found.
+    fn cover_absence(&mut self) -> bool {
+        if self.members.is_empty() || self.substitute.is_some() {
+            return false;
+        }
+        let scheduled = self.members[self.current].chat_id;
+        if !self.is_away(scheduled) {
+            return false;
+        }
+        let len = self.members.len();
+        let cover = (1..len)
+            .map(|offset| self.members[(self.current + offset) % len].chat_id)
+            .find(|&chat_id| self.is_available(chat_id));
+        match cover {
+            Some(cover) => {
+                self.debts.push(Debt {
+                    debtor: scheduled,
+                    creditor: cover,
+                });
+                self.substitute = Some(cover);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Who the rotation will put on duty in the week of `date`, as things
+    /// stand: later swaps and absences can still change it.
+    pub fn on_duty_in(&self, date: NaiveDate) -> Option<i64> {
+        let mut roster = self.clone();
+        roster.advance_to(date);
+        roster.on_duty()
+    }
+
+    /// The next available flatmate after `chat_id` in the rotation, or `None`
+    /// if `chat_id` is not on the roster or nobody else is home.
+    pub fn next_after(&self, chat_id: i64) -> Option<i64> {
+        let position = self.position(chat_id)?;
+        let len = self.members.len();
+        (1..len)
+            .map(|offset| self.members[(position + offset) % len].chat_id)
+            .find(|&chat_id| self.is_available(chat_id))
+    }
+
+    fn settle_debt(&mut self) {
+        let scheduled = self.members[self.current].chat_id;
+        let repayment = self
+            .debts
+            .iter()
+            .position(|debt| debt.creditor == scheduled && self.is_available(debt.debtor));
+        if let Some(index) = repayment {
+            let debt = self.debts.remove(index);
+            self.substitute = Some(debt.debtor);
+        }
+    }
+
+    /// Flatmates away on any day between `from` and `until`, either until
+    /// further notice or through a planned absence, with the end of their
+    /// absence if known.
+    pub fn away_between(&self, from: NaiveDate, until: NaiveDate) -> Vec<(i64, Option<NaiveDate>)> {
+        self.members
+            .iter()
+            .filter_map(|member| {
+                if member.away {
+                    return Some((member.chat_id, None));
+                }
+                self.absences
+                    .iter()
+                    .filter(|absence| {
+                        absence.chat_id == member.chat_id && absence.overlaps(from, until)
+                    })
+                    .map(|absence| absence.until)
+                    .max()
+                    .map(|until| (member.chat_id, Some(until)))
+            })
+            .collect()
+    }
+}
+
+/// `stored` (or a roster seeded from `flatmates`) synced with the configured
+/// flatmates and advanced to the week of `date`.
+fn roster_at(stored: Option<Roster>, flatmates: &[i64], date: NaiveDate) -> Roster {
+    let mut roster = stored.unwrap_or_else(|| Roster::new(flatmates, date));
+    roster.sync(flatmates);
+    roster.advance_to(date);
+    roster.cover_absence();
+    roster
+}
+
+/// The roster as it stands in the week of `date`. Only a copy is advanced:
+/// looking at the roster, even at a later week, never changes it.
+pub fn load_roster_at(flatmates: &[i64], date: NaiveDate) -> Result<Roster, GstaldergeistError> {
+    Ok(roster_at(crate::database::load_roster()?, flatmates, date))
+}
+
+/// Load the persisted roster, advance it to the week of `now`, apply `update`
+/// and save it again, all in one transaction.
+pub fn update_roster<T>(
+    flatmates: &[i64],
+    now: chrono::DateTime<chrono_tz::Tz>,
+    update: impl FnOnce(&mut Roster) -> T,
+) -> Result<T, GstaldergeistError> {
+    crate::database::update_roster(|stored| {
+        let mut roster = roster_at(stored, flatmates, now.date_naive());
+        let result = update(&mut roster);
+        (roster, result)
+    })
+}
+
+/// Flatmates away on any day between `from` and `until`, with the end of
+/// their absence if known.
+pub fn flatmates_away(
+    flatmates: &[i64],
+    from: NaiveDate,
+    until: NaiveDate,
+) -> Result<Vec<(i64, Option<NaiveDate>)>, GstaldergeistError> {
+    Ok(load_roster_at(flatmates, from)?.away_between(from, until))
+}
+
+/// The flatmate on trash duty in the week of `date`.
+pub fn flatmate_on_duty(flatmates: &[i64], date: NaiveDate) -> Result<i64, GstaldergeistError> {
+    load_roster_at(flatmates, date)?
+        .on_duty()
+        .ok_or_else(|| GstaldergeistError::ConfigError("No flatmates configured".to_string()))
+}
+
+/// Who will be on duty in the week of each of `dates`, as seen from `today`.
+pub fn duty_forecast(
+    flatmates: &[i64],
+    today: NaiveDate,
+    dates: &[NaiveDate],
+) -> Result<Vec<Option<i64>>, GstaldergeistError> {
+    let roster = load_roster_at(flatmates, today)?;
+    Ok(dates.iter().map(|date| roster.on_duty_in(*date)).collect())
+}
+
+/// The next available flatmate after `chat_id` in the week of `date`.
+pub fn next_in_rotation(
+    flatmates: &[i64],
+    date: NaiveDate,
+    chat_id: i64,
+) -> Result<Option<i64>, GstaldergeistError> {
+    Ok(load_roster_at(flatmates, date)?.next_after(chat_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn ids(roster: &Roster) -> Vec<i64> {
+        roster.members.iter().map(|member| member.chat_id).collect()
+    }
+
+    #[test]
+    fn seeds_the_flatmate_the_iso_week_formula_picked() {
+        // 2024-01-08 is ISO week 2 -> index (1 + 1) % 3 == 2.
+        let roster = Roster::new(&[10, 20, 30], date(2024, 1, 10));
+        assert_eq!(roster.on_duty(), Some(30));
+        assert_eq!(roster.week, date(2024, 1, 8));
+    }
+
+    #[test]
+    fn advances_once_per_week_boundary() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        assert_eq!(roster.on_duty(), Some(20));
+
+        roster.advance_to(date(2024, 1, 7));
+        assert_eq!(roster.on_duty(), Some(20));
+
+        roster.advance_to(date(2024, 1, 8));
+        assert_eq!(roster.on_duty(), Some(30));
+
+        roster.advance_to(date(2024, 1, 22));
+        assert_eq!(roster.on_duty(), Some(20));
+        assert_eq!(roster.week, date(2024, 1, 22));
+    }
+
+    #[test]
+    fn advancing_to_an_earlier_week_changes_nothing() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 8));
+        let before = roster.clone();
+        roster.advance_to(date(2024, 1, 1));
+        assert_eq!(roster, before);
+    }
+
+    #[test]
+    fn years_with_53_iso_weeks_keep_the_rotation_fair() {
+        // 2026 has 53 ISO weeks: the ISO-week formula would give week 53 and
+        // week 1 of 2027 to the same flatmate with two flatmates.
+        let mut roster = Roster::new(&[10, 20], date(2026, 12, 21));
+        let mut on_duty = vec![roster.on_duty().unwrap()];
+        for monday in [date(2026, 12, 28), date(2027, 1, 4), date(2027, 1, 11)] {
+            roster.advance_to(monday);
+            on_duty.push(roster.on_duty().unwrap());
+        }
+        assert_eq!(on_duty, vec![10, 20, 10, 20]);
+    }
+
+    #[test]
+    fn inserting_before_the_current_flatmate_keeps_them_on_duty() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        roster.insert(40, 0);
+        assert_eq!(ids(&roster), vec![40, 10, 20, 30]);
+        assert_eq!(roster.on_duty(), Some(20));
+
+        roster.advance_to(date(2024, 1, 8));
+        assert_eq!(roster.on_duty(), Some(30));
+    }
+
+    #[test]
+    fn inserting_after_the_current_flatmate_takes_the_next_turn() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        roster.insert(40, 2);
+        assert_eq!(roster.on_duty(), Some(20));
+        roster.advance_to(date(2024, 1, 8));
+        assert_eq!(roster.on_duty(), Some(40));
+    }
+
+    #[test]
+    fn inserting_twice_is_ignored() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        roster.insert(10, 3);
+        assert_eq!(ids(&roster), vec![10, 20, 30]);
+    }
+
+    #[test]
+    fn removing_someone_else_keeps_the_current_flatmate_on_duty() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        roster.remove(10);
+        assert_eq!(roster.on_duty(), Some(20));
+        roster.remove(30);
+        assert_eq!(roster.on_duty(), Some(20));
+    }
+
+    #[test]
+    fn removing_the_current_flatmate_hands_over_to_the_next() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        roster.remove(20);
+        assert_eq!(roster.on_duty(), Some(30));
+        roster.remove(30);
+        assert_eq!(roster.on_duty(), Some(10));
+        roster.remove(10);
+        assert_eq!(roster.on_duty(), None);
+    }
+
+    #[test]
+    fn away_flatmates_are_skipped_and_get_their_turn_back() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        assert!(roster.set_away(20, true));
+        assert_eq!(roster.on_duty(), Some(30));
+
+        // 30 covered for 20 and is on turn again: 20 is still away, so 30
+        // does a second week.
+        roster.advance_to(date(2024, 1, 8));
+        assert_eq!(roster.on_duty(), Some(30));
+
+        assert!(roster.set_away(20, false));
+        roster.advance_to(date(2024, 1, 15));
+        assert_eq!(roster.on_duty(), Some(10));
+
+        // 20 pays back the week 30 covered when 30's turn comes around.
+        roster.advance_to(date(2024, 1, 22));
+        assert_eq!(roster.on_duty(), Some(20));
+        roster.advance_to(date(2024, 1, 29));
+        assert_eq!(roster.on_duty(), Some(20));
+        assert!(roster.debts.is_empty());
+    }
+
+    #[test]
+    fn everyone_away_keeps_the_current_flatmate() {
+        let mut roster = Roster::new(&[10, 20], date(2024, 1, 1));
+        roster.set_away(10, true);
+        roster.set_away(20, true);
+        assert_eq!(roster.on_duty(), Some(20));
+    }
+
+    #[test]
+    fn set_away_for_unknown_flatmate_fails() {
+        let mut roster = Roster::new(&[10], date(2024, 1, 1));
+        assert!(!roster.set_away(99, true));
+    }
+
+    #[test]
+    fn sync_appends_new_and_drops_missing_flatmates() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        roster.sync(&[30, 20, 40]);
+        assert_eq!(ids(&roster), vec![20, 30, 40]);
+        assert_eq!(roster.on_duty(), Some(20));
+    }
+
+    #[test]
+    fn swap_puts_the_volunteer_on_duty_for_one_week() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        assert!(roster.swap(30));
+        assert_eq!(roster.on_duty(), Some(30));
+        assert_eq!(
+            roster.debts,
+            vec![Debt {
+                debtor: 20,
+                creditor: 30
+            }]
+        );
+
+        // Next week the rotation would pick 30, who is owed a week: 20 pays
+        // it back and the debt is settled.
+        roster.advance_to(date(2024, 1, 8));
+        assert_eq!(roster.on_duty(), Some(20));
+        assert!(roster.debts.is_empty());
+
+        // The rotation itself carries on as if nothing happened.
+        roster.advance_to(date(2024, 1, 15));
+        assert_eq!(roster.on_duty(), Some(10));
+    }
+
+    #[test]
+    fn debt_waits_until_the_creditor_is_scheduled() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        assert!(roster.swap(10));
+        roster.advance_to(date(2024, 1, 8));
+        assert_eq!(roster.on_duty(), Some(30));
+        assert_eq!(roster.debts.len(), 1);
+
+        roster.advance_to(date(2024, 1, 15));
+        assert_eq!(roster.on_duty(), Some(20));
+        assert!(roster.debts.is_empty());
+    }
+
+    #[test]
+    fn away_debtor_pays_back_later() {
+        let mut roster = Roster::new(&[10, 20], date(2024, 1, 1));
+        assert!(roster.swap(10));
+        roster.set_away(20, true);
+        roster.advance_to(date(2024, 1, 8));
+        assert_eq!(roster.on_duty(), Some(10));
+        assert_eq!(roster.debts.len(), 1);
+    }
+
+    #[test]
+    fn cannot_swap_with_yourself_or_a_stranger() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        assert!(!roster.swap(20));
+        assert!(!roster.swap(99));
+        assert_eq!(roster.substitute, None);
+        assert!(roster.debts.is_empty());
+    }
+
+    #[test]
+    fn removing_a_flatmate_drops_their_debts() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        assert!(roster.swap(30));
+        roster.remove(30);
+        assert_eq!(roster.on_duty(), Some(20));
+        assert!(roster.debts.is_empty());
+    }
+
+    fn absence(chat_id: i64, from: NaiveDate, until: NaiveDate) -> Absence {
+        Absence {
+            chat_id,
+            from,
+            until,
+        }
+    }
+
+    #[test]
+    fn planned_absence_skips_the_flatmate_only_during_their_holiday() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        // 30 is on turn the week of 2024-01-08 but away from Friday.
+        assert!(roster.add_absence(absence(30, date(2024, 1, 12), date(2024, 1, 16))));
+        assert_eq!(roster.on_duty(), Some(20));
+
+        roster.advance_to(date(2024, 1, 8));
+        assert_eq!(roster.on_duty(), Some(10));
+        assert_eq!(
+            roster.debts,
+            vec![Debt {
+                debtor: 30,
+                creditor: 10
+            }]
+        );
+
+        // The week of 2024-01-15 is 10's turn; 30 is still away on Monday, so
+        // the debt waits.
+        roster.advance_to(date(2024, 1, 15));
+        assert_eq!(roster.on_duty(), Some(10));
+
+        // Back home, 30 does their own turn and pays 10 back on 10's next one.
+        roster.advance_to(date(2024, 1, 22));
+        assert_eq!(roster.on_duty(), Some(20));
+        roster.advance_to(date(2024, 1, 29));
+        assert_eq!(roster.on_duty(), Some(30));
+        roster.advance_to(date(2024, 2, 5));
+        assert_eq!(roster.on_duty(), Some(30));
+        assert!(roster.debts.is_empty());
+    }
+
+    #[test]
+    fn absence_of_the_flatmate_on_duty_is_covered_immediately() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        assert!(roster.add_absence(absence(20, date(2024, 1, 3), date(2024, 1, 4))));
+        assert_eq!(roster.on_duty(), Some(30));
+    }
+
+    #[test]
+    fn absence_of_a_stranger_is_rejected() {
+        let mut roster = Roster::new(&[10], date(2024, 1, 1));
+        assert!(!roster.add_absence(absence(99, date(2024, 1, 3), date(2024, 1, 4))));
+        assert!(roster.absences.is_empty());
+    }
+
+    #[test]
+    fn past_absences_are_forgotten() {
+        let mut roster = Roster::new(&[10, 20], date(2024, 1, 1));
+        roster.add_absence(absence(10, date(2024, 1, 1), date(2024, 1, 2)));
+        roster.add_absence(absence(10, date(2024, 2, 1), date(2024, 2, 2)));
+        roster.advance_to(date(2024, 1, 8));
+        assert_eq!(
+            roster.absences,
+            vec![absence(10, date(2024, 2, 1), date(2024, 2, 2))]
+        );
+    }
+
+    #[test]
+    fn ending_absences_early_keeps_future_ones() {
+        let mut roster = Roster::new(&[10, 20], date(2024, 1, 1));
+        roster.add_absence(absence(10, date(2024, 1, 1), date(2024, 1, 10)));
+        roster.add_absence(absence(10, date(2024, 1, 5), date(2024, 1, 6)));
+        roster.add_absence(absence(10, date(2024, 2, 1), date(2024, 2, 2)));
+        roster.end_absences(10, date(2024, 1, 5));
+        assert_eq!(
+            roster.absences,
+            vec![
+                absence(10, date(2024, 1, 1), date(2024, 1, 4)),
+                absence(10, date(2024, 2, 1), date(2024, 2, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn forecast_follows_absences_without_changing_the_roster() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        roster.add_absence(absence(30, date(2024, 1, 12), date(2024, 1, 16)));
+        let before = roster.clone();
+        assert_eq!(roster.on_duty_in(date(2024, 1, 3)), Some(20));
+        assert_eq!(roster.on_duty_in(date(2024, 1, 9)), Some(10));
+        assert_eq!(roster.on_duty_in(date(2024, 1, 22)), Some(20));
+        assert_eq!(roster, before);
+    }
+
+    #[test]
+    fn next_in_rotation_skips_away_flatmates() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        assert_eq!(roster.next_after(20), Some(30));
+        assert_eq!(roster.next_after(30), Some(10));
+        roster.set_away(30, true);
+        assert_eq!(roster.next_after(20), Some(10));
+        assert_eq!(roster.next_after(99), None);
+    }
+
+    #[test]
+    fn lists_who_is_away_in_a_period() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        roster.set_away(10, true);
+        roster.add_absence(absence(30, date(2024, 1, 5), date(2024, 1, 9)));
+        roster.add_absence(absence(30, date(2024, 1, 9), date(2024, 1, 20)));
+        assert_eq!(
+            roster.away_between(date(2024, 1, 1), date(2024, 1, 7)),
+            vec![(10, None), (30, Some(date(2024, 1, 9)))]
+        );
+        assert_eq!(
+            roster.away_between(date(2024, 1, 8), date(2024, 1, 14)),
+            vec![(10, None), (30, Some(date(2024, 1, 20)))]
+        );
+        assert_eq!(
+            roster.away_between(date(2024, 1, 21), date(2024, 1, 27)),
+            vec![(10, None)]
+        );
+    }
+
+    #[test]
+    fn only_a_week_handed_on_for_an_absence_is_an_absence_cover() {
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        assert_eq!(roster.absence_cover(), None);
+        roster.swap(30);
+        assert_eq!(roster.absence_cover(), None);
+
+        let mut roster = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        roster.add_absence(absence(30, date(2024, 1, 8), date(2024, 1, 14)));
+        roster.advance_to(date(2024, 1, 8));
+        assert_eq!(roster.absence_cover(), Some(10));
+        // Paying the week back is no cover.
+        roster.advance_to(date(2024, 1, 15));
+        assert_eq!(roster.on_duty(), Some(30));
+        assert_eq!(roster.absence_cover(), None);
+    }
+
+    #[test]
+    fn the_week_looked_at_decides_who_is_on_duty() {
+        let stored = Roster::new(&[10, 20, 30], date(2024, 1, 1));
+        let sunday = roster_at(Some(stored.clone()), &[10, 20, 30], date(2024, 1, 7));
+        let monday = roster_at(Some(stored.clone()), &[10, 20, 30], date(2024, 1, 8));
+        assert_eq!(sunday.on_duty(), Some(20));
+        assert_eq!(monday.on_duty(), Some(30));
+        // Whichever was looked at first, the week itself decides.
+        assert_eq!(
+            roster_at(Some(stored), &[10, 20, 30], date(2024, 1, 7)),
+            sunday
+        );
+    }
+}
@@ -0,0 +1,527 @@
+use crate::clock::Clock;
+use crate::error::GstaldergeistError;
+use chrono::{Datelike, Timelike};
+
+/// How far ahead to look for the next run of a cron expression. Eight years
+/// always include a February 29th.
+const MAX_SEARCH_DAYS: i64 = 366 * 8;
+
+/// A five-field cron expression, "minute hour day-of-month month day-of-week",
+/// in local time. Each field takes `*`, numbers, ranges (`1-5`), steps (`*/15`,
+/// `8-18/2`) and comma-separated lists of those. Day of week 0 and 7 both mean
+/// Sunday. As in classic cron, if both day fields are restricted a day
+/// matching either of them matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl Cron {
+    pub fn parse(expression: &str) -> Result<Self, GstaldergeistError> {
+        let invalid = |reason: String| {
+            GstaldergeistError::ConfigError(format!(
+                "Invalid cron expression '{}': {}",
+                expression, reason
+            ))
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(invalid(format!("expected 5 fields, got {}", fields.len())));
+        };
+        let mut weekday_bits = parse_field(weekdays, 0, 7).map_err(&invalid)?;
+        // 7 is another name for Sunday.
+        if weekday_bits & (1 << 7) != 0 {
+            weekday_bits = (weekday_bits | 1) & !(1 << 7);
+        }
+        Ok(Cron {
+            minutes: parse_field(minutes, 0, 59).map_err(&invalid)?,
+            hours: parse_field(hours, 0, 23).map_err(&invalid)? as u32,
+            days: parse_field(days, 1, 31).map_err(&invalid)? as u32,
+            months: parse_field(months, 1, 12).map_err(&invalid)? as u16,
+            weekdays: weekday_bits as u8,
+            days_restricted: days != "*",
+            weekdays_restricted: weekdays != "*",
+        })
+    }
+
+    fn matches_date(&self, date: chrono::NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+
+    /// The first time strictly after `after` matching the expression, with
+    /// daylight saving changes handled as `clock::local_time` does: a time
+    /// the clocks skip runs just after the gap. `None` if the expression
+    /// never matches, e.g. for February 30th.
+    pub fn next_after(
+        &self,
+        after: chrono::DateTime<chrono_tz::Tz>,
+    ) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        let start =
+            after.naive_local().with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        for offset in 0..MAX_SEARCH_DAYS {
+            let date = start.date() + chrono::Duration::days(offset);
+            if !self.matches_date(date) {
+                continue;
+            }
+            // Times moved past a gap may overtake later ones, so the day's
+            // earliest run counts rather than the first one found.
+            let run = (0..24)
+                .filter(|hour| self.hours & (1 << hour) != 0)
+                .flat_map(|hour| {
+                    (0..60)
+                        .filter(|minute| self.minutes & (1 << minute) != 0)
+                        .filter_map(move |minute| date.and_hms_opt(hour, minute, 0))
+                })
+                .filter(|naive| *naive >= start)
+                .map(|naive| crate::clock::local_time(after.timezone(), naive))
+                .filter(|run| *run > after)
+                .min();
+            if run.is_some() {
+                return run;
+            }
+        }
+        None
+    }
+}
+
+/// Bit set of the values a single cron field allows, bit `n` for value `n`.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step in '{}'", part))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let value = |raw: &str| {
+            raw.parse::<u32>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| format!("'{}' is not between {} and {}", raw, min, max))
+        };
+        let (from, to) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((from, to)) => (value(from)?, value(to)?),
+            // "5/10" runs from 5 to the end of the range.
+            None if step > 1 => (value(range)?, max),
+            None => (value(range)?, value(range)?),
+        };
+        if from > to {
+            return Err(format!("empty range '{}'", range));
+        }
+        for value in (from..=to).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+/// A registered job as stored in the database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobRecord {
+    pub name: String,
+    /// Cron expression the job runs on.
+    pub schedule: String,
+    /// `None` once the schedule never matches again.
+    pub next_run: Option<chrono::DateTime<chrono_tz::Tz>>,
+    pub last_run: Option<chrono::DateTime<chrono_tz::Tz>>,
+}
+
+struct Job {
+    record: JobRecord,
+    cron: Cron,
+    /// How late a missed run may still be caught up.
+    catch_up: chrono::Duration,
+}
+
+/// A job whose run has come, with the time it was scheduled for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DueJob {
+    pub name: String,
+    pub scheduled: chrono::DateTime<chrono_tz::Tz>,
+}
+
+/// Keeps track of when each registered job runs next. The scheduler does not
+/// run anything itself: the caller asks it which jobs are due.
+pub struct Scheduler {
+    clock: std::sync::Arc<dyn Clock>,
+    jobs: Vec<Job>,
+    /// Jobs saved before the last shutdown, not registered again yet.
+    saved: Vec<JobRecord>,
+}
+
+impl Scheduler {
+    pub fn new(clock: std::sync::Arc<dyn Clock>, saved: Vec<JobRecord>) -> Self {
+        Scheduler {
+            clock,
+            jobs: Vec::new(),
+            saved,
+        }
+    }
+
+    /// A scheduler that remembers the jobs saved before the last shutdown.
+    pub fn restore(clock: std::sync::Arc<dyn Clock>) -> Self {
+        let saved = match crate::database::load_jobs(clock.now().timezone()) {
+            Ok(saved) => saved,
+            Err(e) => {
+                tracing::error!("Failed to restore scheduled jobs, starting fresh: {}", e);
+                Vec::new()
+            }
+        };
+        Scheduler::new(clock, saved)
+    }
+
+    /// Register a job running on the cron expression `schedule`. A job saved
+    /// with the same name and schedule keeps its next run, so a run missed
+    /// while the bot was down is caught up, if it is at most `catch_up` late.
+    /// Jobs due at the same time run in registration order.
+    pub fn register(
+        &mut self,
+        name: &str,
+        schedule: &str,
+        catch_up: chrono::Duration,
+    ) -> Result<(), GstaldergeistError> {
+        if self.jobs.iter().any(|job| job.record.name == name) {
+            return Err(GstaldergeistError::ConfigError(format!(
+                "The job '{}' is registered twice",
+                name
+            )));
+        }
+        let cron = Cron::parse(schedule)?;
+        let saved = self
+            .saved
+            .iter()
+            .position(|record| record.name == name && record.schedule == schedule);
+        let record = match saved {
+            Some(index) => self.saved.remove(index),
+            None => JobRecord {
+                name: name.to_string(),
+                schedule: schedule.to_string(),
+                next_run: cron.next_after(self.clock.now()),
+                last_run: None,
+            },
+        };
+        self.jobs.push(Job {
+            record,
+            cron,
+            catch_up,
+        });
+        Ok(())
+    }
+
+    /// When the earliest job runs next.
+    pub fn next_run(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        self.jobs.iter().filter_map(|job| job.record.next_run).min()
+    }
+
+    /// The jobs whose run has come, oldest first, each moved on to its next
+    /// run. However many runs of a job were missed, it is due at most once;
+    /// runs later than the job's catch-up window are skipped.
+    pub fn take_due(&mut self) -> Vec<DueJob> {
+        let now = self.clock.now();
+        let mut due = Vec::new();
+        for job in &mut self.jobs {
+            let Some(scheduled) = job.record.next_run else {
+                continue;
+            };
+            if scheduled > now {
+                continue;
+            }
+            job.record.next_run = job.cron.next_after(now);
+            if now - scheduled > job.catch_up {
+                tracing::warn!(
+                    "Skipping job {} scheduled at {}: too late to catch up",
+                    job.record.name,
+                    scheduled
+                );
+                continue;
+            }
+            job.record.last_run = Some(now);
+            due.push(DueJob {
+                name: job.record.name.clone(),
+                scheduled,
+            });
+        }
+        // The sort is stable, so ties keep the registration order.
+        due.sort_by_key(|job| job.scheduled);
+        due
+    }
+
+    pub fn records(&self) -> Vec<JobRecord> {
+        self.jobs.iter().map(|job| job.record.clone()).collect()
+    }
+
+    /// Save the registered jobs, dropping saved jobs that are not registered
+    /// anymore. A failed write is logged rather than propagated: at worst a
+    /// run missed during the next downtime is not caught up.
+    pub fn persist(&self) {
+        if let Err(e) = crate::database::save_jobs(&self.records()) {
+            tracing::error!("Failed to persist scheduled jobs: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FakeClock;
+    use chrono::TimeZone;
+
+    fn local(
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        min: u32,
+    ) -> chrono::DateTime<chrono_tz::Tz> {
+        chrono_tz::Europe::Zurich
+            .with_ymd_and_hms(year, month, day, hour, min, 0)
+            .single()
+            .expect("unambiguous local time")
+    }
+
+    fn next(
+        expression: &str,
+        after: chrono::DateTime<chrono_tz::Tz>,
+    ) -> chrono::DateTime<chrono_tz::Tz> {
+        Cron::parse(expression).unwrap().next_after(after).unwrap()
+    }
+
+    #[test]
+    fn daily_job_runs_later_today_or_tomorrow() {
+        assert_eq!(
+            next("0 16 * * *", local(2026, 6, 7, 9, 30)),
+            local(2026, 6, 7, 16, 0)
+        );
+        assert_eq!(
+            next("0 16 * * *", local(2026, 6, 7, 16, 0)),
+            local(2026, 6, 8, 16, 0)
+        );
+        assert_eq!(
+            next("0 16 * * *", local(2026, 12, 31, 20, 0)),
+            local(2027, 1, 1, 16, 0)
+        );
+    }
+
+    #[test]
+    fn weekly_job_runs_on_its_weekday() {
+        // 2026-06-07 is a Sunday.
+        assert_eq!(
+            next("0 16 * * 0", local(2026, 6, 8, 9, 0)),
+            local(2026, 6, 14, 16, 0)
+        );
+        assert_eq!(
+            next("0 16 * * 7", local(2026, 6, 7, 9, 0)),
+            local(2026, 6, 7, 16, 0)
+        );
+        assert_eq!(
+            next("30 8 * * 1-5", local(2026, 6, 6, 9, 0)),
+            local(2026, 6, 8, 8, 30)
+        );
+    }
+
+    #[test]
+    fn follows_daylight_saving_changes() {
+        use chrono::Utc;
+        // Zurich moves its clocks forward on 2026-03-29 and back on 2026-10-25.
+        let spring = next("0 16 * * *", local(2026, 3, 28, 17, 0));
+        assert_eq!(spring, local(2026, 3, 29, 16, 0));
+        assert_eq!(
+            spring.with_timezone(&Utc),
+            Utc.with_ymd_and_hms(2026, 3, 29, 14, 0, 0).unwrap()
+        );
+        // 02:30 does not exist that night, so the job runs just after the gap.
+        assert_eq!(
+            next("30 2 * * *", local(2026, 3, 28, 17, 0)),
+            local(2026, 3, 29, 3, 30)
+        );
+
+        // 02:30 happens twice in the autumn; only the first one counts.
+        let first = next("30 2 * * *", local(2026, 10, 24, 17, 0));
+        assert_eq!(
+            first.with_timezone(&Utc),
+            Utc.with_ymd_and_hms(2026, 10, 25, 0, 30, 0).unwrap()
+        );
+        assert_eq!(
+            next("30 2 * * *", first).with_timezone(&Utc),
+            Utc.with_ymd_and_hms(2026, 10, 26, 1, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn a_job_in_the_spring_gap_runs_that_day() {
+        // 02:00 to 03:00 is skipped in Zurich on 2026-03-29.
+        let run = next("0 2 * * *", local(2026, 3, 28, 17, 0));
+        assert_eq!(run, local(2026, 3, 29, 3, 0));
+        assert_eq!(
+            run,
+            crate::at_hour(chrono_tz::Europe::Zurich, run.date_naive(), 2)
+        );
+        assert_eq!(next("0 2 * * *", run), local(2026, 3, 30, 2, 0));
+        // Moved past the gap, 02:30 comes after 03:00 rather than before.
+        let first = next("0,30 2-3 * * *", local(2026, 3, 28, 17, 0));
+        assert_eq!(first, local(2026, 3, 29, 3, 0));
+        assert_eq!(next("0,30 2-3 * * *", first), local(2026, 3, 29, 3, 30));
+    }
+
+    #[test]
+    fn runs_at_the_configured_zone_not_the_hosts() {
+        let utc_evening = chrono_tz::UTC
+            .with_ymd_and_hms(2026, 6, 7, 15, 0, 0)
+            .unwrap();
+        let zurich = utc_evening.with_timezone(&chrono_tz::Europe::Zurich);
+        assert_eq!(next("0 16 * * *", utc_evening), local(2026, 6, 7, 18, 0));
+        assert_eq!(next("0 16 * * *", zurich), local(2026, 6, 8, 16, 0));
+    }
+
+    #[test]
+    fn lists_ranges_and_steps() {
+        let after = local(2026, 6, 7, 16, 20);
+        assert_eq!(next("*/15 * * * *", after), local(2026, 6, 7, 16, 30));
+        assert_eq!(next("0 18,20-22 * * *", after), local(2026, 6, 7, 18, 0));
+        assert_eq!(next("0 8-18/4 * * *", after), local(2026, 6, 8, 8, 0));
+        assert_eq!(next("5/20 * * * *", after), local(2026, 6, 7, 16, 25));
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either() {
+        // The 1st of the month or any Monday, whichever comes first.
+        assert_eq!(
+            next("0 9 1 * 1", local(2026, 6, 2, 0, 0)),
+            local(2026, 6, 8, 9, 0)
+        );
+        assert_eq!(
+            next("0 9 1 * 1", local(2026, 6, 29, 10, 0)),
+            local(2026, 7, 1, 9, 0)
+        );
+    }
+
+    #[test]
+    fn leap_day_is_found_years_ahead() {
+        assert_eq!(
+            next("0 0 29 2 *", local(2026, 6, 7, 0, 0)),
+            local(2028, 2, 29, 0, 0)
+        );
+        assert_eq!(
+            Cron::parse("0 0 30 2 *")
+                .unwrap()
+                .next_after(local(2026, 6, 7, 0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for expression in [
+            "",
+            "0 16 * *",
+            "60 * * * *",
+            "0 24 * * *",
+            "0 0 0 * *",
+            "0 0 * 13 *",
+            "0 0 * * 8",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            let err = Cron::parse(expression).unwrap_err();
+            assert!(
+                matches!(err, GstaldergeistError::ConfigError(_)),
+                "'{}' should be rejected",
+                expression
+            );
+        }
+    }
+
+    fn scheduler(clock: &std::sync::Arc<FakeClock>, saved: Vec<JobRecord>) -> Scheduler {
+        let clock: std::sync::Arc<dyn Clock> = clock.clone();
+        let mut scheduler = Scheduler::new(clock, saved);
+        scheduler
+            .register("reminder", "0 16 * * *", chrono::Duration::hours(6))
+            .unwrap();
+        scheduler
+            .register("check", "0 19 * * *", chrono::Duration::minutes(15))
+            .unwrap();
+        scheduler
+    }
+
+    fn names(due: &[DueJob]) -> Vec<&str> {
+        due.iter().map(|job| job.name.as_str()).collect()
+    }
+
+    #[test]
+    fn jobs_become_due_once_at_their_time() {
+        let clock = std::sync::Arc::new(FakeClock::new(local(2026, 6, 7, 9, 0)));
+        let mut scheduler = scheduler(&clock, Vec::new());
+        assert!(scheduler.take_due().is_empty());
+        assert_eq!(scheduler.next_run(), Some(local(2026, 6, 7, 16, 0)));
+
+        clock.set(local(2026, 6, 7, 16, 0));
+        assert_eq!(names(&scheduler.take_due()), vec!["reminder"]);
+        assert!(scheduler.take_due().is_empty());
+        assert_eq!(scheduler.next_run(), Some(local(2026, 6, 7, 19, 0)));
+    }
+
+    #[test]
+    fn missed_runs_are_caught_up_once_within_their_window() {
+        let clock = std::sync::Arc::new(FakeClock::new(local(2026, 6, 7, 9, 0)));
+        let saved = scheduler(&clock, Vec::new()).records();
+
+        // Down from before 16:00 until 19:10.
+        clock.set(local(2026, 6, 7, 19, 10));
+        let mut restarted = scheduler(&clock, saved);
+        let due = restarted.take_due();
+        assert_eq!(names(&due), vec!["reminder", "check"]);
+        assert_eq!(due[0].scheduled, local(2026, 6, 7, 16, 0));
+
+        // Down for two days: both runs are too late to catch up.
+        clock.set(local(2026, 6, 9, 19, 30));
+        assert!(restarted.take_due().is_empty());
+        assert_eq!(restarted.next_run(), Some(local(2026, 6, 10, 16, 0)));
+    }
+
+    #[test]
+    fn changed_schedule_starts_over() {
+        let clock = std::sync::Arc::new(FakeClock::new(local(2026, 6, 7, 9, 0)));
+        let saved = scheduler(&clock, Vec::new()).records();
+        clock.set(local(2026, 6, 7, 17, 0));
+        let mut restarted = Scheduler::new(clock.clone(), saved);
+        restarted
+            .register("reminder", "0 17 * * *", chrono::Duration::hours(6))
+            .unwrap();
+        assert!(restarted.take_due().is_empty());
+        assert_eq!(restarted.next_run(), Some(local(2026, 6, 8, 17, 0)));
+    }
+
+    #[test]
+    fn registering_a_job_twice_fails() {
+        let clock = std::sync::Arc::new(FakeClock::new(local(2026, 6, 7, 9, 0)));
+        let mut scheduler = scheduler(&clock, Vec::new());
+        assert!(
+            scheduler
+                .register("reminder", "0 8 * * *", chrono::Duration::zero())
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use crate::data_grabber::TrashType;
+
+/// Chore name used in the duty log for the trash duty.
+pub const TRASH_CHORE: &str = "trash";
+
+/// How a duty ended for the flatmate it was assigned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DutyOutcome {
+    /// Pressed Done.
+    Done,
+    /// Pressed I can't.
+    Cant,
+    /// Never answered the reminder.
+    Missed,
+    /// Took over someone else's duty.
+    Covered,
+}
+
+impl rusqlite::types::FromSql for DutyOutcome {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        match value.as_i64()? {
+            0 => Ok(DutyOutcome::Done),
+            1 => Ok(DutyOutcome::Cant),
+            2 => Ok(DutyOutcome::Missed),
+            3 => Ok(DutyOutcome::Covered),
+            _ => Err(rusqlite::types::FromSqlError::InvalidType),
+        }
+    }
+}
+
+impl rusqlite::types::ToSql for DutyOutcome {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(rusqlite::types::ToSqlOutput::Owned(
+            rusqlite::types::Value::Integer(match self {
+                DutyOutcome::Done => 0,
+                DutyOutcome::Cant => 1,
+                DutyOutcome::Missed => 2,
+                DutyOutcome::Covered => 3,
+            }),
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DutyLogEntry {
+    pub timestamp: chrono::DateTime<chrono_tz::Tz>,
+    pub chat_id: i64,
+    pub chore: String,
+    /// Trash types collected, e.g. "Bio,Paper". Empty for other chores.
+    pub trashes: String,
+    pub outcome: DutyOutcome,
+}
+
+/// Record the outcome of a duty in the duty log. A failed write is logged
+/// rather than propagated: the duty itself matters more than its statistics.
+pub fn record(
+    now: chrono::DateTime<chrono_tz::Tz>,
+    chat_id: i64,
+    chore: &str,
+    trashes: &[TrashType],
+    outcome: DutyOutcome,
+) {
+    let entry = DutyLogEntry {
+        timestamp: now,
+        chat_id,
+        chore: chore.to_string(),
+        trashes: trashes
+            .iter()
+            .map(|trash| trash.to_string())
+            .collect::<Vec<_>>()
+            .join(","),
+        outcome,
+    };
+    if let Err(e) = crate::database::insert_duty_log(&entry) {
+        tracing::error!("Failed to record {:?} in the duty log: {}", outcome, e);
+    }
+}
+
+/// Trash types collected the day after `today` according to the stored
+/// schedule.
+pub fn tomorrow_trashes(today: chrono::NaiveDate) -> Vec<TrashType> {
+    let tomorrow = today + chrono::Duration::days(1);
+    match crate::database::get_all_trashes() {
+        Ok(mut trashes) => trashes.remove(&tomorrow).unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("Failed to load tomorrow's trashes: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlatmateStats {
+    pub chat_id: i64,
+    pub done: u32,
+    pub cant: u32,
+    pub missed: u32,
+    pub covered: u32,
+    /// Duties done in a row up to now.
+    pub current_streak: u32,
+    pub best_streak: u32,
+}
+
+impl FlatmateStats {
+    /// Share of their own duties the flatmate did, or `None` without any duty
+    /// yet. Covering for others is counted separately.
+    pub fn completion_rate(&self) -> Option<f64> {
+        let assigned = self.done + self.cant + self.missed;
+        if assigned == 0 {
+            return None;
+        }
+        Some(self.done as f64 / assigned as f64)
+    }
+}
+
+/// Statistics of every flatmate in `flatmates`, in that order, from duty log
+/// entries sorted oldest first. A Done extends the streak, an I can't or a
+/// missed duty breaks it; covering for someone else leaves it untouched.
+pub fn compute(flatmates: &[i64], entries: &[DutyLogEntry]) -> Vec<FlatmateStats> {
+    flatmates
+        .iter()
+        .map(|&chat_id| {
+            let mut stats = FlatmateStats {
+                chat_id,
+                ..Default::default()
+            };
+            for entry in entries.iter().filter(|entry| entry.chat_id == chat_id) {
+                match entry.outcome {
+                    DutyOutcome::Done => {
+                        stats.done += 1;
+                        stats.current_streak += 1;
+                        stats.best_streak = stats.best_streak.max(stats.current_streak);
+                    }
+                    DutyOutcome::Cant => {
+                        stats.cant += 1;
+                        stats.current_streak = 0;
+                    }
+                    DutyOutcome::Missed => {
+                        stats.missed += 1;
+                        stats.current_streak = 0;
+                    }
+                    DutyOutcome::Covered => stats.covered += 1,
+                }
+            }
+            stats
+        })
+        .collect()
+}
+
+/// One line per flatmate, e.g.
+/// "Alice: 6/8 done (75%), covered 2x, streak 3 (best 5)".
+pub fn format_stats(stats: &[(String, FlatmateStats)]) -> String {
+    let mut txt = String::from("Duty statistics:\n");
+    for (name, stats) in stats {
+        let assigned = stats.done + stats.cant + stats.missed;
+        let rate = match stats.completion_rate() {
+            Some(rate) => format!("{:.0}%", rate * 100.0),
+            None => "-".to_string(),
+        };
+        txt.push_str(&format!(
+            "{}: {}/{} done ({}), covered {}x, streak {} (best {})\n",
+            name,
+            stats.done,
+            assigned,
+            rate,
+            stats.covered,
+            stats.current_streak,
+            stats.best_streak
+        ));
+    }
+    txt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(chat_id: i64, outcome: DutyOutcome) -> DutyLogEntry {
+        DutyLogEntry {
+            timestamp: chrono::Utc::now().with_timezone(&chrono_tz::Europe::Zurich),
+            chat_id,
+            chore: TRASH_CHORE.to_string(),
+            trashes: String::new(),
+            outcome,
+        }
+    }
+
+    #[test]
+    fn counts_outcomes_per_flatmate() {
+        use DutyOutcome::*;
+        let entries = vec![
+            entry(10, Done),
+            entry(20, Cant),
+            entry(10, Covered),
+            entry(20, Missed),
+            entry(10, Done),
+        ];
+        let stats = compute(&[10, 20], &entries);
+        assert_eq!(
+            stats[0],
+            FlatmateStats {
+                chat_id: 10,
+                done: 2,
+                covered: 1,
+                current_streak: 2,
+                best_streak: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            stats[1],
+            FlatmateStats {
+                chat_id: 20,
+                cant: 1,
+                missed: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn streaks_break_on_cant_and_missed_but_not_on_covering() {
+        use DutyOutcome::*;
+        let entries: Vec<_> = [Done, Done, Done, Missed, Done, Covered, Done, Cant, Done]
+            .into_iter()
+            .map(|outcome| entry(10, outcome))
+            .collect();
+        let stats = &compute(&[10], &entries)[0];
+        assert_eq!(stats.best_streak, 3);
+        assert_eq!(stats.current_streak, 1);
+    }
+
+    #[test]
+    fn completion_rate_ignores_covering() {
+        use DutyOutcome::*;
+        let entries = vec![
+            entry(10, Done),
+            entry(10, Done),
+            entry(10, Done),
+            entry(10, Cant),
+            entry(10, Covered),
+        ];
+        let stats = &compute(&[10], &entries)[0];
+        assert_eq!(stats.completion_rate(), Some(0.75));
+    }
+
+    #[test]
+    fn flatmate_without_duties_has_no_completion_rate() {
+        let stats = &compute(&[10], &[])[0];
+        assert_eq!(stats.completion_rate(), None);
+    }
+
+    #[test]
+    fn formats_one_line_per_flatmate() {
+        use DutyOutcome::*;
+        let entries = vec![entry(10, Done), entry(10, Cant), entry(10, Covered)];
+        let stats = compute(&[10, 20], &entries);
+        let named = vec![
+            ("Alice".to_string(), stats[0].clone()),
+            ("Bob".to_string(), stats[1].clone()),
+        ];
+        assert_eq!(
+            format_stats(&named),
+            "Duty statistics:\n\
+             Alice: 1/2 done (50%), covered 1x, streak 0 (best 1)\n\
+             Bob: 0/0 done (-), covered 0x, streak 0 (best 0)\n"
+        );
+    }
+}
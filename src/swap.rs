@@ -0,0 +1,50 @@
+/// Answer of a flatmate to a swap offer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapAnswer {
+    Accept,
+    Decline,
+}
+
+const CALLBACK_PREFIX: &str = "swap:";
+
+/// Callback data for one of a swap offer's buttons, e.g. "swap:12:accept".
+pub fn callback_data(swap_id: i64, answer: SwapAnswer) -> String {
+    let answer = match answer {
+        SwapAnswer::Accept => "accept",
+        SwapAnswer::Decline => "decline",
+    };
+    format!("{}{}:{}", CALLBACK_PREFIX, swap_id, answer)
+}
+
+/// Inverse of [`callback_data`]. Returns `None` for data that does not belong
+/// to a swap offer.
+pub fn parse_callback_data(data: &str) -> Option<(i64, SwapAnswer)> {
+    let (swap_id, answer) = data.strip_prefix(CALLBACK_PREFIX)?.split_once(':')?;
+    let answer = match answer {
+        "accept" => SwapAnswer::Accept,
+        "decline" => SwapAnswer::Decline,
+        _ => return None,
+    };
+    Some((swap_id.parse().ok()?, answer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn callback_data_round_trips() {
+        for answer in [SwapAnswer::Accept, SwapAnswer::Decline] {
+            let data = callback_data(42, answer);
+            assert_eq!(parse_callback_data(&data), Some((42, answer)));
+        }
+    }
+
+    #[test]
+    fn rejects_foreign_or_malformed_data() {
+        assert_eq!(parse_callback_data("done"), None);
+        assert_eq!(parse_callback_data("chore:bathroom:done"), None);
+        assert_eq!(parse_callback_data("swap:abc:accept"), None);
+        assert_eq!(parse_callback_data("swap:12:maybe"), None);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::data_grabber::TrashType;
+use chrono::NaiveDate;
+
+/// A collection taken as moved lands at most this many days away; further
+/// away, the old one was cancelled and the new one is unrelated.
+pub const MAX_MOVE_DAYS: i64 = 7;
+
+/// A change a provider made to a collection it had announced before.
+#[derive(Debug, PartialEq)]
+pub enum ScheduleChange {
+    Moved {
+        trash: TrashType,
+        from: NaiveDate,
+        to: NaiveDate,
+    },
+    Cancelled {
+        trash: TrashType,
+        date: NaiveDate,
+    },
+}
+
+/// How the collections a provider reported before (`stored`) changed in what
+/// it reports now (`fetched`), both covering the same days up to `until`. A
+/// collection that disappeared moved to the nearest new one of the same type,
+/// if any. Without one, it was cancelled, unless it is so close to `until`
+/// that it may have moved past it. New collections on their own are not a
+/// change: they are usually just days the previous fetch did not cover.
+pub fn diff(
+    stored: &[(NaiveDate, TrashType)],
+    fetched: &[(NaiveDate, TrashType)],
+    until: NaiveDate,
+) -> Vec<ScheduleChange> {
+    let mut added: Vec<&(NaiveDate, TrashType)> = fetched
+        .iter()
+        .filter(|entry| !stored.contains(entry))
+        .collect();
+    let mut changes = Vec::new();
+    for &(date, trash) in stored.iter().filter(|entry| !fetched.contains(entry)) {
+        let distance = |to: NaiveDate| (to - date).num_days().abs();
+        let moved_to = added
+            .iter()
+            .enumerate()
+            .filter(|(_, (to, t))| *t == trash && distance(*to) <= MAX_MOVE_DAYS)
+            .min_by_key(|(_, (to, _))| distance(*to))
+            .map(|(index, _)| index);
+        match moved_to {
+            Some(index) => {
+                let (to, _) = added.remove(index);
+                changes.push(ScheduleChange::Moved {
+                    trash,
+                    from: date,
+                    to: *to,
+                });
+            }
+            None if (until - date).num_days() >= MAX_MOVE_DAYS => {
+                changes.push(ScheduleChange::Cancelled { trash, date })
+            }
+            None => {}
+        }
+    }
+    changes
+}
+
+/// A day as in "Tue 14.10".
+fn format_day(date: NaiveDate) -> String {
+    date.format("%a %d.%m").to_string()
+}
+
+/// The message telling the group about `changes`, or `None` without any.
+pub fn changes_message(changes: &[ScheduleChange]) -> Option<String> {
+    if changes.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = changes
+        .iter()
+        .map(|change| match change {
+            ScheduleChange::Moved { trash, from, to } => format!(
+                "⚠️ {} moved from {} to {}",
+                trash,
+                format_day(*from),
+                format_day(*to)
+            ),
+            ScheduleChange::Cancelled { trash, date } => {
+                format!("⚠️ {} on {} was cancelled", trash, format_day(*date))
+            }
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn unchanged_and_new_collections_are_no_change() {
+        let stored = [(date(2026, 10, 13), TrashType::Normal)];
+        let fetched = [
+            (date(2026, 10, 13), TrashType::Normal),
+            (date(2026, 10, 15), TrashType::Bio),
+        ];
+        assert_eq!(diff(&stored, &fetched, date(2026, 10, 31)), vec![]);
+    }
+
+    #[test]
+    fn a_collection_moves_to_the_nearest_new_day_of_its_type() {
+        let stored = [
+            (date(2026, 10, 14), TrashType::Paper),
+            (date(2026, 10, 14), TrashType::Normal),
+        ];
+        let fetched = [
+            (date(2026, 10, 14), TrashType::Normal),
+            (date(2026, 10, 15), TrashType::Paper),
+            (date(2026, 10, 19), TrashType::Paper),
+        ];
+        assert_eq!(
+            diff(&stored, &fetched, date(2026, 10, 31)),
+            vec![ScheduleChange::Moved {
+                trash: TrashType::Paper,
+                from: date(2026, 10, 14),
+                to: date(2026, 10, 15),
+            }]
+        );
+    }
+
+    #[test]
+    fn a_collection_without_a_replacement_nearby_is_cancelled() {
+        let stored = [(date(2026, 10, 14), TrashType::Cardboard)];
+        let fetched = [
+            (date(2026, 10, 22), TrashType::Cardboard),
+            (date(2026, 10, 15), TrashType::Paper),
+        ];
+        assert_eq!(
+            diff(&stored, &fetched, date(2026, 10, 31)),
+            vec![ScheduleChange::Cancelled {
+                trash: TrashType::Cardboard,
+                date: date(2026, 10, 14),
+            }]
+        );
+    }
+
+    #[test]
+    fn a_collection_near_the_end_may_have_moved_past_it() {
+        let stored = [
+            (date(2026, 10, 24), TrashType::Bio),
+            (date(2026, 10, 31), TrashType::Paper),
+        ];
+        let fetched = [(date(2026, 10, 30), TrashType::Normal)];
+        assert_eq!(
+            diff(&stored, &fetched, date(2026, 10, 31)),
+            vec![ScheduleChange::Cancelled {
+                trash: TrashType::Bio,
+                date: date(2026, 10, 24),
+            }]
+        );
+    }
+
+    #[test]
+    fn changes_are_announced_one_per_line() {
+        let message = changes_message(&[
+            ScheduleChange::Moved {
+                trash: TrashType::Paper,
+                from: date(2026, 10, 13),
+                to: date(2026, 10, 14),
+            },
+            ScheduleChange::Cancelled {
+                trash: TrashType::Bio,
+                date: date(2026, 10, 16),
+            },
+        ]);
+        assert_eq!(
+            message.as_deref(),
+            Some("⚠️ Paper moved from Tue 13.10 to Wed 14.10\n⚠️ Bio on Fri 16.10 was cancelled")
+        );
+        assert_eq!(changes_message(&[]), None);
+    }
+}
@@ -1,4 +1,6 @@
-use super::data_grabber::{TrashType, TrashesSchedule};
+use super::chores::{self, Chore, ChoreAction};
+use super::data_grabber::{ProviderError, TrashType, TrashesSchedule};
+use super::swap::{self, SwapAnswer};
 use chrono::Datelike;
 use teloxide::prelude::*;
 use teloxide::{
@@ -6,10 +8,37 @@ use teloxide::{
     types::{InlineKeyboardButton, InlineKeyboardMarkup},
 };
 
-async fn send(bot: &Bot, channel: i64, message: &str) {
-    match bot.send_message(ChatId(channel), message).await {
-        Ok(_) => tracing::info!("Scheduled message sent successfully"),
-        Err(e) => tracing::error!("Error sending scheduled message: {}", e),
+/// Send `message` to `channel`. Returns whether it was sent.
+async fn send(bot: &Bot, channel: i64, message: &str) -> bool {
+    report_sent(bot.send_message(ChatId(channel), message).await)
+}
+
+/// `send` with buttons below the message.
+async fn send_with_buttons(
+    bot: &Bot,
+    channel: i64,
+    message: &str,
+    keyboard: InlineKeyboardMarkup,
+) -> bool {
+    report_sent(
+        bot.send_message(ChatId(channel), message)
+            .reply_markup(keyboard)
+            .await,
+    )
+}
+
+fn report_sent(res: Result<Message, teloxide::RequestError>) -> bool {
+    match res {
+        Ok(_) => {
+            tracing::info!("Scheduled message sent successfully");
+            super::health::telegram_succeeded();
+            true
+        }
+        Err(e) => {
+            tracing::error!("Error sending scheduled message: {}", e);
+            super::metrics::telegram_send_failed();
+            false
+        }
     }
 }
 
@@ -25,15 +54,29 @@ pub async fn notify_group(bot: &Bot, config: &super::Config, message: &str) {
     send(bot, config.global_channel_id, message).await;
 }
 
-async fn weekly_update(bot: &Bot, config: &super::Config, schedule: &TrashesSchedule) {
-    let global_chat_update_txt =
-        format!("The new food master is {}.", schedule.tomorrow_master_name);
-    send(bot, config.global_channel_id, &global_chat_update_txt).await;
+/// "Away this week: Alice (until 05.01.), Bob.", or `None` if everyone is
+/// home.
+fn format_away(away: &[(String, Option<chrono::NaiveDate>)]) -> Option<String> {
+    if away.is_empty() {
+        return None;
+    }
+    let names = away
+        .iter()
+        .map(|(name, until)| match until {
+            Some(until) => format!("{} (until {})", name, until.format("%d.%m.")),
+            None => name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(format!("Away this week: {}.", names))
+}
 
+/// The new food master's schedule for the 7 days after `schedule.today`.
+pub fn weekly_update_txt(schedule: &TrashesSchedule) -> String {
     let mut master_update_txt = String::new();
     for i in 1..8 {
         // iterate over the next 7 days
-        let date = chrono::Local::now().naive_local().date() + chrono::Duration::days(i);
+        let date = schedule.today + chrono::Duration::days(i);
         let trashes = schedule.dates.get(&date);
         match trashes {
             None => continue,
@@ -44,6 +87,25 @@ async fn weekly_update(bot: &Bot, config: &super::Config, schedule: &TrashesSche
             }
         }
     }
+    format!(
+        "Hello {}!\n\
+        You are the new food master.\n\
+        This week you need to put these trashes in front of the house before 7am.\n\
+        Here is the schedule:\n\
+        {}",
+        schedule.tomorrow_master_name, master_update_txt
+    )
+}
+
+pub async fn weekly_update(bot: &Bot, config: &super::Config, schedule: &TrashesSchedule) {
+    let mut global_chat_update_txt =
+        format!("The new food master is {}.", schedule.tomorrow_master_name);
+    if let Some(away) = format_away(&schedule.away) {
+        global_chat_update_txt.push('\n');
+        global_chat_update_txt.push_str(&away);
+    }
+    send(bot, config.global_channel_id, &global_chat_update_txt).await;
+
     let keyboard = InlineKeyboardMarkup::new(vec![
         // First row with two buttons
         vec![InlineKeyboardButton::callback(
@@ -56,47 +118,108 @@ async fn weekly_update(bot: &Bot, config: &super::Config, schedule: &TrashesSche
         )],
     ]);
 
-    let master_update_txt = format!(
-        "Hello {}!\n\
-        You are the new food master.\n\
-        This week you need to put these trashes in front of the house before 7am.\n\
-        Here is the schedule:\n\
-        {}",
-        schedule.tomorrow_master_name, master_update_txt
-    );
-    send(bot, schedule.tomorrow_master_id, &master_update_txt).await;
+    send(
+        bot,
+        schedule.tomorrow_master_id,
+        &weekly_update_txt(schedule),
+    )
+    .await;
 
     let request_bags_txt =
-        format!("Can you look if we still have enough We-Recycle bags? Do we need to order new?");
-    match bot
-        .send_message(ChatId(schedule.tomorrow_master_id), &request_bags_txt)
-        .reply_markup(keyboard)
-        .await
-    {
-        Ok(_) => tracing::info!("Scheduled message sent successfully"),
-        Err(e) => tracing::error!("Error sending scheduled message: {}", e),
+        "Can you look if we still have enough We-Recycle bags? Do we need to order new?";
+    send_with_buttons(bot, schedule.tomorrow_master_id, request_bags_txt, keyboard).await;
+}
+
+/// The names of the `failed` providers, as in "adliswil and we_recycle", and
+/// the pronoun for them.
+fn failed_providers(failed: &[ProviderError]) -> (String, &'static str) {
+    let names: Vec<&str> = failed.iter().map(|failure| failure.name.as_str()).collect();
+    match names.split_last() {
+        Some((last, [])) => (last.to_string(), "its"),
+        Some((last, rest)) => (format!("{} and {}", rest.join(", "), last), "their"),
+        None => (String::new(), "their"),
     }
 }
 
-async fn daily_update(
+/// Message telling the group which providers could not be reached, or
+/// `None` if all of them could.
+pub fn missing_providers_txt(failed: &[ProviderError]) -> Option<String> {
+    if failed.is_empty() {
+        return None;
+    }
+    let (names, pronoun) = failed_providers(failed);
+    let errors: Vec<String> = failed
+        .iter()
+        .map(|failure| failure.error.to_string())
+        .collect();
+    Some(format!(
+        "⚠️ I couldn't reach {} ({}), so {} collections are missing from the \
+         reminders for now.",
+        names,
+        errors.join("; "),
+        pronoun
+    ))
+}
+
+/// Reminder to put tomorrow's trashes out, or `None` without any trash
+/// tomorrow.
+pub fn daily_update_txt(config: &super::Config, schedule: &TrashesSchedule) -> Option<String> {
+    let trashes = schedule.tomorrow_trashes()?;
+    let mut daily_update_txt = format!(
+        "Hello {} !\nDon't forget to put the {} trashes out before tomorrow morning! \n",
+        schedule.tomorrow_master_name,
+        format_trashes(trashes)
+    );
+    if let Some(hour) = config.escalation.first_group_hour() {
+        daily_update_txt.push_str(&format!(
+            "If you don't answer this message before {}:00,\n\
+            a reminder will be sent to all the flatmates.\n",
+            hour
+        ));
+    }
+    if let Some(cached) = schedule.cached {
+        daily_update_txt.push_str(&format!(
+            "(The waste calendar can't be reached right now, so this is from the \
+            schedule I fetched on {}.)\n",
+            cached.format("%a %d.%m. %H:%M")
+        ));
+    }
+    if !schedule.failed.is_empty() {
+        let (names, pronoun) = failed_providers(&schedule.failed);
+        daily_update_txt.push_str(&format!(
+            "(I couldn't reach {} just now, so {} collections may be missing.)\n",
+            names, pronoun
+        ));
+    }
+    Some(daily_update_txt)
+}
+
+/// Message for a food master without any known trash tomorrow. If some
+/// providers could not be reached, that is not the same as no trash.
+pub fn no_trash_txt(schedule: &TrashesSchedule) -> String {
+    if schedule.failed.is_empty() {
+        return format!(
+            "Hi {}\nNo trashes tomorrow!\nHave a nice evening.",
+            schedule.tomorrow_master_name
+        );
+    }
+    let (names, pronoun) = failed_providers(&schedule.failed);
+    format!(
+        "Hi {}\nI know of no trashes tomorrow, but the schedule is incomplete: \
+        I couldn't reach {} just now, so {} collections may be missing.\n\
+        Please check whether anything needs to go out tomorrow.",
+        schedule.tomorrow_master_name, names, pronoun
+    )
+}
+
+pub async fn daily_update(
     bot: &Bot,
-    _config: &super::Config,
+    config: &super::Config,
     schedule: &TrashesSchedule,
     shared_task: std::sync::Arc<std::sync::Mutex<super::SharedTaskState>>,
 ) {
-    let tomorrow = chrono::Local::now().naive_local().date() + chrono::Duration::days(1);
-    let trashes = schedule.dates.get(&tomorrow);
-    match trashes {
-        Some(trashes) => {
-            let daily_update_txt = format!(
-                "Hello {} !\nDon't forget to put the {} trashes out before tomorrow morning! \n\
-                If you don't answer this message before 9pm,\n\
-                a reminder will be sent to all the flatmates.\n\
-                ",
-                schedule.tomorrow_master_name,
-                format_trashes(trashes)
-            );
-
+    match daily_update_txt(config, schedule) {
+        Some(daily_update_txt) => {
             let keyboard = InlineKeyboardMarkup::new(vec![
                 // First row with two buttons
                 vec![InlineKeyboardButton::callback("Done", "done")],
@@ -107,43 +230,374 @@ async fn daily_update(
                 .send_message(ChatId(schedule.tomorrow_master_id), &daily_update_txt)
                 .reply_markup(keyboard)
                 .await;
-            match res {
-                Ok(_) => tracing::info!("Scheduled message sent successfully"),
-                Err(e) => tracing::error!("Error sending scheduled message: {}", e),
-            }
-            shared_task.lock().unwrap().state = super::TaskState::Pending;
+            let message_id = match res {
+                Ok(message) => {
+                    tracing::info!("Scheduled message sent successfully");
+                    super::health::telegram_succeeded();
+                    super::metrics::reminder_sent("reminder");
+                    Some(message.id.0)
+                }
+                Err(e) => {
+                    tracing::error!("Error sending scheduled message: {}", e);
+                    super::metrics::telegram_send_failed();
+                    None
+                }
+            };
+            let mut task = shared_task.lock().unwrap();
+            task.state = super::TaskState::Pending;
+            task.message_id = message_id;
+            task.sent_on = Some(schedule.today);
+            task.persist();
         }
         None => {
-            send(
-                bot,
-                schedule.tomorrow_master_id,
-                &format!(
-                    "Hi {}\nNo trashes tomorrow!\nHave a nice evening.",
-                    schedule.tomorrow_master_name
-                ),
-            )
-            .await;
-            shared_task.lock().unwrap().state = super::TaskState::None;
+            send(bot, schedule.tomorrow_master_id, &no_trash_txt(schedule)).await;
+            let mut task = shared_task.lock().unwrap();
+            task.state = super::TaskState::None;
+            task.message_id = None;
+            task.sent_on = None;
+            task.persist();
         }
     }
 }
 
-pub async fn send_update(
+/// Ask the group to step in for the food master, or `None` without any
+/// trash tomorrow.
+pub fn shame_update_txt(schedule: &TrashesSchedule) -> Option<String> {
+    let trashes = schedule.tomorrow_trashes()?;
+    Some(format!(
+        "Unfortunately {} is not able to fulfill his role as Food master today...Could someone put the {} trashes out before tomorrow morning? Press Done once they're out. Have a nice evening!",
+        schedule.tomorrow_master_name,
+        format_trashes(trashes)
+    ))
+}
+
+const COVER_CALLBACK_PREFIX: &str = "covered:";
+
+/// Callback data for the Done button of a request to step in for the food
+/// master, e.g. "covered:2026-06-08:42" for the reminder sent on 2026-06-08 to
+/// flatmate 42.
+pub fn cover_callback_data(today: chrono::NaiveDate, master_id: i64) -> String {
+    format!("{}{}:{}", COVER_CALLBACK_PREFIX, today, master_id)
+}
+
+/// Inverse of [`cover_callback_data`]. Returns `None` for data that does not
+/// belong to a request for help.
+pub fn parse_cover_callback_data(data: &str) -> Option<(chrono::NaiveDate, i64)> {
+    let (today, master_id) = data.strip_prefix(COVER_CALLBACK_PREFIX)?.split_once(':')?;
+    Some((today.parse().ok()?, master_id.parse().ok()?))
+}
+
+/// A Done button for whoever steps in for the food master.
+fn cover_keyboard(schedule: &TrashesSchedule) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
+        "Done",
+        cover_callback_data(schedule.today, schedule.tomorrow_master_id),
+    )]])
+}
+
+pub async fn shame_update(bot: &Bot, config: &super::Config, schedule: &TrashesSchedule) {
+    if let Some(shame_update_txt) = shame_update_txt(schedule)
+        && send_with_buttons(
+            bot,
+            config.global_channel_id,
+            &shame_update_txt,
+            cover_keyboard(schedule),
+        )
+        .await
+    {
+        super::metrics::reminder_sent("group");
+    }
+}
+
+/// Remind the food master privately that tomorrow's trashes are still
+/// waiting for them.
+pub async fn nudge_update(bot: &Bot, schedule: &TrashesSchedule) {
+    if let Some(trashes) = schedule.tomorrow_trashes() {
+        let nudge_txt = format!(
+            "Hi {}, friendly reminder: the {} trashes still need to go out before tomorrow \
+            morning. Press Done on my earlier message once they're out.",
+            schedule.tomorrow_master_name,
+            format_trashes(trashes)
+        );
+        if send(bot, schedule.tomorrow_master_id, &nudge_txt).await {
+            super::metrics::reminder_sent("nudge");
+        }
+    }
+}
+
+/// Ask the next flatmate in the rotation directly to step in for the food
+/// master.
+pub async fn next_in_rotation_update(bot: &Bot, schedule: &TrashesSchedule, next_id: i64) {
+    if let Some(trashes) = schedule.tomorrow_trashes() {
+        let next_txt = format!(
+            "Hello!\n{} hasn't confirmed putting the {} trashes out yet. \
+            As next in the rotation, could you make sure they're out before tomorrow morning? \
+            Press Done once they are.",
+            schedule.tomorrow_master_name,
+            format_trashes(trashes)
+        );
+        if send_with_buttons(bot, next_id, &next_txt, cover_keyboard(schedule)).await {
+            super::metrics::reminder_sent("next_in_rotation");
+        }
+    }
+}
+
+/// Ask the flatmate on duty to take care of a chore. Returns the id of the
+/// reminder so its buttons can be cleared later.
+pub async fn chore_reminder(bot: &Bot, chore: &Chore, assignee: i64) -> Option<i32> {
+    let keyboard = InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback(
+            "Done",
+            chores::callback_data(&chore.id, ChoreAction::Done),
+        )],
+        vec![InlineKeyboardButton::callback(
+            "I can't",
+            chores::callback_data(&chore.id, ChoreAction::Cant),
+        )],
+    ]);
+    let res = bot
+        .send_message(
+            ChatId(assignee),
+            format!(
+                "Hello!\nIt's your turn for the {} chore this week.\n\
+                Let me know once it's done.",
+                chore.id
+            ),
+        )
+        .reply_markup(keyboard)
+        .await;
+    match res {
+        Ok(message) => {
+            tracing::info!("Chore reminder for {} sent successfully", chore.id);
+            super::health::telegram_succeeded();
+            Some(message.id.0)
+        }
+        Err(e) => {
+            tracing::error!("Error sending chore reminder for {}: {}", chore.id, e);
+            super::metrics::telegram_send_failed();
+            None
+        }
+    }
+}
+
+/// Ask the whole group to step in for a chore the flatmate on duty could not
+/// do (`Failed`) or never confirmed (`Pending`).
+pub async fn chore_help_request(
     bot: &Bot,
     config: &super::Config,
-    schedule: &TrashesSchedule,
-    weekly: bool,
-    shared_task: std::sync::Arc<std::sync::Mutex<super::SharedTaskState>>,
+    chore: &Chore,
+    state: &super::TaskState,
 ) {
-    if weekly {
-        weekly_update(bot, config, schedule).await;
+    let txt = if *state == super::TaskState::Failed {
+        format!(
+            "The {} chore can't be done by this week's flatmate... Could someone take over?",
+            chore.id
+        )
+    } else {
+        format!(
+            "Nobody confirmed the {} chore this week... Could someone take care of it?",
+            chore.id
+        )
+    };
+    send(bot, config.global_channel_id, &txt).await;
+}
+
+/// Offer a flatmate to take over this week's trash duty from `requester_name`.
+/// Returns the id of the offer so it can be withdrawn once someone accepts.
+pub async fn swap_offer(
+    bot: &Bot,
+    chat_id: i64,
+    swap_id: i64,
+    requester_name: &str,
+) -> Option<i32> {
+    let keyboard = InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback(
+            "I'll take it",
+            swap::callback_data(swap_id, SwapAnswer::Accept),
+        )],
+        vec![InlineKeyboardButton::callback(
+            "Not this time",
+            swap::callback_data(swap_id, SwapAnswer::Decline),
+        )],
+    ]);
+    let res = bot
+        .send_message(
+            ChatId(chat_id),
+            format!(
+                "Hello!\n{} can't be the food master this week.\n\
+                Would you take over? They will take one of your weeks in exchange.",
+                requester_name
+            ),
+        )
+        .reply_markup(keyboard)
+        .await;
+    match res {
+        Ok(message) => {
+            super::health::telegram_succeeded();
+            Some(message.id.0)
+        }
+        Err(e) => {
+            tracing::error!("Error sending swap offer: {}", e);
+            super::metrics::telegram_send_failed();
+            None
+        }
     }
-    daily_update(bot, config, schedule, shared_task).await;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
+
+    fn date(y: i32, m: u32, d: u32) -> chrono::NaiveDate {
+        chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// Normal trash on Monday 2026-06-08, Bio on Wednesday, seen from `today`.
+    fn schedule(today: chrono::NaiveDate) -> TrashesSchedule {
+        TrashesSchedule {
+            today,
+            dates: [
+                (date(2026, 6, 8), vec![TrashType::Normal]),
+                (date(2026, 6, 10), vec![TrashType::Bio]),
+            ]
+            .into_iter()
+            .collect(),
+            by_provider: Vec::new(),
+            tomorrow_master_name: "Alice".to_string(),
+            tomorrow_master_id: 10,
+            away: Vec::new(),
+            cached: None,
+            failed: Vec::new(),
+        }
+    }
+
+    fn failure(name: &str) -> ProviderError {
+        ProviderError {
+            name: name.to_string(),
+            error: crate::error::GstaldergeistError::Other("no answer within 30s".to_string()),
+        }
+    }
+
+    fn config(escalation: crate::escalation::EscalationPolicy) -> super::super::Config {
+        crate::config::Config {
+            escalation,
+            ..crate::config::test_config(vec![10, 20])
+        }
+    }
+
+    #[test]
+    fn weekly_schedule_lists_the_days_after_today() {
+        assert_eq!(
+            weekly_update_txt(&schedule(date(2026, 6, 7))),
+            "Hello Alice!\n\
+             You are the new food master.\n\
+             This week you need to put these trashes in front of the house before 7am.\n\
+             Here is the schedule:\n\
+             Normal on Mon,\n\
+             Bio on Wed,\n"
+        );
+    }
+
+    #[test]
+    fn daily_reminder_announces_the_first_group_step() {
+        let txt = daily_update_txt(&config(Default::default()), &schedule(date(2026, 6, 7)));
+        assert!(txt.unwrap().contains("before 19:00"));
+        let quiet = crate::escalation::parse_escalation("18:nudge", 16).unwrap();
+        let txt = daily_update_txt(&config(quiet), &schedule(date(2026, 6, 7))).unwrap();
+        assert!(!txt.contains("all the flatmates"));
+    }
+
+    #[test]
+    fn a_reminder_from_the_stored_schedule_says_so() {
+        let config = config(Default::default());
+        let fresh = daily_update_txt(&config, &schedule(date(2026, 6, 7))).unwrap();
+        assert!(!fresh.contains("can't be reached"));
+        let fetched = chrono_tz::Europe::Zurich
+            .with_ymd_and_hms(2026, 6, 5, 18, 30, 0)
+            .unwrap();
+        let cached = TrashesSchedule {
+            cached: Some(fetched),
+            ..schedule(date(2026, 6, 7))
+        };
+        let txt = daily_update_txt(&config, &cached).unwrap();
+        assert!(
+            txt.contains("schedule I fetched on Fri 05.06. 18:30"),
+            "{}",
+            txt
+        );
+    }
+
+    #[test]
+    fn a_reminder_without_some_providers_names_them() {
+        let config = config(Default::default());
+        let partial = TrashesSchedule {
+            failed: vec![failure("we_recycle")],
+            ..schedule(date(2026, 6, 7))
+        };
+        let txt = daily_update_txt(&config, &partial).unwrap();
+        assert!(txt.contains("Normal"), "{}", txt);
+        assert!(
+            txt.contains(
+                "(I couldn't reach we_recycle just now, so its collections may be missing.)"
+            ),
+            "{}",
+            txt
+        );
+    }
+
+    #[test]
+    fn no_trash_is_only_promised_with_every_provider() {
+        // 2026-06-08 has Normal trash, so nothing is known for 2026-06-09.
+        let complete = schedule(date(2026, 6, 8));
+        assert_eq!(
+            no_trash_txt(&complete),
+            "Hi Alice\nNo trashes tomorrow!\nHave a nice evening."
+        );
+        let partial = TrashesSchedule {
+            failed: vec![failure("we_recycle")],
+            ..schedule(date(2026, 6, 8))
+        };
+        let txt = no_trash_txt(&partial);
+        assert!(!txt.contains("No trashes tomorrow"), "{}", txt);
+        assert!(
+            txt.contains("the schedule is incomplete: I couldn't reach we_recycle"),
+            "{}",
+            txt
+        );
+    }
+
+    #[test]
+    fn the_group_is_told_which_providers_are_missing() {
+        assert_eq!(missing_providers_txt(&[]), None);
+        assert_eq!(
+            missing_providers_txt(&[failure("we_recycle")]).as_deref(),
+            Some(
+                "⚠️ I couldn't reach we_recycle (Other error: no answer within 30s), so its \
+                 collections are missing from the reminders for now."
+            )
+        );
+        let failed = [failure("adliswil"), failure("ics"), failure("we_recycle")];
+        let txt = missing_providers_txt(&failed).unwrap();
+        assert!(
+            txt.starts_with("⚠️ I couldn't reach adliswil, ics and we_recycle ("),
+            "{}",
+            txt
+        );
+        assert!(txt.contains("so their collections"), "{}", txt);
+    }
+
+    #[test]
+    fn nothing_to_remind_or_shame_without_trash_tomorrow() {
+        let config = config(Default::default());
+        assert_eq!(daily_update_txt(&config, &schedule(date(2026, 6, 8))), None);
+        assert_eq!(shame_update_txt(&schedule(date(2026, 6, 8))), None);
+        assert!(
+            shame_update_txt(&schedule(date(2026, 6, 9)))
+                .unwrap()
+                .contains("Bio")
+        );
+    }
 
     #[test]
     fn formats_a_single_trash() {
@@ -162,18 +616,34 @@ mod tests {
     fn empty_slice_yields_empty_string() {
         assert_eq!(format_trashes(&[]), "");
     }
-}
 
-pub async fn shame_update(bot: &Bot, config: &super::Config, schedule: &TrashesSchedule) {
-    let tomorrow = chrono::Local::now().naive_local().date() + chrono::Duration::days(1);
-    let trashes = schedule.dates.get(&tomorrow);
+    #[test]
+    fn nobody_away_yields_no_line() {
+        assert_eq!(format_away(&[]), None);
+    }
 
-    if let Some(trashes) = trashes {
-        let shame_update_txt = format!(
-            "Unfortunately {} is not able to fulfill his role as Food master today...Could someone put the {} trashes out before tomorrow morning? Have a nice evening!",
-            schedule.tomorrow_master_name,
-            format_trashes(trashes)
+    #[test]
+    fn lists_away_flatmates_with_their_return() {
+        let away = vec![
+            (
+                "Alice".to_string(),
+                chrono::NaiveDate::from_ymd_opt(2027, 1, 5),
+            ),
+            ("Bob".to_string(), None),
+        ];
+        assert_eq!(
+            format_away(&away).unwrap(),
+            "Away this week: Alice (until 05.01.), Bob."
         );
-        send(bot, config.global_channel_id, &shame_update_txt).await;
+    }
+
+    #[test]
+    fn cover_callback_data_round_trips() {
+        let today = chrono::NaiveDate::from_ymd_opt(2026, 6, 8).unwrap();
+        let data = cover_callback_data(today, -42);
+        assert_eq!(data, "covered:2026-06-08:-42");
+        assert_eq!(parse_cover_callback_data(&data), Some((today, -42)));
+        assert_eq!(parse_cover_callback_data("done"), None);
+        assert_eq!(parse_cover_callback_data("covered:yesterday:42"), None);
     }
 }